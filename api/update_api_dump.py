@@ -0,0 +1,38 @@
+#!/usr/bin/env python3
+"""Replaces API-Dump.json with the full Roblox API dump, unmodified.
+
+The dump is the Full-API-Dump.json published by the Roblox client tracker. Without arguments it is downloaded,
+otherwise it is read from the given file. It is only checked to be a dump before being written as it is: the enums
+and the reflection data are generated from every class, member and enum in it, so don't edit it by hand.
+
+    python3 api/update_api_dump.py [Full-API-Dump.json]
+"""
+
+import json
+import os
+import sys
+import urllib.request
+
+URL = "https://raw.githubusercontent.com/MaximumADHD/Roblox-Client-Tracker/roblox/Full-API-Dump.json"
+OUTPUT = os.path.join(os.path.dirname(os.path.abspath(__file__)), "API-Dump.json")
+
+
+def main():
+    if len(sys.argv) > 2:
+        raise SystemExit("usage: update_api_dump.py [Full-API-Dump.json]")
+    if len(sys.argv) == 2:
+        with open(sys.argv[1], "rb") as f:
+            data = f.read()
+    else:
+        with urllib.request.urlopen(URL) as response:
+            data = response.read()
+    dump = json.loads(data)
+    if not isinstance(dump.get("Classes"), list) or not isinstance(dump.get("Enums"), list):
+        raise SystemExit("not an API dump: Classes or Enums is missing")
+    with open(OUTPUT, "wb") as f:
+        f.write(data)
+    print("{} classes, {} enums".format(len(dump["Classes"]), len(dump["Enums"])))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use serde::Deserialize;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token, Ident, LitStr, Result, Token,
+};
+
+use crate::parse::AttrArguments;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ApiDump {
+    pub classes: Vec<ApiClass>,
+    pub enums: Vec<ApiEnum>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ApiClass {
+    pub name: String,
+    pub superclass: String,
+    #[serde(default, deserialize_with = "string_tags")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub members: Vec<ApiMember>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "MemberType")]
+pub(crate) enum ApiMember {
+    Property {
+        #[serde(rename = "Name")]
+        name: String,
+        #[serde(rename = "ValueType")]
+        value_type: ApiValueType,
+        #[serde(rename = "Security")]
+        security: ApiPropertySecurity,
+        #[serde(rename = "Tags", default, deserialize_with = "string_tags")]
+        tags: Vec<String>,
+        #[serde(rename = "Default")]
+        default: Option<String>,
+    },
+    Function {
+        #[serde(rename = "Name")]
+        name: String,
+        #[serde(rename = "Security")]
+        security: String,
+        #[serde(rename = "Tags", default, deserialize_with = "string_tags")]
+        tags: Vec<String>,
+    },
+    Event {
+        #[serde(rename = "Name")]
+        name: String,
+        #[serde(rename = "Security")]
+        security: String,
+        #[serde(rename = "Tags", default, deserialize_with = "string_tags")]
+        tags: Vec<String>,
+    },
+    Callback {
+        #[serde(rename = "Name")]
+        name: String,
+        #[serde(rename = "Security")]
+        security: String,
+        #[serde(rename = "Tags", default, deserialize_with = "string_tags")]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ApiValueType {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ApiPropertySecurity {
+    pub read: String,
+    pub write: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ApiEnum {
+    pub name: String,
+    pub items: Vec<ApiEnumItem>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ApiEnumItem {
+    pub name: String,
+    pub value: i32,
+}
+
+/// Tags are mostly plain names, but the full dump also has tags carrying data, such as
+/// `{"PreferredDescriptorName": "Name"}`. Those aren't used, so only the names are kept.
+fn string_tags<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<String>, D::Error> {
+    let tags: Vec<serde_json::Value> = Deserialize::deserialize(deserializer)?;
+    Ok(tags
+        .into_iter()
+        .filter_map(|x| match x {
+            serde_json::Value::String(x) => Some(x),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reads an API dump, relative to the manifest directory of the crate being compiled.
+pub(crate) fn load_api_dump(path: &LitStr) -> Result<ApiDump> {
+    let mut full_path = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    full_path.push(path.value());
+    let contents = std::fs::read_to_string(&full_path).map_err(|e| {
+        syn::Error::new(
+            path.span(),
+            format!("unable to read {}: {}", full_path.display(), e),
+        )
+    })?;
+    serde_json::from_str(&contents)
+        .map_err(|e| syn::Error::new(path.span(), format!("invalid API dump: {}", e)))
+}
+
+/// Makes the including crate rebuild when the dump changes.
+pub(crate) fn track_api_dump(path: &LitStr) -> TokenStream {
+    let path = format!("/{}", path.value());
+    quote! {
+        const _: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), #path));
+    }
+}
+
+pub(crate) struct EnumOverride {
+    pub name: Ident,
+    pub args: AttrArguments,
+}
+
+impl Parse for EnumOverride {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let args = if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            content.parse()?
+        } else {
+            AttrArguments {
+                args: Punctuated::new(),
+            }
+        };
+        Ok(EnumOverride { name, args })
+    }
+}
+
+/// `"path/to/API-Dump.json", [EnumName(default = X, default_name = Y), ...]`
+pub(crate) struct ApiDumpEnumsInput {
+    pub path: LitStr,
+    pub overrides: Vec<EnumOverride>,
+}
+
+impl Parse for ApiDumpEnumsInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path = input.parse()?;
+        let mut overrides = vec![];
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let content;
+            syn::bracketed!(content in input);
+            let list: Punctuated<EnumOverride, Token![,]> =
+                content.parse_terminated(EnumOverride::parse, Token![,])?;
+            overrides.extend(list);
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(ApiDumpEnumsInput { path, overrides })
+    }
+}
+
+fn security_context(name: &str) -> TokenStream {
+    match name {
+        "None" => quote! { crate::core::SecurityContext::NONE },
+        "PluginSecurity" => quote! { crate::core::SecurityContext::PLUGIN },
+        "LocalUserSecurity" => quote! { crate::core::SecurityContext::LOCAL_USER },
+        "RobloxScriptSecurity" => quote! { crate::core::SecurityContext::ROBLOX_SCRIPT },
+        _ => quote! { crate::core::SecurityContext::ROBLOX },
+    }
+}
+
+fn class_tags(tags: &[String]) -> TokenStream {
+    let tags: Vec<TokenStream> = tags
+        .iter()
+        .filter_map(|x| match x.as_str() {
+            "NotCreatable" => Some(quote! { crate::core::ClassTags::NOT_CREATABLE }),
+            "Service" => Some(quote! { crate::core::ClassTags::SERVICE }),
+            "NotReplicated" => Some(quote! { crate::core::ClassTags::NOT_REPLICATED }),
+            "Deprecated" => Some(quote! { crate::core::ClassTags::DEPRECATED }),
+            _ => None,
+        })
+        .collect();
+    if tags.is_empty() {
+        quote! {}
+    } else {
+        quote! { .tags(#(#tags)|*) }
+    }
+}
+
+fn has_tag(tags: &[String], tag: &str) -> bool {
+    tags.iter().any(|x| x == tag)
+}
+
+/// Generates a `Vec<ClassDescriptor>` expression holding the reflection data of every class in the dump.
+/// Members tagged `NotScriptable` are left out, as scripts can't see them.
+pub(crate) fn generate_classes(path: &LitStr, dump: &ApiDump) -> TokenStream {
+    let track = track_api_dump(path);
+    let classes: Vec<TokenStream> = dump
+        .classes
+        .iter()
+        .map(|class| {
+            let name = &class.name;
+            let superclass = if class.superclass == "<<<ROOT>>>" {
+                quote! { None }
+            } else {
+                let s = &class.superclass;
+                quote! { Some(#s) }
+            };
+            let tags = class_tags(&class.tags);
+            let members: Vec<TokenStream> = class
+                .members
+                .iter()
+                .filter_map(|member| {
+                    let (name, security, tags, kind) = match member {
+                        ApiMember::Property {
+                            name,
+                            value_type,
+                            security,
+                            tags,
+                            default,
+                        } => {
+                            if has_tag(tags, "NotScriptable") {
+                                return None;
+                            }
+                            let value_type = &value_type.name;
+                            let read = security_context(&security.read);
+                            let write = security_context(&security.write);
+                            let readonly = has_tag(tags, "ReadOnly").then(|| quote! { .readonly() });
+                            let not_replicated =
+                                has_tag(tags, "NotReplicated").then(|| quote! { .not_replicated() });
+                            let default = default.as_ref().map(|x| quote! { .default_value(#x) });
+                            return Some(quote! {
+                                .property(
+                                    crate::core::PropertyDescriptor::new(#name, #value_type)
+                                        .security(#read)
+                                        .write_security(#write)
+                                        #readonly
+                                        #not_replicated
+                                        #default
+                                )
+                            });
+                        }
+                        ApiMember::Function {
+                            name,
+                            security,
+                            tags,
+                        } => (name, security, tags, quote! { Function }),
+                        ApiMember::Event {
+                            name,
+                            security,
+                            tags,
+                        } => (name, security, tags, quote! { Event }),
+                        ApiMember::Callback {
+                            name,
+                            security,
+                            tags,
+                        } => (name, security, tags, quote! { Callback }),
+                    };
+                    if has_tag(tags, "NotScriptable") {
+                        return None;
+                    }
+                    let security = security_context(security);
+                    Some(quote! {
+                        .member(
+                            crate::core::MemberDescriptor::new(#name, crate::core::MemberKind::#kind)
+                                .security(#security)
+                        )
+                    })
+                })
+                .collect();
+            quote! {
+                crate::core::ClassDescriptor::new(#name, #superclass)
+                    #tags
+                    #(#members)*
+            }
+        })
+        .collect();
+    quote! {
+        {
+            #track
+            vec![#(#classes),*]
+        }
+    }
+}
+
+/// Generates every enum of the dump as an `ItemEnum` with explicit discriminants, paired with its overrides.
+pub(crate) fn enum_items(
+    input: &ApiDumpEnumsInput,
+    dump: &ApiDump,
+) -> Result<Vec<(syn::ItemEnum, AttrArguments)>> {
+    let mut overrides: HashMap<String, &EnumOverride> = input
+        .overrides
+        .iter()
+        .map(|x| (x.name.to_string(), x))
+        .collect();
+    let mut items = vec![];
+    // An enum without items can't be turned into a Rust enum the scripts could get values from.
+    for e in dump.enums.iter().filter(|x| !x.items.is_empty()) {
+        let name = Ident::new(&e.name, Span::call_site());
+        // Deprecated aliases share the value of the item they replace, Rust enums can't express that.
+        let mut seen = std::collections::HashSet::new();
+        let variants: Vec<TokenStream> = e
+            .items
+            .iter()
+            .filter(|x| seen.insert(x.value))
+            .map(|x| {
+                let variant = Ident::new(&x.name, Span::call_site());
+                let value = proc_macro2::Literal::i32_unsuffixed(x.value);
+                quote! { #variant = #value }
+            })
+            .collect();
+        let item: syn::ItemEnum = syn::parse2(quote! {
+            pub enum #name {
+                #(#variants),*
+            }
+        })?;
+        let args = match overrides.remove(&e.name) {
+            Some(x) => x.args.clone(),
+            None => AttrArguments {
+                args: Punctuated::new(),
+            },
+        };
+        items.push((item, args));
+    }
+    if let Some(x) = overrides.into_values().next() {
+        return Err(syn::Error::new(
+            x.name.span(),
+            format!("enum {} is not in the API dump", x.name),
+        ));
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiDump, ApiMember};
+
+    #[test]
+    fn reads_the_full_dump_format() {
+        // Trimmed from the format of Full-API-Dump.json: data carrying tags, fields nothing reads and values
+        // that don't fit in 16 bits.
+        let dump: ApiDump = serde_json::from_str(
+            r#"{
+                "Classes": [{
+                    "Members": [{
+                        "Category": "Data",
+                        "MemberType": "Property",
+                        "Name": "className",
+                        "Security": { "Read": "None", "Write": "None" },
+                        "Serialization": { "CanLoad": false, "CanSave": false },
+                        "Tags": ["Deprecated", "NotReplicated", "ReadOnly", { "PreferredDescriptorName": "ClassName" }],
+                        "ThreadSafety": "ReadSafe",
+                        "ValueType": { "Category": "Primitive", "Name": "string" }
+                    }, {
+                        "MemberType": "Function",
+                        "Name": "Destroy",
+                        "Parameters": [],
+                        "ReturnType": { "Category": "Primitive", "Name": "null" },
+                        "Security": "None",
+                        "ThreadSafety": "Unsafe"
+                    }],
+                    "MemoryCategory": "Instances",
+                    "Name": "Instance",
+                    "Superclass": "<<<ROOT>>>",
+                    "Tags": ["NotBrowsable", "NotCreatable"]
+                }],
+                "Enums": [{
+                    "Items": [
+                        { "Name": "Selection", "Value": 3984588 },
+                        { "LegacyNames": ["Old"], "Name": "New", "Value": 1 }
+                    ],
+                    "Name": "ReservedHighlightId"
+                }],
+                "Version": 1
+            }"#,
+        )
+        .unwrap();
+        let class = &dump.classes[0];
+        assert_eq!(class.tags, ["NotBrowsable", "NotCreatable"]);
+        match &class.members[0] {
+            ApiMember::Property { tags, .. } => {
+                assert_eq!(tags, &["Deprecated", "NotReplicated", "ReadOnly"])
+            }
+            _ => panic!("expected a property"),
+        }
+        assert_eq!(dump.enums[0].items[0].value, 3984588);
+    }
+}
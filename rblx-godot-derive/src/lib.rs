@@ -3,7 +3,7 @@
 use convert_case::Casing;
 use parse::{
     parse_lua_fn_attr, AttrArguments, Instance, InstanceConfig, InstanceConfigAttr,
-    LuaFunctionData, LuaPropertyData,
+    LuaFunctionData, LuaPropertyData, SecurityContext,
 };
 use proc_macro2::Span;
 use quote::{quote, ToTokens};
@@ -12,8 +12,9 @@ use syn::{
     Field, Ident, ItemEnum, ItemImpl, LitStr, Path, PathSegment, Token, TraitBound, TypeParamBound,
     TypeTraitObject,
 };
-use utils::camel_case_to_snake_case;
+use utils::{camel_case_to_snake_case, map_last_segment, value_type_name};
 
+mod api_dump;
 mod parse;
 mod utils;
 
@@ -23,6 +24,12 @@ macro_rules! error_cattr {
     };
 }
 
+/// Generates an instance class from a component declaration.
+///
+/// Fields marked `#[property(name = "...", ...)]` or `#[event(name = "...")]` are exposed to Lua,
+/// other fields are plain Rust state initialized with `Default::default()`. Lua methods are declared
+/// with `#[method(name = "...", func = fn ...)]` on the struct and implemented in a `#[methods]` block.
+/// `hierarchy` lists the ancestor classes (nearest first) whose components the class embeds.
 #[proc_macro_attribute]
 pub fn instance(
     item: proc_macro::TokenStream,
@@ -40,6 +47,7 @@ pub fn instance(
     let mut hierarchy: Option<Vec<syn::Path>> = None;
     let mut custom_new = None;
     let mut requires_init = None;
+    let mut superclass = None;
 
     for ca in item {
         match ca {
@@ -78,6 +86,13 @@ pub fn instance(
                     return error_cattr!(span, "`requires_init` specified twice").into();
                 }
             }
+            InstanceConfigAttr::Superclass(_eq, name, span) => {
+                if superclass.is_none() {
+                    superclass = Some(name.value())
+                } else {
+                    return error_cattr!(span, "`superclass` specified twice").into();
+                }
+            }
         }
     }
 
@@ -87,16 +102,22 @@ pub fn instance(
         hierarchy: hierarchy.unwrap_or(vec![]),
         custom_new: custom_new.unwrap_or(false),
         requires_init: requires_init.unwrap_or(false),
+        superclass,
     };
-    let ts1 = ts.clone(); // temporary
-    let mut inst: Instance = parse_macro_input!(ts1);
+    let mut inst: Instance = parse_macro_input!(ts);
+
+    if !inst.generics.params.is_empty() {
+        return Error::new(inst.generics.span(), "instances cannot be generic")
+            .into_compile_error()
+            .into();
+    }
 
     let mut lua_fns: Vec<LuaFunctionData> = vec![];
     let mut taken = vec![];
     let mut i = 0;
     while i < inst.attrs.len() {
         if inst.attrs[i].path().is_ident("method") {
-            taken.push(inst.attrs.swap_remove(i)); // Swap and remove the matching item
+            taken.push(inst.attrs.remove(i));
         } else {
             i += 1; // Only increment if no removal
         }
@@ -110,11 +131,15 @@ pub fn instance(
     }
 
     let mut rust_fields: Vec<Field> = vec![];
+    let mut stored_rust_fields: Vec<Ident> = vec![];
     let mut lua_fields: Vec<LuaPropertyData> = vec![];
 
     for i in inst.contents.named {
         match i {
-            parse::InstanceContent::RustField { rust_field } => rust_fields.push(rust_field),
+            parse::InstanceContent::RustField { rust_field } => {
+                stored_rust_fields.push(rust_field.ident.clone().unwrap());
+                rust_fields.push(rust_field);
+            }
             parse::InstanceContent::LuaField {
                 lua_field,
                 rust_field,
@@ -127,290 +152,519 @@ pub fn instance(
         }
     }
 
-    let (attr, vis, struct_token, gens, ident) = (
-        inst.attrs,
-        inst.vis,
-        inst.struct_token,
-        inst.generics,
-        inst.ident,
-    );
+    let (attr, vis, struct_token, ident) = (inst.attrs, inst.vis, inst.struct_token, inst.ident);
 
     let component_name = Ident::new(&(ident.to_string() + "Component"), ident.span());
     let trait_name = Ident::new(&("I".to_owned() + &ident.to_string()), ident.span());
 
     let snake = ident.to_string().to_case(convert_case::Case::Snake);
     let snake_id = Ident::new(&snake, ident.span());
-    let mut component_get_name = String::from("get_");
-    component_get_name.push_str(&snake);
-    component_get_name.push_str("_component");
+    let (cgn, cgmn) = (
+        Ident::new(&format!("get_{}_component", snake), ident.span()),
+        Ident::new(&format!("get_{}_component_mut", snake), ident.span()),
+    );
 
-    let mut component_get_mut_name = String::from("get_");
-    component_get_mut_name.push_str(&snake);
-    component_get_mut_name.push_str("_component_mut");
+    // Every class in the hierarchy (nearest first) contributes a component, a trait and a pair of getters.
+    let mut ancestor_names: Vec<String> = vec![];
+    let mut ancestor_traits: Vec<syn::Path> = vec![];
+    let mut ancestor_components: Vec<syn::Path> = vec![];
+    let mut ancestor_fields: Vec<Ident> = vec![];
+    let mut ancestor_getters: Vec<Ident> = vec![];
+    let mut ancestor_getters_mut: Vec<Ident> = vec![];
+
+    for path in ic.hierarchy.iter() {
+        let Some(last) = path.segments.last() else {
+            return error_cattr!(path.span(), "empty path in `hierarchy`").into();
+        };
+        let name = last.ident.to_string();
+        let snake = name.to_case(convert_case::Case::Snake);
+        ancestor_traits.push(map_last_segment(path, "I", ""));
+        ancestor_components.push(map_last_segment(path, "", "Component"));
+        ancestor_fields.push(Ident::new(&snake, last.ident.span()));
+        ancestor_getters.push(Ident::new(
+            &format!("get_{}_component", snake),
+            last.ident.span(),
+        ));
+        ancestor_getters_mut.push(Ident::new(
+            &format!("get_{}_component_mut", snake),
+            last.ident.span(),
+        ));
+        ancestor_names.push(name);
+    }
 
-    let (cgn, cgmn) = (
-        Ident::new(&component_get_name, ident.span()),
-        Ident::new(&component_get_mut_name, ident.span()),
+    let supertrait = match ancestor_traits.first() {
+        Some(p) => quote! { #p },
+        None => quote! { crate::core::IInstance },
+    };
+    let superclass = LitStr::new(
+        ic.superclass
+            .as_deref()
+            .or(ancestor_names.first().map(|x| x.as_str()))
+            .unwrap_or("Instance"),
+        ident.span(),
     );
 
-    let inherited_names: Vec<LitStr> = ic
-        .hierarchy
-        .iter()
-        .map(|i| LitStr::new(&i.segments.last().unwrap().ident.to_string(), i.span()))
-        .collect();
+    let class_name = ident.to_string();
+    let s = LitStr::new(&class_name, ident.span());
 
-    let inherited: Vec<syn::Path> = ic
-        .hierarchy
-        .into_iter()
-        .into_iter()
-        .map(|i| syn::Path {
-            leading_colon: i.leading_colon,
-            segments: {
-                let mut punct: Punctuated<PathSegment, Token![::]> = Punctuated::new();
+    let security_check = |ctx: &SecurityContext, member: &str, ret: proc_macro2::TokenStream| {
+        if let SecurityContext::None = ctx {
+            quote! {}
+        } else {
+            let label = LitStr::new(&format!("{}.{}", class_name, member), Span::call_site());
+            quote! {
+                if let Err(e) = crate::core::check_security_context(lua, #ctx, #label) {
+                    return #ret(Err(e));
+                }
+            }
+        }
+    };
 
-                let len = i.segments.len();
+    let mut iinstance_lua_get: Vec<proc_macro2::TokenStream> = vec![];
+    let mut iinstance_lua_set: Vec<proc_macro2::TokenStream> = vec![];
+    let mut field_news: Vec<proc_macro2::TokenStream> = vec![];
+    let mut field_clones: Vec<proc_macro2::TokenStream> = vec![];
+    let mut property_descriptors: Vec<proc_macro2::TokenStream> = vec![];
+
+    for f in &lua_fields {
+        let name = LitStr::new(&f.name, Span::call_site());
+        let rust_name = &f.rust_name;
+        let check = security_check(&f.security_context, &f.name, quote! { Some });
+
+        if let Some(get) = &f.get {
+            iinstance_lua_get.push(quote! {
+                #name => {
+                    #check
+                    Some(#get(&**self, ptr, lua).and_then(|x| x.into_lua(lua)))
+                }
+            });
+        } else if f.transparent {
+            return Error::new(f.span, "has no getter despite being transparent")
+                .into_compile_error()
+                .into();
+        } else {
+            iinstance_lua_get.push(quote! {
+                #name => {
+                    #check
+                    Some(lua_getter!(clone, lua, self.#rust_name))
+                }
+            });
+        }
 
-                for (i, mut element) in i.segments.into_iter().enumerate() {
-                    if i < len - 1 {
-                        punct.push(element);
-                    } else {
-                        element.ident = Ident::new(
-                            &("I".to_owned() + &element.ident.to_string()),
-                            element.ident.span(),
-                        );
-                        punct.push(element);
+        if f.readonly {
+            let msg = LitStr::new(
+                &format!(
+                    "Unable to assign property {}. Property is read only",
+                    f.name
+                ),
+                Span::call_site(),
+            );
+            iinstance_lua_set.push(quote! {
+                #name => {
+                    #check
+                    Some(Err(LuaError::RuntimeError(#msg.into())))
+                }
+            });
+        } else {
+            let assign = if let Some(set) = &f.set {
+                quote! {
+                    if let Err(e) = #set(&mut **self, ptr, lua, lua_setter!(opt_clone, lua, value)) {
+                        return Some(Err(e));
                     }
                 }
+            } else if f.transparent {
+                return Error::new(
+                    f.span,
+                    "has no setter despite being transparent (mark it `readonly` if it cannot be set)",
+                )
+                .into_compile_error()
+                .into();
+            } else {
+                quote! {
+                    self.#rust_name = lua_setter!(opt_clone, lua, value);
+                }
+            };
+            iinstance_lua_set.push(quote! {
+                #name => {
+                    #check
+                    #assign
+                    let _release = self.guard_release();
+                    Some(crate::core::InstanceComponent::emit_property_changed(
+                        &crate::core::IInstance::get_instance_component(ptr),
+                        lua,
+                        #name,
+                        value,
+                    ))
+                }
+            });
+        }
 
-                punct
-            },
-        })
-        .collect();
+        if f.transparent {
+            // not stored, nothing to construct or clone
+        } else if f.event {
+            field_news.push(quote! {
+                #rust_name: crate::userdata::RBXScriptSignal::new(metadata)
+            });
+            field_clones.push(quote! {
+                #rust_name: crate::userdata::RBXScriptSignal::new(metadata)
+            });
+        } else {
+            match &f.default {
+                Some(Some(s)) => field_news.push(quote! { #rust_name: #s }),
+                Some(None) => field_news.push(quote! { #rust_name: Default::default() }),
+                None => {
+                    return Error::new(
+                        f.span,
+                        "default impl required for field (use Option if you don't have one)",
+                    )
+                    .into_compile_error()
+                    .into()
+                }
+            }
+            field_clones.push(quote! { #rust_name: self.#rust_name.clone() });
+        }
+
+        if !f.event {
+            let value_type = LitStr::new(&value_type_name(&f.ty), Span::call_site());
+            let readonly = f.readonly.then(|| quote! { .readonly() });
+            let not_replicated = f.not_replicated.then(|| quote! { .not_replicated() });
+            let security = match f.security_context {
+                SecurityContext::None => None,
+                ref ctx => Some(quote! { .security(#ctx) }),
+            };
+            property_descriptors.push(quote! {
+                .property(
+                    crate::core::PropertyDescriptor::new(#name, #value_type)
+                        #readonly #not_replicated #security
+                )
+            });
+        }
+    }
+
+    for f in &stored_rust_fields {
+        field_news.push(quote! { #f: Default::default() });
+        field_clones.push(quote! { #f: self.#f.clone() });
+    }
 
-    let s = LitStr::new(&ident.to_string(), ident.span());
-
-    let iinstance_lua_get = {
-        // todo: add more features (e.g. security enforcement)
-        let mut quotes: Vec<proc_macro2::TokenStream> = vec![];
-        for f in &lua_fields {
-            if f.transparent {
-                if let Some(get) = &f.get {
-                    let name = LitStr::new(&f.name, Span::call_site());
-                    quotes.push(quote! {
-                        #name => Some(lua_getter!(lua, #get(ptr, lua)))
-                    });
+    for f in &lua_fns {
+        if f.virt {
+            return Error::new(f.sig.span(), "`virtual` methods are not supported yet")
+                .into_compile_error()
+                .into();
+        }
+        let name = LitStr::new(&f.lua_name, Span::call_site());
+        let fn_label = LitStr::new(
+            &format!("{}::{}", class_name, f.lua_name),
+            Span::call_site(),
+        );
+        let func = &f.sig.ident;
+
+        let mut uses_lua = !matches!(f.security_context, SecurityContext::None);
+        let mut call_args: Vec<proc_macro2::TokenStream> = vec![];
+        let mut arg_names: Vec<Ident> = vec![];
+        let mut arg_types: Vec<syn::Type> = vec![];
+
+        for input in f.sig.inputs.iter() {
+            let syn::FnArg::Typed(pt) = input else {
+                continue;
+            };
+            let is_lua = match &*pt.ty {
+                syn::Type::Reference(r) => match &*r.elem {
+                    syn::Type::Path(p) => p
+                        .path
+                        .segments
+                        .last()
+                        .map(|x| x.ident == "Lua")
+                        .unwrap_or(false),
+                    _ => false,
+                },
+                _ => false,
+            };
+            if is_lua {
+                uses_lua = true;
+                call_args.push(if f.asyn {
+                    quote! { &lua }
                 } else {
-                    return syn::Error::new(f.span, "has no getter despite being transparent")
-                        .into_compile_error()
-                        .into();
-                }
-            } else if let Some(get) = &f.get {
-                let name = LitStr::new(&f.name, Span::call_site());
-                quotes.push(quote! {
-                    #name => Some(lua_getter!(lua, #get(ptr, lua)))
+                    quote! { lua }
                 });
             } else {
-                let lua_name = LitStr::new(&f.name, Span::call_site());
-                let rust_name = &f.rust_name;
-
-                quotes.push(quote! {
-                    #lua_name => Some(lua_getter!(clone, lua, self.#rust_name))
-                });
+                let arg = Ident::new(&format!("arg{}", arg_names.len()), pt.span());
+                call_args.push(quote! { #arg });
+                arg_names.push(arg);
+                arg_types.push((*pt.ty).clone());
             }
         }
 
-        quotes
-    };
+        let returns_result = match &f.sig.output {
+            syn::ReturnType::Type(_, ty) => match &**ty {
+                syn::Type::Path(p) => p
+                    .path
+                    .segments
+                    .last()
+                    .map(|x| x.ident == "LuaResult" || x.ident == "Result")
+                    .unwrap_or(false),
+                _ => false,
+            },
+            syn::ReturnType::Default => false,
+        };
 
-    let field_news = {
-        // todo: finish this
-        let mut quotes: Vec<proc_macro2::TokenStream> = vec![];
-
-        for f in &lua_fields {
-            if let Some(s) = &f.default {
-                let n = &f.rust_name;
-                if let Some(s) = s {
-                    quotes.push(quote! {
-                        #n: #s
-                    });
-                } else {
-                    quotes.push(quote! {
-                        #n: Default::default()
-                    });
-                }
+        let call = if f.asyn {
+            quote! { this.#func(#(#call_args),*).await }
+        } else {
+            quote! { this.#func(#(#call_args),*) }
+        };
+        let call = if returns_result {
+            call
+        } else {
+            quote! { Ok(#call) }
+        };
+
+        let lua_param = if uses_lua {
+            quote! { lua }
+        } else {
+            quote! { _ }
+        };
+        let check = if let SecurityContext::None = f.security_context {
+            quote! {}
+        } else {
+            let ctx = &f.security_context;
+            let label = LitStr::new(&format!("{}.{}", class_name, f.lua_name), Span::call_site());
+            let lua_ref = if f.asyn {
+                quote! { &lua }
             } else {
-                return syn::Error::new(
-                    f.span,
-                    "default impl required for field (use Option if you don't have one)",
-                )
-                .into_compile_error()
-                .into();
+                quote! { lua }
+            };
+            quote! { crate::core::check_security_context(#lua_ref, #ctx, #label)?; }
+        };
+
+        let body = quote! {
+            #check
+            let this = inheritance_cast_to!(&*this, dyn #trait_name)
+                .map_err(|_| lua_invalid_argument!(#fn_label, 1, self cast Instance to #ident))?;
+            #call
+        };
+
+        iinstance_lua_get.push(if f.asyn {
+            quote! {
+                #name => lua_getter!(function_async_opt, lua, async |#lua_param, (this, #(#arg_names,)*): (crate::core::ManagedInstance, #(#arg_types,)*)| {
+                    #body
+                })
             }
-        }
+        } else {
+            quote! {
+                #name => lua_getter!(function_opt, lua, |#lua_param, (this, #(#arg_names,)*): (crate::core::ManagedInstance, #(#arg_types,)*)| {
+                    #body
+                })
+            }
+        });
+    }
 
-        quotes
+    let component_clone = if ic.no_clone {
+        let msg = LitStr::new(
+            &format!("Cannot clone {}", component_name),
+            Span::call_site(),
+        );
+        quote! {
+            Err(LuaError::RuntimeError(#msg.into()))
+        }
+    } else {
+        quote! {
+            Ok(#component_name {
+                #(#field_clones),*
+            })
+        }
     };
 
-    let iinstance_lua_set = {
-        // todo: add more features (e.g. security enforcement)
-        let mut quotes: Vec<proc_macro2::TokenStream> = vec![];
+    let clone_instance = if ic.no_clone {
+        let msg = LitStr::new(&format!("Cannot clone {}", class_name), Span::call_site());
+        quote! {
+            Err(LuaError::RuntimeError(#msg.into()))
+        }
+    } else {
+        quote! {
+            use crate::core::IInstanceComponent;
+            Ok(crate::core::Irc::new_cyclic_fallable::<_, LuaError>(|x| {
+                let metadata = crate::core::InstanceCreationMetadata::new(#s, x.cast_to_instance());
+                let mut i = #ident {
+                    instance: crate::core::RwLock::new_with_flag_auto(
+                        self.get_instance_component().clone(lua, &metadata)?,
+                    ),
+                    #(#ancestor_fields: crate::core::RwLock::new_with_flag_auto(
+                        <Self as #ancestor_traits>::#ancestor_getters(self).clone(lua, &metadata)?,
+                    ),)*
+                    #snake_id: crate::core::RwLock::new_with_flag_auto(
+                        self.#cgn().clone(lua, &metadata)?,
+                    ),
+                };
+                crate::core::DynInstance::submit_metadata(&mut i, metadata);
+                Ok(i)
+            })?
+            .cast_from_sized()
+            .unwrap())
+        }
+    };
 
-        for f in lua_fields {
-            if f.readonly {
-                let name = LitStr::new(&f.name, Span::call_site());
-                quotes.push(quote! {
-                    #name => Some(Err(r2g_mlua::prelude::LuaError::RuntimeError("Cannot set read only property.".into())))
-                });
+    let public_new = if ic.custom_new {
+        quote! {}
+    } else {
+        quote! {
+            pub fn new() -> crate::core::ManagedInstance {
+                Self::construct().cast_from_sized().unwrap()
             }
         }
-
-        quotes
     };
 
     quote! {
-        //static DEBUG: &str = #ls;
-        // static IC: &str = #moar;
-        // static RECEIVED_CODE: &str = #code;
-        // static FNS: &str = #lua_fns;
-        #(#attr)* #vis #struct_token #gens #component_name {
+        #(#attr)* #vis #struct_token #component_name {
             #(#rust_fields),*
         }
 
-        impl crate::instance::IInstanceComponent for #component_name {
-            fn new(_ptr: crate::instance::WeakManagedInstance, _class_name: &'static str) -> Self {
-                todo!("implement `new` *cleanly*. somehow. who knows how. cuz rust fields idk how to implement default/new for them here (lua fields are handled)")
+        #[allow(unused_variables, unused_imports)]
+        impl crate::core::IInstanceComponent for #component_name {
+            fn new(metadata: &crate::core::InstanceCreationMetadata) -> Self {
+                #component_name {
+                    #(#field_news),*
+                }
             }
 
-            fn clone(self: &crate::core::RwLockReadGuard<'_, Self>, _: &r2g_mlua::Lua, _: &crate::instance::WeakManagedInstance) -> r2g_mlua::prelude::LuaResult<Self> {
-                Err(r2g_mlua::prelude::LuaError::RuntimeError("Cannot clone DataModelComponent".into()))
+            fn clone(self: &crate::core::RwLockReadGuard<'_, Self>, lua: &r2g_mlua::Lua, metadata: &crate::core::InstanceCreationMetadata) -> r2g_mlua::prelude::LuaResult<Self> {
+                use r2g_mlua::prelude::*;
+                #component_clone
             }
 
-            fn lua_get(self: &mut crate::core::RwLockReadGuard<'_, Self>, ptr: &crate::instance::DynInstance, lua: &r2g_mlua::Lua, key: &String) -> Option<r2g_mlua::prelude::LuaResult<r2g_mlua::prelude::LuaValue>> {
-                use crate::core::lua_macros::lua_getter;
+            fn lua_get(self: &mut crate::core::RwLockReadGuard<'_, Self>, ptr: &crate::core::DynInstance, lua: &r2g_mlua::Lua, key: &String) -> Option<r2g_mlua::prelude::LuaResult<r2g_mlua::prelude::LuaValue>> {
+                use crate::core::lua_macros::{lua_getter, lua_invalid_argument};
                 use crate::core::inheritance_cast_to;
-                use crate::core::lua_macros::lua_invalid_argument;
-                use r2g_mlua::prelude::IntoLua;
+                use r2g_mlua::prelude::*;
                 match key.as_str() {
-                    #(#iinstance_lua_get),*,
+                    #(#iinstance_lua_get,)*
                     _ => None
                 }
             }
 
-            fn lua_set(self: &mut crate::core::RwLockWriteGuard<'_, Self>, _ptr: &crate::instance::DynInstance, _lua: &r2g_mlua::Lua, key: &String, _value: &r2g_mlua::prelude::LuaValue) -> Option<r2g_mlua::prelude::LuaResult<()>> {
-                use r2g_mlua::prelude::IntoLua;
+            fn lua_set(self: &mut crate::core::RwLockWriteGuard<'_, Self>, ptr: &crate::core::DynInstance, lua: &r2g_mlua::Lua, key: &String, value: &r2g_mlua::prelude::LuaValue) -> Option<r2g_mlua::prelude::LuaResult<()>> {
+                use crate::core::lua_macros::lua_setter;
+                use r2g_mlua::prelude::*;
                 match key.as_str() {
-                    #(#iinstance_lua_set),*,
+                    #(#iinstance_lua_set,)*
                     _ => None
                 }
             }
         }
 
-        trait #trait_name {
+        #vis trait #trait_name: #supertrait {
             fn #cgn(&self) -> crate::core::RwLockReadGuard<'_, #component_name>;
             fn #cgmn(&self) -> crate::core::RwLockWriteGuard<'_, #component_name>;
         }
 
-        struct #ident {
-            // base
-            instance: crate::core::RwLock<crate::instance::InstanceComponent>,
-            // all elements in hierarchy
-            service_provider: crate::core::RwLock<crate::instance::ServiceProviderComponent>,
-            // self
+        #[derive(Debug)]
+        #vis struct #ident {
+            instance: crate::core::RwLock<crate::core::InstanceComponent>,
+            #(#ancestor_fields: crate::core::RwLock<#ancestor_components>,)*
             #snake_id: crate::core::RwLock<#component_name>,
         }
 
         impl crate::core::InheritanceBase for #ident {
             fn inheritance_table(&self) -> crate::core::InheritanceTable {
                 crate::core::InheritanceTableBuilder::new()
-                    .insert_type::<#ident, dyn crate::instance::IObject>(|x| x, |x| x)
-                    .insert_type::<#ident, crate::instance::DynInstance>(|x| x, |x| x)
-                    #(.insert_type::<#ident, dyn #inherited>(|x| x, |x| x))*
+                    .insert_type::<#ident, dyn crate::core::IObject>(|x| x, |x| x)
+                    .insert_type::<#ident, crate::core::DynInstance>(|x| x, |x| x)
+                    #(.insert_type::<#ident, dyn #ancestor_traits>(|x| x, |x| x))*
                     .insert_type::<#ident, dyn #trait_name>(|x| x, |x| x)
+                    .insert_type::<#ident, #ident>(|x| x, |x| x)
                     .output()
             }
         }
 
-        impl crate::instance::IObject for #ident {
-            fn is_a(&self, class_name: &String) -> bool {
-                match class_name.as_str() {
-                    "DataModel" => true,
-                    //"ServiceProvider" => true,
-                    "Instance" => true,
-                    "Object" => true,
-                    #(#inherited_names => true),*,
-                    #s => true,
-                    _ => false
-                }
-            }
+        impl crate::core::IObject for #ident {
             fn lua_get(&self, lua: &r2g_mlua::Lua, name: String) -> r2g_mlua::prelude::LuaResult<r2g_mlua::prelude::LuaValue> {
-                use crate::instance::IInstanceComponent;
+                use crate::core::{IInstance, IInstanceComponent};
                 self.#snake_id.read().unwrap().lua_get(self, lua, &name)
-                    .or_else(|| self.service_provider.read().unwrap().lua_get(self, lua, &name))
-                    .unwrap_or_else(|| self.instance.read().unwrap().lua_get(lua, &name))
+                    #(.or_else(|| self.#ancestor_fields.read().unwrap().lua_get(self, lua, &name)))*
+                    .unwrap_or_else(|| self.get_instance_component().lua_get(lua, &name))
             }
             fn get_changed_signal(&self) -> crate::userdata::ManagedRBXScriptSignal {
-                use crate::instance::IInstance;
+                use crate::core::IInstance;
                 self.get_instance_component().changed.clone()
             }
             fn get_property_changed_signal(&self, property: String) -> crate::userdata::ManagedRBXScriptSignal {
-                use crate::instance::IInstance;
+                use crate::core::IInstance;
                 self.get_instance_component().get_property_changed_signal(property).unwrap()
             }
             fn get_class_name(&self) -> &'static str { #s }
         }
 
-        impl #trait_name for #ident {
-            fn #cgn(&self) -> crate::core::RwLockReadGuard<'_, #component_name> {
-                self.#snake_id.read().unwrap()
-            }
-
-            fn #cgmn(&self) -> crate::core::RwLockWriteGuard<'_, #component_name> {
-                self.#snake_id.write().unwrap()
-            }
-        }
-
-        impl crate::instance::IInstance for #ident {
-            fn get_instance_component(&self) -> crate::core::RwLockReadGuard<crate::instance::InstanceComponent> {
+        #[allow(unused_imports)]
+        impl crate::core::IInstance for #ident {
+            fn get_instance_component(&self) -> crate::core::RwLockReadGuard<'_, crate::core::InstanceComponent> {
                 self.instance.read().unwrap()
             }
 
-            fn get_instance_component_mut(&self) -> crate::core::RwLockWriteGuard<crate::instance::InstanceComponent> {
+            fn get_instance_component_mut(&self) -> crate::core::RwLockWriteGuard<'_, crate::core::InstanceComponent> {
                 self.instance.write().unwrap()
             }
 
             fn lua_set(&self, lua: &r2g_mlua::Lua, name: String, val: r2g_mlua::prelude::LuaValue) -> r2g_mlua::prelude::LuaResult<()> {
-                use crate::instance::IInstanceComponent;
+                use crate::core::IInstanceComponent;
                 self.#snake_id.write().unwrap().lua_set(self, lua, &name, &val)
-                    .or_else(|| self.service_provider.write().unwrap().lua_set(self, lua, &name, &val))
-                    .unwrap_or_else(|| self.instance.write().unwrap().lua_set(lua, &name, val))
+                    #(.or_else(|| self.#ancestor_fields.write().unwrap().lua_set(self, lua, &name, &val)))*
+                    .unwrap_or_else(|| self.get_instance_component_mut().lua_set(lua, &name, val))
             }
 
-            fn clone_instance(&self, _: &r2g_mlua::Lua) -> r2g_mlua::prelude::LuaResult<crate::instance::ManagedInstance> {
-                todo!("implement this cleanly too (same issue applies, but if no_clone this is optional)")
+            fn clone_instance(&self, lua: &r2g_mlua::Lua) -> r2g_mlua::prelude::LuaResult<crate::core::ManagedInstance> {
+                use r2g_mlua::prelude::*;
+                #clone_instance
             }
         }
 
-        impl crate::instance::IServiceProvider for #ident {
-            fn get_service_provider_component(&self) -> crate::core::RwLockReadGuard<crate::instance::ServiceProviderComponent> {
-                self.service_provider.read().unwrap()
+        #(
+            impl #ancestor_traits for #ident {
+                fn #ancestor_getters(&self) -> crate::core::RwLockReadGuard<'_, #ancestor_components> {
+                    self.#ancestor_fields.read().unwrap()
+                }
+
+                fn #ancestor_getters_mut(&self) -> crate::core::RwLockWriteGuard<'_, #ancestor_components> {
+                    self.#ancestor_fields.write().unwrap()
+                }
+            }
+        )*
+
+        impl #trait_name for #ident {
+            fn #cgn(&self) -> crate::core::RwLockReadGuard<'_, #component_name> {
+                self.#snake_id.read().unwrap()
             }
 
-            fn get_service_provider_component_mut(&self) -> crate::core::RwLockWriteGuard<crate::instance::ServiceProviderComponent> {
-                self.service_provider.write().unwrap()
+            fn #cgmn(&self) -> crate::core::RwLockWriteGuard<'_, #component_name> {
+                self.#snake_id.write().unwrap()
             }
+        }
 
-            fn get_service(&self, service_name: String) -> r2g_mlua::prelude::LuaResult<crate::instance::ManagedInstance> {
-                self.find_service(service_name)
-                    .and_then(|x|
-                        x.ok_or_else(|| r2g_mlua::prelude::LuaError::RuntimeError("Service not found".into()))
-                    )
+        impl #ident {
+            fn construct() -> crate::core::Irc<#ident> {
+                use crate::core::IInstanceComponent;
+                crate::core::Irc::new_cyclic(|x| {
+                    let metadata = crate::core::InstanceCreationMetadata::new(#s, x.cast_to_instance());
+                    let mut i = #ident {
+                        instance: crate::core::RwLock::new_with_flag_auto(crate::core::InstanceComponent::new(&metadata)),
+                        #(#ancestor_fields: crate::core::RwLock::new_with_flag_auto(#ancestor_components::new(&metadata)),)*
+                        #snake_id: crate::core::RwLock::new_with_flag_auto(#component_name::new(&metadata)),
+                    };
+                    crate::core::DynInstance::submit_metadata(&mut i, metadata);
+                    i
+                })
             }
 
-            fn find_service(&self, service_name: String) -> r2g_mlua::prelude::LuaResult<Option<crate::instance::ManagedInstance>> {
-                crate::instance::DynInstance::find_first_child_of_class(self, service_name)
+            #public_new
+
+            /// Reflection metadata of this class, for the class registry.
+            pub fn class_descriptor() -> crate::core::ClassDescriptor {
+                crate::core::ClassDescriptor::new(#s, Some(#superclass))
+                    #(#property_descriptors)*
             }
         }
-    }.into_token_stream().into()
+    }
+    .into_token_stream()
+    .into()
 }
 
 // #[proc_macro_attribute]
@@ -469,22 +723,26 @@ pub fn lua_enum(
     ts: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let enum_block: ItemEnum = parse_macro_input!(ts);
+    let args: AttrArguments = parse_macro_input!(arguments);
+    match lua_enum_impl(enum_block, &args) {
+        Ok(x) => x.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn lua_enum_impl(
+    enum_block: ItemEnum,
+    args: &AttrArguments,
+) -> syn::Result<proc_macro2::TokenStream> {
     let name = enum_block.ident.clone();
     let vis = enum_block.vis.clone();
 
-    let args: AttrArguments = parse_macro_input!(arguments);
-
     let default_impl = if let Some(default) = args.get_named_arg("default") {
         let default_val = &default.value;
         let default_name: Ident = {
             let default_name_arg = args.get_named_arg("default_name").map(|x| x.value.clone());
             match default_name_arg {
-                Some(x) => match x.try_into() {
-                    Ok(x) => x,
-                    Err(e) => {
-                        return e.into_compile_error().into();
-                    }
-                },
+                Some(x) => x.try_into()?,
                 None => Ident::new("Default", Span::call_site()),
             }
         };
@@ -508,7 +766,7 @@ pub fn lua_enum(
         quote! {}
     };
 
-    let mut last_index: Option<usize> = None;
+    let mut last_index: Option<i32> = None;
     let mut variants = vec![];
 
     for x in enum_block.variants.iter() {
@@ -519,13 +777,13 @@ pub fn lua_enum(
                     ..
                 }) = e =>
             {
-                last_index = Some(i.base10_parse().unwrap());
-                variants.push((x.ident.to_string(), last_index.unwrap() as i16));
+                last_index = Some(i.base10_parse()?);
+                variants.push((x.ident.to_string(), last_index.unwrap()));
             }
             None => match last_index {
                 Some(i) => {
                     last_index = Some(i + 1);
-                    variants.push((x.ident.to_string(), last_index.unwrap() as i16));
+                    variants.push((x.ident.to_string(), last_index.unwrap()));
                 }
                 None => {
                     last_index = Some(0);
@@ -533,9 +791,7 @@ pub fn lua_enum(
                 }
             },
             _ => {
-                return syn::Error::new(x.span(), "invalid discriminant")
-                    .into_compile_error()
-                    .into();
+                return Err(syn::Error::new(x.span(), "invalid discriminant"));
             }
         }
     }
@@ -559,6 +815,26 @@ pub fn lua_enum(
         })
         .collect();
 
+    let variant_from_values: Vec<proc_macro2::TokenStream> = variants
+        .iter()
+        .map(|(variant_name, value)| {
+            let variant_name = Ident::new(variant_name, Span::call_site());
+            quote! {
+                #value => Some(Self::#variant_name)
+            }
+        })
+        .collect();
+
+    let variant_from_names: Vec<proc_macro2::TokenStream> = variants
+        .iter()
+        .map(|(variant_name, _value)| {
+            let variant_ident = Ident::new(variant_name, Span::call_site());
+            quote! {
+                #variant_name => Some(Self::#variant_ident)
+            }
+        })
+        .collect();
+
     let variant_fields: Vec<proc_macro2::TokenStream> = variants
         .iter()
         .map(|(variant_name, _value)| {
@@ -571,11 +847,11 @@ pub fn lua_enum(
 
     let enum_type_name = Ident::new(&format!("LuaEnum{}", name.to_string()), Span::call_site());
 
-    quote! {
+    Ok(quote! {
         use r2g_mlua::prelude::*;
 
         #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-        #[repr(i16)]
+        #[repr(i32)]
         #enum_block
 
         impl FromLua for #name {
@@ -602,6 +878,26 @@ pub fn lua_enum(
             }
         }
 
+        impl #name {
+            pub const fn name(self) -> &'static str {
+                match self {
+                    #(#variant_quotes_names_only),*
+                }
+            }
+            pub const fn from_value(value: i32) -> Option<Self> {
+                match value {
+                    #(#variant_from_values,)*
+                    _ => None,
+                }
+            }
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#variant_from_names,)*
+                    _ => None,
+                }
+            }
+        }
+
         impl LuaUserData for #name {
             fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
                 methods.add_meta_method("__tostring", |_, this, ()| {
@@ -615,7 +911,7 @@ pub fn lua_enum(
                 fields.add_field_method_get("Name", |_, this| Ok(match (this) {
                     #(#variant_quotes_names_only),*
                 }));
-                fields.add_field_method_get("Value", |_, this| Ok(*this as i16));
+                fields.add_field_method_get("Value", |_, this| Ok(*this as i32));
 
             }
         }
@@ -661,8 +957,7 @@ pub fn lua_enum(
         }
 
         #default_impl
-    }
-    .into()
+    })
 }
 
 #[doc(hidden)]
@@ -709,6 +1004,81 @@ pub fn create_enums(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
         })
         .collect();
 
+    let singleton = lua_enums_singleton(&enums);
+
+    quote! {
+        use r2g_mlua::prelude::*;
+
+        #(#modules)*
+        #(#enum_use)*
+
+        #singleton
+    }
+    .into()
+}
+
+/// Generates every enum of an API dump, along with the `Enums` singleton.
+///
+/// `api_dump_enums!("api/API-Dump.json", [EnumName(default = X, default_name = Y), ...])`, the list holds the
+/// `#[lua_enum]` arguments of the enums that need them.
+#[proc_macro]
+pub fn api_dump_enums(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: api_dump::ApiDumpEnumsInput = parse_macro_input!(ts);
+    let dump = match api_dump::load_api_dump(&input.path) {
+        Ok(x) => x,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let items = match api_dump::enum_items(&input, &dump) {
+        Ok(x) => x,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
+    let mut enums = vec![];
+    let mut modules = vec![];
+    for (item, args) in items {
+        let x = item.ident.clone();
+        let ident = Ident::new(&camel_case_to_snake_case(x.to_string().as_str()), x.span());
+        let enum_type_name = Ident::new(&format!("LuaEnum{}", x.to_string()), Span::call_site());
+        let body = match lua_enum_impl(item, &args) {
+            Ok(x) => x,
+            Err(e) => return e.into_compile_error().into(),
+        };
+        modules.push(quote! {
+            mod #ident {
+                #body
+            }
+            pub use #ident::#x;
+            pub use #ident::#enum_type_name;
+        });
+        enums.push(x);
+    }
+
+    let track = api_dump::track_api_dump(&input.path);
+    let singleton = lua_enums_singleton(&enums);
+
+    quote! {
+        use r2g_mlua::prelude::*;
+
+        #track
+
+        #(#modules)*
+
+        #singleton
+    }
+    .into()
+}
+
+/// Expands to a `Vec<ClassDescriptor>` with the reflection data of every class in an API dump.
+#[proc_macro]
+pub fn api_dump_classes(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path: LitStr = parse_macro_input!(ts);
+    match api_dump::load_api_dump(&path) {
+        Ok(dump) => api_dump::generate_classes(&path, &dump).into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn lua_enums_singleton(enums: &[Ident]) -> proc_macro2::TokenStream {
     let enum_fields: Vec<proc_macro2::TokenStream> = enums
         .iter()
         .map(|x| {
@@ -721,11 +1091,6 @@ pub fn create_enums(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .collect();
 
     quote! {
-        use r2g_mlua::prelude::*;
-
-        #(#modules)*
-        #(#enum_use)*
-
         #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
         pub struct LuaEnums;
 
@@ -765,13 +1130,28 @@ pub fn create_enums(ts: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
         }
 
+        impl LuaEnums {
+            /// Gets an item of the enum called `enum_name` from its name, for callers that only know the enum by name.
+            pub fn item_from_name(lua: &Lua, enum_name: &str, name: &str) -> LuaResult<Option<LuaValue>> {
+                match enum_name {
+                    #(stringify!(#enums) => #enums::from_name(name).map(|x| x.into_lua(lua)).transpose(),)*
+                    _ => Ok(None),
+                }
+            }
+            /// Gets an item of the enum called `enum_name` from its value.
+            pub fn item_from_value(lua: &Lua, enum_name: &str, value: i32) -> LuaResult<Option<LuaValue>> {
+                match enum_name {
+                    #(stringify!(#enums) => #enums::from_value(value).map(|x| x.into_lua(lua)).transpose(),)*
+                    _ => Ok(None),
+                }
+            }
+        }
+
         impl crate::userdata::LuaSingleton for LuaEnums {
             fn register_singleton(lua: &Lua) -> LuaResult<()> {
                 lua.globals().raw_set("Enums", LuaEnums)?;
                 Ok(())
             }
         }
-
     }
-    .into()
 }
@@ -1,5 +1,5 @@
 use proc_macro2::Span;
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use syn::{
     braced, bracketed,
     parse::{Parse, ParseStream},
@@ -18,10 +18,26 @@ pub enum SecurityContext {
     RobloxScriptSecurity,
 }
 
+impl ToTokens for SecurityContext {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(match self {
+            SecurityContext::None => quote! { crate::core::SecurityContext::NONE },
+            SecurityContext::PluginSecurity => quote! { crate::core::SecurityContext::PLUGIN },
+            SecurityContext::LocalUserSecurity => {
+                quote! { crate::core::SecurityContext::LOCAL_USER }
+            }
+            SecurityContext::RobloxScriptSecurity => {
+                quote! { crate::core::SecurityContext::ROBLOX_SCRIPT }
+            }
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct LuaPropertyData {
     pub name: String,
     pub readonly: bool,
+    pub event: bool,
     pub get: Option<Path>,
     pub set: Option<Path>,
     pub security_context: SecurityContext,
@@ -29,6 +45,7 @@ pub struct LuaPropertyData {
     pub not_replicated: bool,
     pub transparent: bool,
     pub rust_name: Ident,
+    pub ty: syn::Type,
     pub span: Span,
 }
 
@@ -180,6 +197,7 @@ pub enum InstanceConfigAttr {
     Hierarchy(Eq, Bracket, Punctuated<syn::Path, Token![,]>, Span),
     CustomNew(bool, Option<Eq>, Span),
     RequiresInit(bool, Option<Eq>, Span),
+    Superclass(Eq, LitStr, Span),
 }
 
 macro_rules! bool_arg {
@@ -207,6 +225,11 @@ impl Parse for InstanceConfigAttr {
             "parent_locked" => return bool_arg!(input => ParentLocked | ident.span()),
             "custom_new" => return bool_arg!(input => CustomNew | ident.span()),
             "requires_init" => return bool_arg!(input => RequiresInit | ident.span()),
+            "superclass" => {
+                let equals = input.parse::<Token![=]>()?;
+                let name = input.parse::<LitStr>()?;
+                return Ok(InstanceConfigAttr::Superclass(equals, name, ident.span()));
+            }
             "hierarchy" => {
                 // this, will be a nightmare.
                 let equals = input.parse::<Token![=]>()?;
@@ -235,6 +258,7 @@ pub struct InstanceConfig {
     pub hierarchy: Vec<syn::Path>,
     pub custom_new: bool,
     pub requires_init: bool,
+    pub superclass: Option<String>,
 }
 
 impl Parse for Instance {
@@ -265,7 +289,7 @@ fn search_attrs_field(mut field: Field) -> Result<InstanceContent> {
         .attrs
         .iter()
         .enumerate()
-        .filter(|(_, a)| a.path().is_ident("property"))
+        .filter(|(_, a)| a.path().is_ident("property") || a.path().is_ident("event"))
         .collect();
 
     let field_name = field.ident.clone().unwrap();
@@ -284,6 +308,7 @@ fn search_attrs_field(mut field: Field) -> Result<InstanceContent> {
         ));
     } else {
         let (idx, attr) = filtered.pop().unwrap();
+        let event = attr.path().is_ident("event");
         let (
             mut name,
             mut readonly,
@@ -324,6 +349,9 @@ fn search_attrs_field(mut field: Field) -> Result<InstanceContent> {
                         return Err(nested_meta.error("already specified"));
                     }
                 }
+                "readonly" if event => {
+                    return Err(nested_meta.error("events are always read only"));
+                }
                 "readonly" => {
                     if let None = readonly {
                         if let Ok(ts) = nested_meta.value() {
@@ -417,7 +445,8 @@ fn search_attrs_field(mut field: Field) -> Result<InstanceContent> {
                 Some(s) => s,
                 None => return Err(Error::new(attr.span(), "name must be specified")),
             },
-            readonly: readonly.unwrap_or(false),
+            readonly: event || readonly.unwrap_or(false),
+            event,
             get,
             set,
             security_context: security_context.unwrap_or(SecurityContext::None),
@@ -426,6 +455,7 @@ fn search_attrs_field(mut field: Field) -> Result<InstanceContent> {
             transparent: transparent.unwrap_or(false),
             span: field.span(),
             rust_name: field_name,
+            ty: field.ty.clone(),
         };
 
         field.attrs.remove(idx);
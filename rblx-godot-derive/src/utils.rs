@@ -14,3 +14,43 @@ pub fn camel_case_to_snake_case(name: &str) -> String {
     }
     result
 }
+
+/// Replaces the last segment of `path` with `prefix` + segment + `suffix`, e.g. `super::Model` to `super::IModel`.
+pub fn map_last_segment(path: &syn::Path, prefix: &str, suffix: &str) -> syn::Path {
+    let mut path = path.clone();
+    if let Some(last) = path.segments.last_mut() {
+        last.ident = syn::Ident::new(
+            &format!("{}{}{}", prefix, last.ident, suffix),
+            last.ident.span(),
+        );
+    }
+    path
+}
+
+/// Best effort name of a Rust type as it appears in the reflection metadata.
+pub fn value_type_name(ty: &syn::Type) -> String {
+    let syn::Type::Path(p) = ty else {
+        return String::from("Variant");
+    };
+    let Some(last) = p.path.segments.last() else {
+        return String::from("Variant");
+    };
+    let name = last.ident.to_string();
+    match name.as_str() {
+        "Option" => match &last.arguments {
+            syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                Some(syn::GenericArgument::Type(ty)) => value_type_name(ty),
+                _ => String::from("Variant"),
+            },
+            _ => String::from("Variant"),
+        },
+        "bool" => String::from("bool"),
+        "f32" => String::from("float"),
+        "f64" => String::from("double"),
+        "i8" | "i16" | "i32" | "u8" | "u16" | "u32" => String::from("int"),
+        "i64" | "u64" => String::from("int64"),
+        "String" => String::from("string"),
+        "ManagedInstance" | "WeakManagedInstance" => String::from("Instance"),
+        _ => name,
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use r2g_mlua::prelude::*;
+use r2g_mlua::Compiler;
+
+/// How much bytecode the process wide cache holds in memory before dropping the least recently used chunks.
+const DEFAULT_CAPACITY: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+struct Entries {
+    chunks: HashMap<u128, (Arc<[u8]>, u64)>,
+    /// Total length of the chunks.
+    size: usize,
+    /// Bumped on every access, the chunks keep the value of their last one.
+    clock: u64,
+}
+
+/// Bytecode of compiled chunks, shared by every Luau state of the process.
+///
+/// Entries are keyed by a hash of the source, the compiler options and the bytecode version the vendored
+/// Luau emits, so a Luau update or different fast flags never pick up stale bytecode. Chunks that fail to
+/// compile aren't cached, their errors get reported again on every compile.
+#[derive(Debug)]
+pub struct BytecodeCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+}
+
+impl Default for BytecodeCache {
+    fn default() -> Self {
+        BytecodeCache::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+static BYTECODE_CACHE: LazyLock<BytecodeCache> = LazyLock::new(BytecodeCache::default);
+
+/// Gets the process wide bytecode cache.
+pub fn get_bytecode_cache() -> &'static BytecodeCache {
+    &BYTECODE_CACHE
+}
+
+/// The version bytes at the start of every chunk the compiler emits.
+static BYTECODE_VERSION: LazyLock<Vec<u8>> = LazyLock::new(|| {
+    Compiler::new()
+        .compile("")
+        .map(|x| x.into_iter().take(2).collect())
+        .unwrap_or_default()
+});
+
+/// 128 bit FNV-1a, stable across builds unlike the hashers of the standard library.
+fn hash(parts: &[&[u8]]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET;
+    for part in parts {
+        // The length keeps ("ab", "c") and ("a", "bc") apart.
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(part.iter()) {
+            hash ^= *byte as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Files start with the key they were written for and a hash of the bytecode following them.
+const HEADER_LENGTH: usize = 32;
+
+fn encode_file(key: u128, bytecode: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HEADER_LENGTH + bytecode.len());
+    data.extend_from_slice(&key.to_le_bytes());
+    data.extend_from_slice(&hash(&[bytecode]).to_le_bytes());
+    data.extend_from_slice(bytecode);
+    data
+}
+
+/// Gets the bytecode out of a cache file, if it was written for `key` and is intact.
+fn decode_file(key: u128, data: &[u8]) -> Option<&[u8]> {
+    if data.len() < HEADER_LENGTH {
+        return None;
+    }
+    let (header, bytecode) = data.split_at(HEADER_LENGTH);
+    let written_key = u128::from_le_bytes(header[..16].try_into().unwrap());
+    let checksum = u128::from_le_bytes(header[16..].try_into().unwrap());
+    (written_key == key && checksum == hash(&[bytecode]) && bytecode.starts_with(&BYTECODE_VERSION))
+        .then_some(bytecode)
+}
+
+impl BytecodeCache {
+    /// Creates a cache holding up to `capacity` bytes of bytecode in memory.
+    pub fn with_capacity(capacity: usize) -> BytecodeCache {
+        BytecodeCache {
+            entries: Mutex::new(Entries::default()),
+            capacity,
+        }
+    }
+    fn key_of(compiler: &Compiler, source: &str) -> u128 {
+        let options = format!("{:?}", compiler);
+        hash(&[&BYTECODE_VERSION, options.as_bytes(), source.as_bytes()])
+    }
+    /// Returns the bytecode of `source` as `compiler` builds it, compiling it only when neither the cache
+    /// nor `directory`, when given, already hold it. New bytecode gets written to `directory` too.
+    pub fn get_or_compile(
+        &self,
+        compiler: &Compiler,
+        source: &str,
+        directory: Option<&Path>,
+    ) -> LuaResult<Arc<[u8]>> {
+        let key = Self::key_of(compiler, source);
+        if let Some(x) = self.get(key) {
+            return Ok(x);
+        }
+        let file = directory.map(|x| Self::file_of(x, key));
+        let persisted = file.as_ref().and_then(|x| fs::read(x).ok());
+        let bytecode: Arc<[u8]> = match persisted.as_deref().and_then(|x| decode_file(key, x)) {
+            Some(x) => x.into(),
+            None => {
+                let bytecode: Arc<[u8]> = compiler.compile(source)?.into();
+                if let Some(file) = file.as_ref() {
+                    // The cache only saves time, failing to persist it must not fail the script.
+                    let _ = Self::write_file(file, &encode_file(key, &bytecode));
+                }
+                bytecode
+            }
+        };
+        self.insert(key, bytecode.clone());
+        Ok(bytecode)
+    }
+    /// Forgets the bytecode of `source`, in memory and in `directory`, so the next `get_or_compile` compiles it
+    /// again. For bytecode that was cached but doesn't load.
+    pub fn invalidate(&self, compiler: &Compiler, source: &str, directory: Option<&Path>) {
+        let key = Self::key_of(compiler, source);
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((bytecode, _)) = entries.chunks.remove(&key) {
+            entries.size -= bytecode.len();
+        }
+        if let Some(directory) = directory {
+            let _ = fs::remove_file(Self::file_of(directory, key));
+        }
+    }
+    fn get(&self, key: u128) -> Option<Arc<[u8]>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.chunks.get_mut(&key).map(|(bytecode, used)| {
+            *used = clock;
+            bytecode.clone()
+        })
+    }
+    /// Adds a chunk, then drops the least recently used ones until the cache fits its capacity again. The chunk
+    /// just added always stays.
+    fn insert(&self, key: u128, bytecode: Arc<[u8]>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.size += bytecode.len();
+        if let Some((previous, _)) = entries.chunks.insert(key, (bytecode, clock)) {
+            entries.size -= previous.len();
+        }
+        while entries.size > self.capacity && entries.chunks.len() > 1 {
+            let oldest = entries
+                .chunks
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| *key)
+                .unwrap();
+            let (bytecode, _) = entries.chunks.remove(&oldest).unwrap();
+            entries.size -= bytecode.len();
+        }
+    }
+    fn file_of(directory: &Path, key: u128) -> PathBuf {
+        directory.join(format!("{:032x}.luauc", key))
+    }
+    /// Writes through a temporary file, so another process never reads a partial chunk.
+    fn write_file(file: &Path, data: &[u8]) -> std::io::Result<()> {
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = file.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temp, data)?;
+        fs::rename(&temp, file)
+    }
+    /// Returns how many chunks are held in memory.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().chunks.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Forgets the chunks held in memory, the ones persisted on disk stay.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.chunks.clear();
+        entries.size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use r2g_mlua::Compiler;
+
+    use super::{encode_file, BytecodeCache};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rblx-godot-bytecode-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn hits_until_the_options_change() {
+        let cache = BytecodeCache::default();
+        let compiler = Compiler::new();
+        let first = cache.get_or_compile(&compiler, "return 1", None).unwrap();
+        let second = cache.get_or_compile(&compiler, "return 1", None).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        let optimized = Compiler::new().set_optimization_level(2);
+        let third = cache.get_or_compile(&optimized, "return 1", None).unwrap();
+        assert!(!Arc::ptr_eq(&first, &third));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn drops_the_least_recently_used_chunks() {
+        let compiler = Compiler::new();
+        let size = compiler.compile("return 1").unwrap().len();
+        // Room for two chunks of about that size.
+        let cache = BytecodeCache::with_capacity(size * 2 + size / 2);
+        let first = cache.get_or_compile(&compiler, "return 1", None).unwrap();
+        cache.get_or_compile(&compiler, "return 2", None).unwrap();
+        // Using the first chunk again makes the second one the oldest.
+        cache.get_or_compile(&compiler, "return 1", None).unwrap();
+        cache.get_or_compile(&compiler, "return 3", None).unwrap();
+        assert_eq!(cache.len(), 2);
+        let again = cache.get_or_compile(&compiler, "return 1", None).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+    }
+
+    #[test]
+    fn persists_chunks_and_ignores_damaged_files() {
+        let dir = temp_dir("persist");
+        let compiler = Compiler::new();
+        let bytecode = BytecodeCache::default()
+            .get_or_compile(&compiler, "return 1", Some(&dir))
+            .unwrap();
+        let files: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|x| x.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+
+        // Another process finds the chunk on disk instead of compiling it: planted bytecode is what it gets.
+        let key = BytecodeCache::key_of(&compiler, "return 1");
+        let planted = compiler.compile("return 2").unwrap();
+        fs::write(&files[0], encode_file(key, &planted)).unwrap();
+        let read = BytecodeCache::default()
+            .get_or_compile(&compiler, "return 1", Some(&dir))
+            .unwrap();
+        assert_eq!(&*read, &planted[..]);
+
+        // A damaged file gets compiled again, and replaced.
+        let mut damaged = encode_file(key, &planted);
+        let last = damaged.len() - 1;
+        damaged[last] ^= 0xff;
+        fs::write(&files[0], damaged).unwrap();
+        let recompiled = BytecodeCache::default()
+            .get_or_compile(&compiler, "return 1", Some(&dir))
+            .unwrap();
+        assert_eq!(recompiled, bytecode);
+        assert_eq!(fs::read(&files[0]).unwrap(), encode_file(key, &bytecode));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
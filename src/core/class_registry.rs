@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::BitOr;
+use std::sync::{LazyLock, RwLock as StdRwLock, RwLockReadGuard as StdRwLockReadGuard};
+
+use r2g_mlua::prelude::*;
+
+use super::{get_state, ManagedInstance, SecurityContext};
+use crate::userdata::enums::ScriptCapability;
+
+/// Constructs a new instance of a class. Used by `Instance.new` and the file loaders.
+pub type ClassConstructor = fn(&Lua) -> LuaResult<ManagedInstance>;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct ClassTags(u8);
+impl ClassTags {
+    pub const NONE: ClassTags = ClassTags(0);
+    pub const NOT_CREATABLE: ClassTags = ClassTags(0x1);
+    pub const SERVICE: ClassTags = ClassTags(0x2);
+    pub const NOT_REPLICATED: ClassTags = ClassTags(0x4);
+    pub const DEPRECATED: ClassTags = ClassTags(0x8);
+}
+
+impl BitOr for ClassTags {
+    type Output = ClassTags;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        ClassTags(self.0 | rhs.0)
+    }
+}
+
+impl ClassTags {
+    pub const fn has(self, other: ClassTags) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PropertyDescriptor {
+    pub name: String,
+    pub value_type: String,
+    pub readonly: bool,
+    pub not_replicated: bool,
+    pub security: SecurityContext,
+    pub write_security: SecurityContext,
+    pub default_value: Option<String>,
+}
+
+impl PropertyDescriptor {
+    pub fn new(name: &str, value_type: &str) -> PropertyDescriptor {
+        PropertyDescriptor {
+            name: name.into(),
+            value_type: value_type.into(),
+            readonly: false,
+            not_replicated: false,
+            security: SecurityContext::NONE,
+            write_security: SecurityContext::NONE,
+            default_value: None,
+        }
+    }
+    pub fn readonly(mut self) -> PropertyDescriptor {
+        self.readonly = true;
+        self
+    }
+    pub fn not_replicated(mut self) -> PropertyDescriptor {
+        self.not_replicated = true;
+        self
+    }
+    /// Sets the security required to read and write the property.
+    pub fn security(mut self, security: SecurityContext) -> PropertyDescriptor {
+        self.security = security;
+        self.write_security = security;
+        self
+    }
+    /// Sets the security required to write the property, when it differs from the read security.
+    pub fn write_security(mut self, security: SecurityContext) -> PropertyDescriptor {
+        self.write_security = security;
+        self
+    }
+    pub fn default_value(mut self, default_value: &str) -> PropertyDescriptor {
+        self.default_value = Some(default_value.into());
+        self
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum MemberKind {
+    Property,
+    Function,
+    Event,
+    Callback,
+}
+
+impl Display for MemberKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MemberKind::Property => "Property",
+            MemberKind::Function => "Function",
+            MemberKind::Event => "Event",
+            MemberKind::Callback => "Callback",
+        })
+    }
+}
+
+/// A function, event or callback of a class. Properties are described by [`PropertyDescriptor`].
+#[derive(Clone, Debug)]
+pub struct MemberDescriptor {
+    pub name: String,
+    pub kind: MemberKind,
+    pub security: SecurityContext,
+}
+
+impl MemberDescriptor {
+    pub fn new(name: &str, kind: MemberKind) -> MemberDescriptor {
+        MemberDescriptor {
+            name: name.into(),
+            kind,
+            security: SecurityContext::NONE,
+        }
+    }
+    pub fn security(mut self, security: SecurityContext) -> MemberDescriptor {
+        self.security = security;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ClassDescriptor {
+    pub name: String,
+    pub superclass: Option<String>,
+    pub constructor: Option<ClassConstructor>,
+    pub tags: ClassTags,
+    pub properties: Vec<PropertyDescriptor>,
+    pub members: Vec<MemberDescriptor>,
+    /// The capability sandboxed scripts need to use the class. Inherited when not set.
+    pub capability: Option<ScriptCapability>,
+}
+
+impl ClassDescriptor {
+    pub fn new(name: &str, superclass: Option<&str>) -> ClassDescriptor {
+        ClassDescriptor {
+            name: name.into(),
+            superclass: superclass.map(|x| x.into()),
+            constructor: None,
+            tags: ClassTags::NONE,
+            properties: Vec::new(),
+            members: Vec::new(),
+            capability: None,
+        }
+    }
+    pub fn constructor(mut self, constructor: ClassConstructor) -> ClassDescriptor {
+        self.constructor = Some(constructor);
+        self
+    }
+    pub fn tags(mut self, tags: ClassTags) -> ClassDescriptor {
+        self.tags = self.tags | tags;
+        self
+    }
+    pub fn property(mut self, property: PropertyDescriptor) -> ClassDescriptor {
+        self.properties.push(property);
+        self
+    }
+    pub fn capability(mut self, capability: ScriptCapability) -> ClassDescriptor {
+        self.capability = Some(capability);
+        self
+    }
+    pub fn member(mut self, member: MemberDescriptor) -> ClassDescriptor {
+        self.members.push(member);
+        self
+    }
+    /// Gets the security required to read, or with `write` to assign, a member declared by this class.
+    pub fn member_security(&self, name: &str, write: bool) -> Option<SecurityContext> {
+        if let Some(property) = self.properties.iter().find(|x| x.name == name) {
+            Some(if write {
+                property.write_security
+            } else {
+                property.security
+            })
+        } else {
+            self.members
+                .iter()
+                .find(|x| x.name == name)
+                .map(|x| x.security)
+        }
+    }
+    /// Looks up a member declared by this class, not including inherited ones.
+    pub fn find_member(&self, name: &str) -> Option<MemberKind> {
+        if self.properties.iter().any(|x| x.name == name) {
+            Some(MemberKind::Property)
+        } else {
+            self.members.iter().find(|x| x.name == name).map(|x| x.kind)
+        }
+    }
+    #[inline]
+    pub fn is_creatable(&self) -> bool {
+        self.constructor.is_some() && !self.tags.has(ClassTags::NOT_CREATABLE)
+    }
+    #[inline]
+    pub fn is_service(&self) -> bool {
+        self.tags.has(ClassTags::SERVICE)
+    }
+}
+
+/// Table of every class known to the VM, shared by all VMs in the process.
+///
+/// Besides the implemented classes, the registry holds the reflection data imported from the API dump
+/// (`api/API-Dump.json`, see `api/README.md`), which also covers classes and members that aren't implemented yet.
+#[derive(Debug, Default)]
+pub struct ClassRegistry {
+    classes: HashMap<String, ClassDescriptor>,
+    reflection: HashMap<String, ClassDescriptor>,
+}
+
+impl ClassRegistry {
+    /// Registers a class, replacing any previous class with the same name.
+    pub fn register(&mut self, descriptor: ClassDescriptor) {
+        self.classes.insert(descriptor.name.clone(), descriptor);
+    }
+    pub fn get(&self, class_name: &str) -> Option<&ClassDescriptor> {
+        self.classes.get(class_name)
+    }
+    pub fn classes(&self) -> impl Iterator<Item = &ClassDescriptor> {
+        self.classes.values()
+    }
+    /// Returns the superclass of a class, if it is registered and has one.
+    pub fn get_superclass(&self, class_name: &str) -> Option<&ClassDescriptor> {
+        self.get(class_name)
+            .and_then(|x| x.superclass.as_ref())
+            .and_then(|x| self.get(x))
+    }
+    /// Returns true if `class_name` is `base` or inherits from it.
+    pub fn is_a(&self, class_name: &str, base: &str) -> bool {
+        let mut current = self.get(class_name);
+        if current.is_none() {
+            return class_name == base;
+        }
+        while let Some(class) = current {
+            if class.name == base {
+                return true;
+            }
+            current = class.superclass.as_ref().and_then(|x| self.get(x));
+        }
+        false
+    }
+    /// Returns every property of the class, including inherited ones, base classes first.
+    pub fn get_properties(&self, class_name: &str) -> Vec<&PropertyDescriptor> {
+        let mut chain = Vec::new();
+        let mut current = self.get(class_name);
+        while let Some(class) = current {
+            chain.push(class);
+            current = class.superclass.as_ref().and_then(|x| self.get(x));
+        }
+        chain
+            .into_iter()
+            .rev()
+            .flat_map(|x| x.properties.iter())
+            .collect()
+    }
+    pub fn register_reflected(&mut self, descriptor: ClassDescriptor) {
+        self.reflection.insert(descriptor.name.clone(), descriptor);
+    }
+    /// Gets the reflection data of a class from the API dump.
+    pub fn get_reflected(&self, class_name: &str) -> Option<&ClassDescriptor> {
+        self.reflection.get(class_name)
+    }
+    /// Finds a member of a class or one of its superclasses in the API dump. Returns the class declaring it.
+    pub fn find_known_member(&self, class_name: &str, member: &str) -> Option<(&str, MemberKind)> {
+        let mut current = Some(class_name);
+        while let Some(name) = current {
+            let reflected = self.get_reflected(name);
+            if let Some(kind) = reflected.and_then(|x| x.find_member(member)) {
+                return Some((reflected.unwrap().name.as_str(), kind));
+            }
+            current = reflected
+                .or_else(|| self.get(name))
+                .and_then(|x| x.superclass.as_deref());
+        }
+        None
+    }
+    /// Lists the members of a class and its superclasses, sorted by name, with properties described as members of
+    /// kind `Property`. Members the API dump knows about are listed even when the VM doesn't implement them yet.
+    pub fn get_members(&self, class_name: &str) -> Vec<MemberDescriptor> {
+        let mut members: HashMap<&str, MemberDescriptor> = HashMap::new();
+        let mut current = Some(class_name);
+        while let Some(name) = current {
+            let reflected = self.get_reflected(name);
+            let implemented = self.get(name);
+            // The API dump goes first, so its security wins like in `get_member_security`.
+            for class in reflected.into_iter().chain(implemented) {
+                for property in class.properties.iter() {
+                    members.entry(&property.name).or_insert_with(|| {
+                        MemberDescriptor::new(&property.name, MemberKind::Property)
+                            .security(property.security)
+                    });
+                }
+                for member in class.members.iter() {
+                    members
+                        .entry(&member.name)
+                        .or_insert_with(|| member.clone());
+                }
+            }
+            current = reflected
+                .or(implemented)
+                .and_then(|x| x.superclass.as_deref());
+        }
+        let mut members: Vec<MemberDescriptor> = members.into_values().collect();
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        members
+    }
+    /// Gets the security required to access a member of a class or one of its superclasses.
+    /// The API dump takes precedence over the implemented classes.
+    pub fn get_member_security(
+        &self,
+        class_name: &str,
+        member: &str,
+        write: bool,
+    ) -> Option<SecurityContext> {
+        let mut current = Some(class_name);
+        while let Some(name) = current {
+            let reflected = self.get_reflected(name);
+            let implemented = self.get(name);
+            let security = reflected
+                .and_then(|x| x.member_security(member, write))
+                .or_else(|| implemented.and_then(|x| x.member_security(member, write)));
+            if security.is_some() {
+                return security;
+            }
+            current = reflected
+                .or(implemented)
+                .and_then(|x| x.superclass.as_deref());
+        }
+        None
+    }
+    /// Gets the security the API dump requires to access a member of a class or one of its superclasses. Unlike
+    /// [`ClassRegistry::get_member_security`], the implemented classes aren't looked at, their accessors check it.
+    pub fn get_reflected_member_security(
+        &self,
+        class_name: &str,
+        member: &str,
+        write: bool,
+    ) -> Option<SecurityContext> {
+        let mut current = Some(class_name);
+        while let Some(name) = current {
+            let reflected = self.get_reflected(name);
+            if let Some(security) = reflected.and_then(|x| x.member_security(member, write)) {
+                return Some(security);
+            }
+            current = reflected
+                .or_else(|| self.get(name))
+                .and_then(|x| x.superclass.as_deref());
+        }
+        None
+    }
+    /// Gets the capability sandboxed scripts need to use a class, `Basic` unless the class or a superclass says otherwise.
+    pub fn get_required_capability(&self, class_name: &str) -> ScriptCapability {
+        let mut current = Some(class_name);
+        while let Some(name) = current {
+            let class = self.get(name).or_else(|| self.get_reflected(name));
+            if let Some(capability) = class.and_then(|x| x.capability) {
+                return capability;
+            }
+            current = class.and_then(|x| x.superclass.as_deref());
+        }
+        ScriptCapability::Basic
+    }
+    /// The error raised when a script uses a member the API dump knows about, but the VM doesn't implement.
+    pub fn not_implemented_error(&self, class_name: &str, member: &str) -> Option<LuaError> {
+        self.find_known_member(class_name, member)
+            .map(|(class, kind)| {
+                LuaError::RuntimeError(format!(
+                    "{} {}.{} is not implemented yet",
+                    kind, class, member
+                ))
+            })
+    }
+    pub fn get_constructor(&self, class_name: &str) -> LuaResult<ClassConstructor> {
+        self.get(class_name)
+            .filter(|x| x.is_creatable())
+            .and_then(|x| x.constructor)
+            .ok_or_else(|| {
+                LuaError::RuntimeError(format!(
+                    "Unable to create an Instance of type \"{}\"",
+                    class_name
+                ))
+            })
+    }
+}
+
+static CLASS_REGISTRY: LazyLock<StdRwLock<ClassRegistry>> = LazyLock::new(|| {
+    let mut registry = ClassRegistry::default();
+    for class in rblx_godot_derive::api_dump_classes!("api/API-Dump.json") {
+        registry.register_reflected(class);
+    }
+    crate::instance::register_builtin_classes(&mut registry);
+    StdRwLock::new(registry)
+});
+
+/// Gets the process wide class registry.
+pub fn get_class_registry() -> StdRwLockReadGuard<'static, ClassRegistry> {
+    CLASS_REGISTRY.read().unwrap()
+}
+
+/// Registers a class in the process wide class registry. Embedders should call this at startup, before any VM is created.
+pub fn register_class(descriptor: ClassDescriptor) {
+    CLASS_REGISTRY.write().unwrap().register(descriptor);
+}
+
+/// Creates a new instance of the given class, resolving it through the class registry.
+pub fn create_instance(lua: &Lua, class_name: &str) -> LuaResult<ManagedInstance> {
+    // The constructor is copied out, so the registry lock is not held while constructing.
+    let constructor = get_class_registry().get_constructor(class_name)?;
+    let instance = constructor(lua)?;
+    get_state(lua).get_vm().register_instance_tree(&instance);
+    Ok(instance)
+}
@@ -0,0 +1,153 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use r2g_mlua::ffi::lua_clock;
+
+/// Reads the real clock, in seconds. The watchdogs always use it, a script stuck in a loop has to be stopped
+/// even if the scheduler clock doesn't move.
+#[inline(always)]
+pub fn real_clock() -> f64 {
+    unsafe { lua_clock() }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockMode {
+    /// The clock follows the real one.
+    RealTime,
+    /// The clock only moves when a frame runs, by exactly the delta of the frame. Waiting for 5 seconds then
+    /// takes the frames adding up to 5 seconds, however long they really take.
+    Manual,
+}
+
+/// The clock a VM schedules its threads on. In real time mode it follows the real clock, sped up or slowed down
+/// by the time scale, and stands still while frozen.
+#[derive(Debug)]
+pub struct SchedulerClock {
+    mode: ClockMode,
+    /// Real time of the last change of speed.
+    base_real: f64,
+    /// Time of the clock at that moment.
+    base_time: f64,
+    time_scale: f64,
+    frozen: bool,
+}
+
+impl SchedulerClock {
+    pub fn new() -> SchedulerClock {
+        let now = real_clock();
+        SchedulerClock {
+            mode: ClockMode::RealTime,
+            base_real: now,
+            base_time: now,
+            time_scale: 1.0,
+            frozen: false,
+        }
+    }
+    /// Returns the time of the clock in seconds.
+    pub fn now(&self) -> f64 {
+        if self.frozen || self.mode == ClockMode::Manual {
+            self.base_time
+        } else {
+            self.base_time + (real_clock() - self.base_real) * self.time_scale
+        }
+    }
+    /// Returns the seconds elapsed since the Unix epoch. In manual mode that's the time of the clock, so dates
+    /// don't depend on when the VM runs either.
+    pub fn unix_time(&self) -> f64 {
+        match self.mode {
+            ClockMode::RealTime => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs_f64())
+                .unwrap_or(0.0),
+            ClockMode::Manual => self.base_time,
+        }
+    }
+    fn rebase(&mut self) {
+        self.base_time = self.now();
+        self.base_real = real_clock();
+    }
+    #[inline(always)]
+    pub fn get_mode(&self) -> ClockMode {
+        self.mode
+    }
+    /// Switches to real time mode, or to manual mode starting at `time`.
+    pub fn set_mode(&mut self, mode: ClockMode, time: Option<f64>) {
+        self.rebase();
+        self.mode = mode;
+        if let Some(time) = time {
+            self.base_time = time;
+        }
+    }
+    #[inline(always)]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.rebase();
+        self.frozen = frozen;
+    }
+    #[inline(always)]
+    pub fn get_time_scale(&self) -> f64 {
+        self.time_scale
+    }
+    /// Sets how fast the clock goes compared to the real one, which has to be finite and not negative.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        debug_assert!(time_scale.is_finite() && time_scale >= 0.0);
+        self.rebase();
+        self.time_scale = time_scale;
+    }
+    /// Accounts for a frame simulating `delta` seconds. Unless the clock follows the real one, it moves forward
+    /// by exactly that.
+    pub fn advance_frame(&mut self, delta: f64) {
+        self.rebase();
+        if self.frozen || self.mode == ClockMode::Manual {
+            self.base_time += delta;
+        }
+    }
+}
+
+impl Default for SchedulerClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r2g_mlua::prelude::*;
+
+    use super::ClockMode;
+    use crate::core::{GlobalTaskScheduler, RblxVM};
+
+    #[test]
+    fn manual_clock_resumes_waits_after_exactly_the_simulated_time() {
+        let vm = RblxVM::new_for_test();
+        let lua = {
+            let mut write = vm.write().unwrap();
+            write.set_clock_mode(ClockMode::Manual, Some(0.0));
+            write.get_main_state().get_lua().clone()
+        };
+        let results: LuaTable = lua
+            .load(
+                r#"
+                local results = {}
+                local clock, ticked = os.clock(), tick()
+                task.spawn(function()
+                    results.waited = task.wait(5)
+                    results.clock = os.clock() - clock
+                    results.tick = tick() - ticked
+                end)
+                return results
+                "#,
+            )
+            .call(())
+            .unwrap();
+        for _ in 0..4 {
+            GlobalTaskScheduler::frame_step(vm.write().unwrap(), 1.0).unwrap();
+        }
+        assert_eq!(results.get::<Option<f64>>("waited").unwrap(), None);
+        GlobalTaskScheduler::frame_step(vm.write().unwrap(), 1.0).unwrap();
+        assert_eq!(results.get::<f64>("waited").unwrap(), 5.0);
+        assert_eq!(results.get::<f64>("clock").unwrap(), 5.0);
+        assert_eq!(results.get::<f64>("tick").unwrap(), 5.0);
+    }
+}
@@ -0,0 +1,221 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering::Relaxed};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize, Debug)]
+pub struct DapRequest {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Debug)]
+pub enum DapMessage {
+    Connected,
+    Request(DapRequest),
+    Disconnected,
+}
+
+/// Accepts one Debug Adapter Protocol client at a time on a localhost TCP port.
+///
+/// Messages are read on a background thread and handed over through a channel, the VM thread picks them up
+/// whenever it can. Pause requests are also flagged separately, so running scripts can notice them.
+#[derive(Debug)]
+pub struct DapServer {
+    port: u16,
+    messages: Mutex<Receiver<DapMessage>>,
+    client: Arc<Mutex<Option<TcpStream>>>,
+    pause_requested: Arc<AtomicBool>,
+    shutdown: Arc<AtomicBool>,
+    seq: AtomicI64,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DapServer {
+    /// Starts listening on `port`, zero picks any free port.
+    pub fn bind(port: u16) -> std::io::Result<DapServer> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let port = listener.local_addr()?.port();
+        let (sender, messages) = channel();
+        let client = Arc::new(Mutex::new(None));
+        let pause_requested = Arc::new(AtomicBool::new(false));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let client = client.clone();
+            let pause_requested = pause_requested.clone();
+            let shutdown = shutdown.clone();
+            std::thread::Builder::new()
+                .name("rblx-godot debugger".into())
+                .spawn(move || Self::listen(listener, sender, client, pause_requested, shutdown))?
+        };
+        Ok(DapServer {
+            port,
+            messages: Mutex::new(messages),
+            client,
+            pause_requested,
+            shutdown,
+            seq: AtomicI64::new(1),
+            thread: Some(thread),
+        })
+    }
+    fn listen(
+        listener: TcpListener,
+        sender: Sender<DapMessage>,
+        client: Arc<Mutex<Option<TcpStream>>>,
+        pause_requested: Arc<AtomicBool>,
+        shutdown: Arc<AtomicBool>,
+    ) {
+        for stream in listener.incoming() {
+            if shutdown.load(Relaxed) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let Ok(writer) = stream.try_clone() else {
+                continue;
+            };
+            *client.lock().unwrap() = Some(writer);
+            if sender.send(DapMessage::Connected).is_err() {
+                break;
+            }
+            let mut reader = BufReader::new(stream);
+            while let Some(message) = read_message(&mut reader) {
+                if message["type"] != "request" {
+                    continue;
+                }
+                let Ok(request) = serde_json::from_value::<DapRequest>(message) else {
+                    continue;
+                };
+                if request.command == "pause" {
+                    pause_requested.store(true, Relaxed);
+                }
+                if sender.send(DapMessage::Request(request)).is_err() {
+                    return;
+                }
+            }
+            *client.lock().unwrap() = None;
+            if sender.send(DapMessage::Disconnected).is_err() {
+                break;
+            }
+        }
+    }
+    #[inline]
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    pub fn is_connected(&self) -> bool {
+        self.client.lock().unwrap().is_some()
+    }
+    /// Returns the next message if one is already waiting.
+    pub fn try_recv(&self) -> Option<DapMessage> {
+        self.messages.lock().unwrap().try_recv().ok()
+    }
+    /// Blocks until the next message, returns `None` once the server is gone.
+    pub fn recv(&self) -> Option<DapMessage> {
+        self.messages.lock().unwrap().recv().ok()
+    }
+    /// Returns whether the client asked to pause since the last call.
+    pub fn take_pause_request(&self) -> bool {
+        self.pause_requested.swap(false, Relaxed)
+    }
+    pub fn respond(&self, request: &DapRequest, body: Result<Value, String>) {
+        self.send(match body {
+            Ok(body) => json!({
+                "type": "response",
+                "request_seq": request.seq,
+                "success": true,
+                "command": request.command,
+                "body": body,
+            }),
+            Err(message) => json!({
+                "type": "response",
+                "request_seq": request.seq,
+                "success": false,
+                "command": request.command,
+                "message": message,
+            }),
+        })
+    }
+    pub fn event(&self, event: &str, body: Value) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }))
+    }
+    fn send(&self, mut message: Value) {
+        message["seq"] = self.seq.fetch_add(1, Relaxed).into();
+        let body = message.to_string();
+        if let Some(client) = self.client.lock().unwrap().as_mut() {
+            let _ = write!(client, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+            let _ = client.flush();
+        }
+    }
+}
+
+impl Drop for DapServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Relaxed);
+        if let Some(client) = self.client.lock().unwrap().take() {
+            let _ = client.shutdown(Shutdown::Both);
+        }
+        // Wakes the listener up so it notices the shutdown.
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Largest message body accepted from a client. Nothing the debugger is sent comes close, a bigger length is
+/// treated as a broken client rather than allocated.
+const MAX_MESSAGE_LENGTH: usize = 4 * 1024 * 1024;
+
+/// Reads a single `Content-Length` framed message, returns `None` once the stream ends or the message is too large.
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.filter(|x| *x <= MAX_MESSAGE_LENGTH)?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_message, MAX_MESSAGE_LENGTH};
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let body = r#"{"type":"request"}"#;
+        let message = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        assert_eq!(
+            read_message(&mut message.as_bytes()).unwrap()["type"],
+            "request"
+        );
+        let message = format!("Content-Length: {}\r\n\r\n", MAX_MESSAGE_LENGTH + 1);
+        assert!(read_message(&mut message.as_bytes()).is_none());
+    }
+}
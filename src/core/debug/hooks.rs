@@ -0,0 +1,41 @@
+use std::ffi::CString;
+
+use r2g_mlua::ffi::{self, lua_Debug, lua_State};
+use r2g_mlua::prelude::*;
+
+use super::Debugger;
+use crate::core::{registry_keys, LuauState};
+
+/// Finds the state and the debugger a hook got called for.
+unsafe fn with_debugger(thread: *mut lua_State, f: impl FnOnce(&Debugger, &mut LuauState)) {
+    let key = CString::new(registry_keys::STATE_REGISTRYKEY).unwrap();
+    ffi::lua_rawgetfield(thread, ffi::LUA_REGISTRYINDEX, key.as_ptr());
+    let state = ffi::lua_tolightuserdata(thread, -1).cast::<LuauState>();
+    ffi::lua_pop(thread, 1);
+    let Some(state) = state.as_mut() else {
+        return;
+    };
+    if let Some(debugger) = state.get_debugger() {
+        f(debugger, state);
+    }
+}
+
+unsafe extern "C-unwind" fn debug_break(thread: *mut lua_State, _: *mut lua_Debug) {
+    with_debugger(thread, |debugger, state| debugger.on_break(state, thread));
+}
+
+/// Installs the breakpoint hook on a Luau state, or removes it.
+pub(crate) fn install_hooks(lua: &Lua, enabled: bool) {
+    // SAFETY: The callbacks are shared by every thread of the state, any of them can be used to reach them.
+    unsafe {
+        lua.exec_raw::<()>((), |state| {
+            let callbacks = ffi::lua_callbacks(state);
+            if enabled {
+                (*callbacks).debugbreak = Some(debug_break);
+            } else {
+                (*callbacks).debugbreak = None;
+            }
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+use std::ffi::{c_int, CStr, CString};
+use std::mem::zeroed;
+
+use r2g_mlua::ffi::{self, lua_Debug, lua_State};
+use r2g_mlua::prelude::*;
+
+use crate::core::{registry_keys, ManagedInstance};
+
+/// Something the client can expand while paused.
+#[derive(Clone, Debug)]
+pub(super) enum Container {
+    Locals(*mut lua_State, c_int),
+    Upvalues(*mut lua_State, c_int),
+    Table(LuaTable),
+    Instance(ManagedInstance),
+    Children(ManagedInstance),
+}
+
+#[derive(Debug)]
+pub(super) struct FrameInfo {
+    pub name: Option<String>,
+    /// The chunk name, `None` for C functions.
+    pub source: Option<String>,
+    pub line: c_int,
+}
+
+unsafe fn to_string(s: *const std::ffi::c_char) -> Option<String> {
+    (!s.is_null()).then(|| CStr::from_ptr(s).to_string_lossy().into_owned())
+}
+
+/// Describes the function running at `level` of `thread`, level zero being the innermost one.
+pub(super) unsafe fn frame_info(thread: *mut lua_State, level: c_int) -> Option<FrameInfo> {
+    let mut ar: lua_Debug = zeroed();
+    if ffi::lua_getinfo(thread, level, c"sln".as_ptr(), &mut ar) == 0 {
+        return None;
+    }
+    let is_c = to_string(ar.what).is_some_and(|x| x == "C");
+    Some(FrameInfo {
+        name: to_string(ar.name),
+        source: if is_c { None } else { to_string(ar.source) },
+        line: ar.currentline,
+    })
+}
+
+/// Pops the value on top of `thread`. Values can't be read from the stack of an arbitrary thread
+/// through mlua, so they take a detour through the registry.
+unsafe fn pop_value(lua: &Lua, thread: *mut lua_State) -> LuaValue {
+    let key = CString::new(registry_keys::DEBUG_VALUE_REGISTRYKEY).unwrap();
+    ffi::lua_setfield(thread, ffi::LUA_REGISTRYINDEX, key.as_ptr());
+    let value = lua
+        .named_registry_value::<LuaValue>(registry_keys::DEBUG_VALUE_REGISTRYKEY)
+        .unwrap_or(LuaValue::Nil);
+    let _ = lua.unset_named_registry_value(registry_keys::DEBUG_VALUE_REGISTRYKEY);
+    value
+}
+
+/// Returns the locals in scope at `level` of `thread`, leaving out compiler temporaries.
+pub(super) unsafe fn locals(
+    lua: &Lua,
+    thread: *mut lua_State,
+    level: c_int,
+) -> Vec<(String, LuaValue)> {
+    let mut vars = Vec::new();
+    if ffi::lua_checkstack(thread, 2) == 0 {
+        return vars;
+    }
+    for n in 1.. {
+        let name = ffi::lua_getlocal(thread, level, n);
+        if name.is_null() {
+            break;
+        }
+        let name = to_string(name).unwrap_or_default();
+        let value = pop_value(lua, thread);
+        if !name.starts_with('(') {
+            vars.push((name, value));
+        }
+    }
+    vars
+}
+
+/// Returns the upvalues of the function running at `level` of `thread`.
+pub(super) unsafe fn upvalues(
+    lua: &Lua,
+    thread: *mut lua_State,
+    level: c_int,
+) -> Vec<(String, LuaValue)> {
+    let mut vars = Vec::new();
+    let mut ar: lua_Debug = zeroed();
+    if ffi::lua_checkstack(thread, 3) == 0
+        || ffi::lua_getinfo(thread, level, c"f".as_ptr(), &mut ar) == 0
+    {
+        return vars;
+    }
+    for n in 1.. {
+        let name = ffi::lua_getupvalue(thread, -1, n);
+        if name.is_null() {
+            break;
+        }
+        let name = to_string(name)
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| format!("upvalue {}", n));
+        vars.push((name, pop_value(lua, thread)));
+    }
+    ffi::lua_pop(thread, 1);
+    vars
+}
+
+/// Returns the environment of the function running at `level` of `thread`.
+pub(super) unsafe fn environment(
+    lua: &Lua,
+    thread: *mut lua_State,
+    level: c_int,
+) -> Option<LuaTable> {
+    let mut ar: lua_Debug = zeroed();
+    if ffi::lua_checkstack(thread, 3) == 0
+        || ffi::lua_getinfo(thread, level, c"f".as_ptr(), &mut ar) == 0
+    {
+        return None;
+    }
+    ffi::lua_getfenv(thread, -1);
+    let env = pop_value(lua, thread);
+    ffi::lua_pop(thread, 1);
+    match env {
+        LuaValue::Table(env) => Some(env),
+        _ => None,
+    }
+}
+
+/// Formats a value for display, along with what it expands to.
+pub(super) fn describe(lua: &Lua, value: &LuaValue) -> (String, Option<Container>) {
+    match value {
+        LuaValue::String(s) => (format!("{:?}", s.to_string_lossy()), None),
+        LuaValue::Table(t) => (
+            value.to_string().unwrap_or_else(|_| "table".into()),
+            Some(Container::Table(t.clone())),
+        ),
+        LuaValue::UserData(_) => match ManagedInstance::from_lua(value.clone(), lua) {
+            Ok(instance) => (
+                format!("{} {}", instance.get_class_name(), instance.get_name()),
+                Some(Container::Instance(instance)),
+            ),
+            Err(_) => (
+                value.to_string().unwrap_or_else(|_| "userdata".into()),
+                None,
+            ),
+        },
+        _ => (
+            value
+                .to_string()
+                .unwrap_or_else(|_| value.type_name().into()),
+            None,
+        ),
+    }
+}
+
+/// Indexes a value the way scripts would, except that Instance members are read without security checks.
+pub(super) fn index(lua: &Lua, value: &LuaValue, key: &str) -> Result<LuaValue, String> {
+    match value {
+        LuaValue::Table(t) => t.get(key).map_err(|x| x.to_string()),
+        LuaValue::UserData(_) => ManagedInstance::from_lua(value.clone(), lua)
+            .map_err(|_| format!("attempt to index {} with '{}'", value.type_name(), key))?
+            .lua_get(lua, key.into())
+            .map_err(|x| x.to_string()),
+        _ => Err(format!(
+            "attempt to index {} with '{}'",
+            value.type_name(),
+            key
+        )),
+    }
+}
@@ -0,0 +1,8 @@
+mod dap;
+mod hooks;
+mod inspect;
+mod session;
+
+pub(crate) use hooks::install_hooks;
+pub(crate) use session::register_chunk;
+pub use session::{Debugger, StateDebugger};
@@ -0,0 +1,816 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{c_int, c_void};
+use std::sync::Mutex;
+
+use r2g_mlua::ffi::{self, lua_State};
+use r2g_mlua::prelude::*;
+use serde_json::{json, Value};
+
+use super::dap::{DapMessage, DapRequest, DapServer};
+use super::inspect::{self, Container};
+use crate::core::{get_class_registry, registry_keys, LuauState, Trc};
+
+const NOT_PAUSED: &str = "The VM is not paused";
+// Tables with more entries than this are cut short in the variables view.
+const MAX_TABLE_ENTRIES: usize = 1000;
+
+#[derive(Debug)]
+struct Breakpoint {
+    id: i64,
+    line: c_int,
+    /// The line the breakpoint actually landed on, once a chunk with that name got loaded.
+    verified: Option<c_int>,
+}
+
+impl Breakpoint {
+    fn to_json(&self) -> Value {
+        match self.verified {
+            Some(line) => json!({ "id": self.id, "verified": true, "line": line }),
+            None => json!({
+                "id": self.id,
+                "verified": false,
+                "line": self.line,
+                "message": "The script hasn't been loaded yet",
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum StepMode {
+    In,
+    Over,
+    Out,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct StepState {
+    thread: *mut lua_State,
+    mode: StepMode,
+    depth: c_int,
+    line: c_int,
+}
+
+#[derive(Debug, Default)]
+struct DebugSession {
+    breakpoints: HashMap<String, Vec<Breakpoint>>,
+    /// Bumped whenever the breakpoints change, states resynchronize theirs when it differs.
+    generation: u64,
+    next_breakpoint_id: i64,
+    /// Chunk names handed out as source references, a reference being the index plus one.
+    sources: Vec<String>,
+    step: Option<StepState>,
+}
+
+impl DebugSession {
+    fn reset(&mut self) {
+        self.breakpoints.clear();
+        self.generation += 1;
+        self.step = None;
+    }
+    fn source_reference(&mut self, chunk: &str) -> usize {
+        match self.sources.iter().position(|x| x == chunk) {
+            Some(i) => i + 1,
+            None => {
+                self.sources.push(chunk.into());
+                self.sources.len()
+            }
+        }
+    }
+}
+
+/// Debugger bookkeeping of a single Luau state.
+#[derive(Debug, Default)]
+pub struct StateDebugger {
+    attached: bool,
+    /// Breakpoint lines set on the loaded functions of each chunk.
+    applied: HashMap<String, Vec<c_int>>,
+    generation: u64,
+    /// Whether every line of the loaded chunks holds a breakpoint, which is how steps get interrupted.
+    stepping: bool,
+}
+
+impl StateDebugger {
+    #[inline]
+    pub fn is_attached(&self) -> bool {
+        self.attached
+    }
+    #[inline]
+    pub(crate) fn set_attached(&mut self, attached: bool) {
+        self.attached = attached;
+    }
+}
+
+/// Frames and containers handed out while paused, their ids are indices into these.
+struct PausedState {
+    thread: *mut lua_State,
+    frames: Vec<(*mut lua_State, c_int)>,
+    variables: Vec<Container>,
+}
+
+impl PausedState {
+    fn frame_id(&mut self, thread: *mut lua_State, level: c_int) -> usize {
+        match self.frames.iter().position(|x| *x == (thread, level)) {
+            Some(i) => i,
+            None => {
+                self.frames.push((thread, level));
+                self.frames.len() - 1
+            }
+        }
+    }
+    fn reference(&mut self, container: Option<Container>) -> usize {
+        match container {
+            Some(container) => {
+                self.variables.push(container);
+                self.variables.len()
+            }
+            None => 0,
+        }
+    }
+    fn variable(&mut self, lua: &Lua, name: String, value: LuaResult<LuaValue>) -> Value {
+        match value {
+            Ok(value) => {
+                let (text, container) = inspect::describe(lua, &value);
+                json!({
+                    "name": name,
+                    "value": text,
+                    "type": value.type_name(),
+                    "variablesReference": self.reference(container),
+                })
+            }
+            Err(err) => json!({
+                "name": name,
+                "value": format!("<error: {}>", err),
+                "variablesReference": 0,
+            }),
+        }
+    }
+}
+
+/// A Debug Adapter Protocol server driving the Luau debug hooks of every state of a VM.
+///
+/// Clients attach over TCP, VS Code does so with a launch configuration pointing `debugServer` at the port.
+/// Breakpoints are set by chunk name, which is `<script at FullName>` for scripts compiled in debug mode.
+/// While paused, the hook blocks the VM thread and serves the client's requests until it resumes. The VM runs on
+/// the Godot main thread, so the whole game and the editor stop responding meanwhile. There is no timeout, as a
+/// pause can last as long as the user wants: resuming, or the client disconnecting, is what releases it.
+#[derive(Debug)]
+pub struct Debugger {
+    server: DapServer,
+    session: Mutex<DebugSession>,
+}
+
+impl Debugger {
+    pub fn new(port: u16) -> std::io::Result<Debugger> {
+        Ok(Debugger {
+            server: DapServer::bind(port)?,
+            session: Mutex::new(DebugSession {
+                generation: 1,
+                ..Default::default()
+            }),
+        })
+    }
+    #[inline]
+    pub fn port(&self) -> u16 {
+        self.server.port()
+    }
+    pub fn is_connected(&self) -> bool {
+        self.server.is_connected()
+    }
+    /// Handles the requests received since the last frame and brings every state's breakpoints up to date.
+    /// Called by the scheduler between frames, requests made there are answered against `main`.
+    pub(crate) fn poll(&self, main: &Trc<LuauState>, states: &[Trc<LuauState>]) {
+        let Ok(mut session) = self.session.try_lock() else {
+            return;
+        };
+        while let Some(message) = self.server.try_recv() {
+            match message {
+                DapMessage::Connected => {}
+                DapMessage::Request(request) => {
+                    self.handle(&mut session, &mut main.write(), None, &request);
+                }
+                DapMessage::Disconnected => session.reset(),
+            }
+        }
+        for state in states {
+            let mut state = state.write();
+            if session.step.is_none() {
+                self.set_stepping(&mut session, &mut state, false);
+            }
+            self.sync_breakpoints(&mut session, &mut state, true);
+        }
+    }
+    /// Removes every breakpoint and the hooks of every state, before the debugger goes away.
+    pub(crate) fn detach(&self, states: &[Trc<LuauState>]) {
+        let mut session = self.session.lock().unwrap();
+        session.reset();
+        for state in states {
+            let mut state = state.write();
+            self.set_stepping(&mut session, &mut state, false);
+            self.sync_breakpoints(&mut session, &mut state, false);
+            state.set_debugger_attached(false);
+        }
+    }
+    /// Called from the interrupt of a state, pauses `thread` right away if the client asked to.
+    pub(crate) fn check_pause_request(&self, state: &mut LuauState, thread: *mut lua_State) {
+        if !self.server.take_pause_request() {
+            return;
+        }
+        let Ok(mut session) = self.session.try_lock() else {
+            return;
+        };
+        unsafe { self.pause(&mut session, state, thread, "pause") };
+    }
+    /// Called when a script chunk gets compiled in debug mode, sets the breakpoints already known for it.
+    pub(crate) fn chunk_loaded(&self, state: &mut LuauState, chunk: &str, func: &LuaFunction) {
+        let Ok(mut session) = self.session.try_lock() else {
+            return;
+        };
+        let Some(breakpoints) = session.breakpoints.get_mut(chunk) else {
+            return;
+        };
+        let lua = state.get_lua().clone();
+        for breakpoint in breakpoints.iter_mut() {
+            if let Some(line) = set_breakpoint(&lua, func, breakpoint.line, true) {
+                if breakpoint.verified.is_none() {
+                    breakpoint.verified = Some(line);
+                    self.server.event(
+                        "breakpoint",
+                        json!({ "reason": "changed", "breakpoint": breakpoint.to_json() }),
+                    );
+                }
+            }
+        }
+        let applied = state
+            .get_debugger_state_mut()
+            .applied
+            .entry(chunk.into())
+            .or_default();
+        for breakpoint in breakpoints.iter() {
+            if !applied.contains(&breakpoint.line) {
+                applied.push(breakpoint.line);
+            }
+        }
+    }
+    pub(super) unsafe fn on_break(&self, state: &mut LuauState, thread: *mut lua_State) {
+        let Ok(mut session) = self.session.try_lock() else {
+            return;
+        };
+        if !state.get_debugger_state_mut().stepping {
+            self.pause(&mut session, state, thread, "breakpoint");
+            return;
+        }
+        let Some(info) = inspect::frame_info(thread, 0) else {
+            return;
+        };
+        let stepped = session.step.is_some_and(|step| {
+            if step.thread != thread {
+                // The stepping thread went back to the scheduler, stop in whatever runs next.
+                return ffi::lua_stackdepth(step.thread) == 0;
+            }
+            let depth = ffi::lua_stackdepth(thread);
+            match step.mode {
+                StepMode::In => depth != step.depth || info.line != step.line,
+                StepMode::Over => {
+                    depth < step.depth || (depth == step.depth && info.line != step.line)
+                }
+                StepMode::Out => depth < step.depth,
+            }
+        });
+        if stepped {
+            self.pause(&mut session, state, thread, "step");
+        } else if info
+            .source
+            .and_then(|x| session.breakpoints.get(&x))
+            .is_some_and(|x| x.iter().any(|x| x.verified == Some(info.line)))
+        {
+            self.pause(&mut session, state, thread, "breakpoint");
+        }
+    }
+    /// Blocks the VM thread, serving the client until it resumes or goes away.
+    unsafe fn pause(
+        &self,
+        session: &mut DebugSession,
+        state: &mut LuauState,
+        thread: *mut lua_State,
+        reason: &str,
+    ) {
+        session.step = None;
+        self.set_stepping(session, state, false);
+        self.server.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": thread as i64, "allThreadsStopped": true }),
+        );
+        let mut paused = PausedState {
+            thread,
+            frames: Vec::new(),
+            variables: Vec::new(),
+        };
+        while let Some(message) = self.server.recv() {
+            match message {
+                DapMessage::Connected => {}
+                DapMessage::Request(request) => {
+                    if self.handle(session, state, Some(&mut paused), &request) {
+                        break;
+                    }
+                }
+                DapMessage::Disconnected => {
+                    session.reset();
+                    self.sync_breakpoints(session, state, false);
+                    break;
+                }
+            }
+        }
+        self.server.take_pause_request();
+        let stepping = session.step.is_some();
+        self.set_stepping(session, state, stepping);
+        // The time spent paused doesn't count against the scripts.
+        (*(*state.get_vm_ptr()).access()).watchdog_resume();
+    }
+    /// Handles a request, returns true if execution should resume.
+    fn handle(
+        &self,
+        session: &mut DebugSession,
+        state: &mut LuauState,
+        mut paused: Option<&mut PausedState>,
+        request: &DapRequest,
+    ) -> bool {
+        let lua = state.get_lua().clone();
+        let args = &request.arguments;
+        let mut resume = false;
+        let result = match request.command.as_str() {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsEvaluateForHovers": true,
+            })),
+            "attach" | "launch" | "configurationDone" | "setExceptionBreakpoints" | "pause" => {
+                Ok(json!({}))
+            }
+            "setBreakpoints" => self.set_breakpoints(session, state, args),
+            "threads" => Ok(threads(state, paused.as_deref().map(|x| x.thread))),
+            "source" => source(&lua, session, args),
+            "continue" => {
+                session.step = None;
+                resume = true;
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" | "stepIn" | "stepOut" => match paused.as_deref() {
+                Some(paused) => resolve_thread(state, paused, args).map(|thread| {
+                    let mode = match request.command.as_str() {
+                        "next" => StepMode::Over,
+                        "stepIn" => StepMode::In,
+                        _ => StepMode::Out,
+                    };
+                    session.step = Some(StepState {
+                        thread,
+                        mode,
+                        depth: unsafe { ffi::lua_stackdepth(thread) },
+                        line: unsafe { inspect::frame_info(thread, 0) }.map_or(-1, |x| x.line),
+                    });
+                    resume = true;
+                    json!({})
+                }),
+                None => Err(NOT_PAUSED.into()),
+            },
+            "disconnect" => {
+                session.reset();
+                self.sync_breakpoints(session, state, false);
+                resume = true;
+                Ok(json!({}))
+            }
+            "stackTrace" => match paused.as_deref_mut() {
+                Some(paused) => stack_trace(session, state, paused, args),
+                None => Err(NOT_PAUSED.into()),
+            },
+            "scopes" => match paused.as_deref_mut() {
+                Some(paused) => scopes(paused, args),
+                None => Err(NOT_PAUSED.into()),
+            },
+            "variables" => match paused.as_deref_mut() {
+                Some(paused) => variables(&lua, paused, args),
+                None => Err(NOT_PAUSED.into()),
+            },
+            "evaluate" => match paused {
+                Some(paused) => evaluate(&lua, paused, args),
+                None => Err(NOT_PAUSED.into()),
+            },
+            _ => Err(format!("Unsupported request '{}'", request.command)),
+        };
+        self.server.respond(request, result);
+        if request.command == "initialize" {
+            self.server.event("initialized", json!({}));
+        }
+        resume
+    }
+    fn set_breakpoints(
+        &self,
+        session: &mut DebugSession,
+        state: &mut LuauState,
+        args: &Value,
+    ) -> Result<Value, String> {
+        let source = &args["source"];
+        let chunk = source["sourceReference"]
+            .as_u64()
+            .filter(|x| *x != 0)
+            .and_then(|x| session.sources.get(x as usize - 1).cloned())
+            .or_else(|| source["name"].as_str().map(String::from))
+            .or_else(|| source["path"].as_str().map(String::from))
+            .ok_or("The source has no name")?;
+        let lines: Vec<c_int> = match args["breakpoints"].as_array() {
+            Some(breakpoints) => breakpoints
+                .iter()
+                .filter_map(|x| x["line"].as_i64())
+                .map(|x| x as c_int)
+                .collect(),
+            None => args["lines"]
+                .as_array()
+                .map(|x| x.iter().filter_map(|x| x.as_i64()).map(|x| x as c_int))
+                .into_iter()
+                .flatten()
+                .collect(),
+        };
+        let breakpoints: Vec<Breakpoint> = lines
+            .into_iter()
+            .map(|line| {
+                session.next_breakpoint_id += 1;
+                Breakpoint {
+                    id: session.next_breakpoint_id,
+                    line,
+                    verified: None,
+                }
+            })
+            .collect();
+        if breakpoints.is_empty() {
+            session.breakpoints.remove(&chunk);
+        } else {
+            session.breakpoints.insert(chunk.clone(), breakpoints);
+        }
+        session.generation += 1;
+        self.sync_breakpoints(session, state, false);
+        let breakpoints: Vec<Value> = session
+            .breakpoints
+            .get(&chunk)
+            .map(|x| x.iter().map(Breakpoint::to_json).collect())
+            .unwrap_or_default();
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+    /// Sets or clears a breakpoint on every line of the chunks loaded in a state.
+    /// Clearing them also clears the session's breakpoints, which get set again right after.
+    fn set_stepping(&self, session: &mut DebugSession, state: &mut LuauState, stepping: bool) {
+        if state.get_debugger_state_mut().stepping == stepping {
+            return;
+        }
+        let lua = state.get_lua().clone();
+        let sources =
+            lua.named_registry_value::<Option<LuaTable>>(registry_keys::DEBUG_SOURCES_REGISTRYKEY);
+        if let Ok(Some(sources)) = sources {
+            for (chunk, functions) in chunk_functions(&lua) {
+                let Ok(Some(source)) = sources.raw_get::<Option<LuaString>>(chunk) else {
+                    continue;
+                };
+                let lines = source.as_bytes().split(|x| *x == b'\n').count() as c_int;
+                for func in &functions {
+                    for line in 1..=lines {
+                        set_breakpoint(&lua, func, line, stepping);
+                    }
+                }
+            }
+        }
+        let debugger = state.get_debugger_state_mut();
+        debugger.stepping = stepping;
+        if !stepping {
+            debugger.applied.clear();
+            debugger.generation = 0;
+            self.sync_breakpoints(session, state, false);
+        }
+    }
+    /// Brings the breakpoints set in a state's functions up to date with the session.
+    fn sync_breakpoints(&self, session: &mut DebugSession, state: &mut LuauState, notify: bool) {
+        if state.get_debugger_state_mut().generation == session.generation {
+            return;
+        }
+        let lua = state.get_lua().clone();
+        let functions = chunk_functions(&lua);
+        let debugger = state.get_debugger_state_mut();
+        let chunks: HashSet<String> = debugger
+            .applied
+            .keys()
+            .chain(session.breakpoints.keys())
+            .cloned()
+            .collect();
+        for chunk in chunks {
+            let old = debugger.applied.remove(&chunk).unwrap_or_default();
+            let Some(functions) = functions.get(&chunk) else {
+                continue;
+            };
+            for func in functions {
+                for line in &old {
+                    set_breakpoint(&lua, func, *line, false);
+                }
+            }
+            let Some(breakpoints) = session.breakpoints.get_mut(&chunk) else {
+                continue;
+            };
+            for breakpoint in breakpoints.iter_mut() {
+                for func in functions {
+                    let Some(line) = set_breakpoint(&lua, func, breakpoint.line, true) else {
+                        continue;
+                    };
+                    if breakpoint.verified.is_none() {
+                        breakpoint.verified = Some(line);
+                        if notify {
+                            self.server.event(
+                                "breakpoint",
+                                json!({ "reason": "changed", "breakpoint": breakpoint.to_json() }),
+                            );
+                        }
+                    }
+                }
+            }
+            debugger
+                .applied
+                .insert(chunk, breakpoints.iter().map(|x| x.line).collect());
+        }
+        debugger.generation = session.generation;
+    }
+}
+
+/// Sets or clears a breakpoint in a function and the functions nested in it.
+/// Returns the line it landed on, breakpoints move to the next line holding code.
+fn set_breakpoint(lua: &Lua, func: &LuaFunction, line: c_int, enabled: bool) -> Option<c_int> {
+    let mut target = -1;
+    // SAFETY: The function is the only value on the stack.
+    let _ = unsafe {
+        lua.exec_raw::<()>(func.clone(), |state| {
+            target = ffi::lua_breakpoint(state, -1, line, enabled as c_int);
+        })
+    };
+    (target >= 0).then_some(target)
+}
+
+/// Records a chunk compiled in debug mode, so breakpoints can be set in it and its source shown.
+pub(crate) fn register_chunk(
+    state: &mut LuauState,
+    chunk: &str,
+    source: &str,
+    func: &LuaFunction,
+) -> LuaResult<()> {
+    let lua = state.get_lua().clone();
+    let chunks = match lua
+        .named_registry_value::<Option<LuaTable>>(registry_keys::DEBUG_CHUNKS_REGISTRYKEY)?
+    {
+        Some(chunks) => chunks,
+        None => {
+            // Weak keys, registering a chunk doesn't keep its script alive.
+            let chunks = lua.create_table()?;
+            let metatable = lua.create_table()?;
+            metatable.raw_set("__mode", "k")?;
+            chunks.set_metatable(Some(metatable));
+            lua.set_named_registry_value(registry_keys::DEBUG_CHUNKS_REGISTRYKEY, &chunks)?;
+            chunks
+        }
+    };
+    chunks.raw_set(func.clone(), chunk)?;
+    let sources = match lua
+        .named_registry_value::<Option<LuaTable>>(registry_keys::DEBUG_SOURCES_REGISTRYKEY)?
+    {
+        Some(sources) => sources,
+        None => {
+            let sources = lua.create_table()?;
+            lua.set_named_registry_value(registry_keys::DEBUG_SOURCES_REGISTRYKEY, &sources)?;
+            sources
+        }
+    };
+    sources.raw_set(chunk, source)?;
+    if let Some(debugger) = state.get_debugger() {
+        debugger.chunk_loaded(state, chunk, func);
+    }
+    Ok(())
+}
+
+fn chunk_functions(lua: &Lua) -> HashMap<String, Vec<LuaFunction>> {
+    let mut functions: HashMap<String, Vec<LuaFunction>> = HashMap::new();
+    let Ok(Some(chunks)) =
+        lua.named_registry_value::<Option<LuaTable>>(registry_keys::DEBUG_CHUNKS_REGISTRYKEY)
+    else {
+        return functions;
+    };
+    for (func, chunk) in chunks.pairs::<LuaFunction, String>().flatten() {
+        functions.entry(chunk).or_default().push(func);
+    }
+    functions
+}
+
+fn thread_json(state: &LuauState, thread: *mut lua_State) -> Value {
+    let name = state
+        .get_thread_identity_pointer(thread as *const c_void)
+        .and_then(|x| x.script.as_ref()?.upgrade())
+        .and_then(|x| x.get_full_name().ok())
+        .unwrap_or_else(|| format!("thread 0x{:x}", thread as usize));
+    json!({ "id": thread as i64, "name": name })
+}
+
+/// Lists the scheduler's threads that are in the middle of running something, the paused thread first.
+fn threads(state: &LuauState, paused: Option<*mut lua_State>) -> Value {
+    let mut others: Vec<*mut lua_State> = state
+        .get_thread_pointers()
+        .map(|x| x as *mut lua_State)
+        .filter(|x| Some(*x) != paused && unsafe { ffi::lua_stackdepth(*x) } > 0)
+        .collect();
+    others.sort();
+    let threads: Vec<Value> = paused
+        .into_iter()
+        .chain(others)
+        .map(|x| thread_json(state, x))
+        .collect();
+    json!({ "threads": threads })
+}
+
+fn resolve_thread(
+    state: &LuauState,
+    paused: &PausedState,
+    args: &Value,
+) -> Result<*mut lua_State, String> {
+    let id = args["threadId"].as_i64().ok_or("Missing thread id")?;
+    let thread = id as *mut lua_State;
+    if thread == paused.thread
+        || state
+            .get_thread_identity_pointer(thread as *const c_void)
+            .is_some()
+    {
+        Ok(thread)
+    } else {
+        Err(format!("Unknown thread {}", id))
+    }
+}
+
+fn stack_trace(
+    session: &mut DebugSession,
+    state: &LuauState,
+    paused: &mut PausedState,
+    args: &Value,
+) -> Result<Value, String> {
+    let thread = resolve_thread(state, paused, args)?;
+    let mut frames = Vec::new();
+    let mut level = 0;
+    while let Some(info) = unsafe { inspect::frame_info(thread, level) } {
+        let id = paused.frame_id(thread, level);
+        frames.push(match info.source {
+            Some(chunk) => json!({
+                "id": id,
+                "name": info.name.as_deref().unwrap_or("<anonymous>"),
+                "line": info.line.max(0),
+                "column": 1,
+                "source": { "name": chunk, "sourceReference": session.source_reference(&chunk) },
+            }),
+            None => json!({
+                "id": id,
+                "name": format!("[C] {}", info.name.as_deref().unwrap_or("?")),
+                "line": 0,
+                "column": 0,
+                "presentationHint": "subtle",
+            }),
+        });
+        level += 1;
+    }
+    let total = frames.len();
+    let start = args["startFrame"].as_u64().unwrap_or(0) as usize;
+    let count = args["levels"]
+        .as_u64()
+        .filter(|x| *x != 0)
+        .map_or(total, |x| x as usize);
+    let frames: Vec<Value> = frames.into_iter().skip(start).take(count).collect();
+    Ok(json!({ "stackFrames": frames, "totalFrames": total }))
+}
+
+fn scopes(paused: &mut PausedState, args: &Value) -> Result<Value, String> {
+    let &(thread, level) = args["frameId"]
+        .as_u64()
+        .and_then(|x| paused.frames.get(x as usize))
+        .ok_or("Unknown frame")?;
+    let locals = paused.reference(Some(Container::Locals(thread, level)));
+    let upvalues = paused.reference(Some(Container::Upvalues(thread, level)));
+    Ok(json!({ "scopes": [
+        { "name": "Locals", "presentationHint": "locals", "variablesReference": locals, "expensive": false },
+        { "name": "Upvalues", "variablesReference": upvalues, "expensive": false },
+    ]}))
+}
+
+fn variables(lua: &Lua, paused: &mut PausedState, args: &Value) -> Result<Value, String> {
+    let container = args["variablesReference"]
+        .as_u64()
+        .filter(|x| *x != 0)
+        .and_then(|x| paused.variables.get(x as usize - 1))
+        .cloned()
+        .ok_or("Unknown variables reference")?;
+    let mut vars = Vec::new();
+    match container {
+        Container::Locals(thread, level) => {
+            for (name, value) in unsafe { inspect::locals(lua, thread, level) } {
+                vars.push(paused.variable(lua, name, Ok(value)));
+            }
+        }
+        Container::Upvalues(thread, level) => {
+            for (name, value) in unsafe { inspect::upvalues(lua, thread, level) } {
+                vars.push(paused.variable(lua, name, Ok(value)));
+            }
+        }
+        Container::Table(table) => {
+            for pair in table.pairs::<LuaValue, LuaValue>().take(MAX_TABLE_ENTRIES) {
+                let (key, value) = pair.map_err(|x| x.to_string())?;
+                let name = match &key {
+                    LuaValue::String(s) => s.to_string_lossy(),
+                    key => format!("[{}]", inspect::describe(lua, key).0),
+                };
+                vars.push(paused.variable(lua, name, Ok(value)));
+            }
+        }
+        Container::Instance(instance) => {
+            let properties: Vec<String> = get_class_registry()
+                .get_properties(instance.get_class_name())
+                .into_iter()
+                .map(|x| x.name.clone())
+                .collect();
+            for name in properties {
+                let value = instance.lua_get(lua, name.clone());
+                vars.push(paused.variable(lua, name, value));
+            }
+            let children = instance.get_children().unwrap_or_default();
+            if !children.is_empty() {
+                let reference = paused.reference(Some(Container::Children(instance)));
+                vars.push(json!({
+                    "name": "Children",
+                    "value": format!("{} children", children.len()),
+                    "variablesReference": reference,
+                    "presentationHint": { "kind": "virtual" },
+                }));
+            }
+        }
+        Container::Children(instance) => {
+            for child in instance.get_children().unwrap_or_default() {
+                let name = child.get_name();
+                vars.push(paused.variable(lua, name, child.into_lua(lua)));
+            }
+        }
+    }
+    Ok(json!({ "variables": vars }))
+}
+
+/// Evaluates a variable name followed by field accesses, like `script.Parent.Name`.
+/// Names are looked up in the locals, then the upvalues, then the environment of the frame.
+fn evaluate(lua: &Lua, paused: &mut PausedState, args: &Value) -> Result<Value, String> {
+    let expression = args["expression"].as_str().unwrap_or_default().trim();
+    let path: Vec<&str> = expression.split('.').map(str::trim).collect();
+    let is_name = |x: &&str| {
+        x.chars()
+            .next()
+            .is_some_and(|x| x.is_alphabetic() || x == '_')
+            && x.chars().all(|x| x.is_alphanumeric() || x == '_')
+    };
+    if !path.iter().all(is_name) {
+        return Err("Only variable names and field accesses can be evaluated".into());
+    }
+    let (thread, level) = args["frameId"]
+        .as_u64()
+        .and_then(|x| paused.frames.get(x as usize).copied())
+        .unwrap_or((paused.thread, 0));
+    let root = path[0];
+    let local = unsafe { inspect::locals(lua, thread, level) }
+        .into_iter()
+        .rev()
+        .find(|(name, _)| name == root);
+    let mut value = match local.or_else(|| {
+        unsafe { inspect::upvalues(lua, thread, level) }
+            .into_iter()
+            .find(|(name, _)| name == root)
+    }) {
+        Some((_, value)) => value,
+        None => unsafe { inspect::environment(lua, thread, level) }
+            .unwrap_or_else(|| lua.globals())
+            .get::<LuaValue>(root)
+            .map_err(|x| x.to_string())?,
+    };
+    for key in &path[1..] {
+        value = inspect::index(lua, &value, key)?;
+    }
+    let (text, container) = inspect::describe(lua, &value);
+    Ok(json!({
+        "result": text,
+        "type": value.type_name(),
+        "variablesReference": paused.reference(container),
+    }))
+}
+
+fn source(lua: &Lua, session: &DebugSession, args: &Value) -> Result<Value, String> {
+    let chunk = args["sourceReference"]
+        .as_u64()
+        .filter(|x| *x != 0)
+        .and_then(|x| session.sources.get(x as usize - 1))
+        .ok_or("Unknown source")?;
+    lua.named_registry_value::<Option<LuaTable>>(registry_keys::DEBUG_SOURCES_REGISTRYKEY)
+        .ok()
+        .flatten()
+        .and_then(|x| x.raw_get::<Option<String>>(chunk.as_str()).ok().flatten())
+        .map(|content| json!({ "content": content, "mimeType": "text/x-luau" }))
+        .ok_or_else(|| format!("The source of {} isn't available", chunk))
+}
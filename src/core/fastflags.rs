@@ -30,8 +30,21 @@ pub enum FastFlag {
     IsClient,        // bool
     IsStudio,        // bool
     DebugMode,       // bool
+    AnalyzeOnLoad,   // bool, warns about scripts asking for type checking as they load
 
     SignalBehavior, // int
+    MemoryLimitMb,  // int
+    DebuggerPort,   // int
+    CloseTimeout,   // float, seconds the BindToClose functions get to finish on shutdown
+
+    NativeCodegen,            // int, 0: scripts asking for it, 1: every script, 2: none
+    OptimizationLevelDebug,   // int
+    DebugLevelDebug,          // int
+    OptimizationLevelRelease, // int
+    DebugLevelRelease,        // int
+    OptimizationLevelNative,  // int
+    DebugLevelNative,         // int
+    BytecodeCacheDirectory,   // string, empty keeps compiled scripts in memory only
 }
 union FlagInternal {
     bool_value: bool,
@@ -44,9 +57,10 @@ impl FlagInternal {
     #[inline(always)]
     fn get_string(&self, flag: FastFlag) -> String {
         match flag {
-            FastFlag::JobId | FastFlag::PrivateServerId | FastFlag::GameName => unsafe {
-                String::clone(&self.str_value)
-            },
+            FastFlag::JobId
+            | FastFlag::PrivateServerId
+            | FastFlag::GameName
+            | FastFlag::BytecodeCacheDirectory => unsafe { String::clone(&self.str_value) },
             _ => panic!("Invalid flag"),
         }
     }
@@ -59,14 +73,25 @@ impl FlagInternal {
             | FastFlag::PlaceId
             | FastFlag::PlaceVersion
             | FastFlag::PrivateServerOwnerId
-            | FastFlag::SignalBehavior => unsafe { self.int_value },
+            | FastFlag::SignalBehavior
+            | FastFlag::MemoryLimitMb
+            | FastFlag::DebuggerPort
+            | FastFlag::NativeCodegen
+            | FastFlag::OptimizationLevelDebug
+            | FastFlag::DebugLevelDebug
+            | FastFlag::OptimizationLevelRelease
+            | FastFlag::DebugLevelRelease
+            | FastFlag::OptimizationLevelNative
+            | FastFlag::DebugLevelNative => unsafe { self.int_value },
             _ => panic!("Invalid flag"),
         }
     }
     #[inline(always)]
     const fn get_float(&self, flag: FastFlag) -> f64 {
         match flag {
-            FastFlag::TargetFPS | FastFlag::TargetPhysicsFPS => unsafe { self.float_value },
+            FastFlag::TargetFPS | FastFlag::TargetPhysicsFPS | FastFlag::CloseTimeout => unsafe {
+                self.float_value
+            },
             _ => panic!("Invalid flag"),
         }
     }
@@ -77,14 +102,18 @@ impl FlagInternal {
             | FastFlag::GlobalsReadonly
             | FastFlag::IsClient
             | FastFlag::IsStudio
-            | FastFlag::DebugMode => unsafe { self.bool_value },
+            | FastFlag::DebugMode
+            | FastFlag::AnalyzeOnLoad => unsafe { self.bool_value },
             _ => panic!("Invalid flag"),
         }
     }
     #[inline(always)]
     fn set_string(&mut self, flag: FastFlag, v: String) {
         match flag {
-            FastFlag::JobId | FastFlag::PrivateServerId | FastFlag::GameName => unsafe {
+            FastFlag::JobId
+            | FastFlag::PrivateServerId
+            | FastFlag::GameName
+            | FastFlag::BytecodeCacheDirectory => unsafe {
                 ManuallyDrop::drop(&mut self.str_value);
                 self.str_value = ManuallyDrop::new(v);
             },
@@ -101,14 +130,25 @@ impl FlagInternal {
             | FastFlag::PlaceId
             | FastFlag::PlaceVersion
             | FastFlag::PrivateServerOwnerId
-            | FastFlag::SignalBehavior => self.int_value = v,
+            | FastFlag::SignalBehavior
+            | FastFlag::MemoryLimitMb
+            | FastFlag::DebuggerPort
+            | FastFlag::NativeCodegen
+            | FastFlag::OptimizationLevelDebug
+            | FastFlag::DebugLevelDebug
+            | FastFlag::OptimizationLevelRelease
+            | FastFlag::DebugLevelRelease
+            | FastFlag::OptimizationLevelNative
+            | FastFlag::DebugLevelNative => self.int_value = v,
             _ => panic!("Invalid flag"),
         }
     }
     #[inline(always)]
     const fn set_float(&mut self, flag: FastFlag, v: f64) {
         match flag {
-            FastFlag::TargetFPS | FastFlag::TargetPhysicsFPS => self.float_value = v,
+            FastFlag::TargetFPS | FastFlag::TargetPhysicsFPS | FastFlag::CloseTimeout => {
+                self.float_value = v
+            }
             _ => panic!("Invalid flag"),
         }
     }
@@ -119,13 +159,17 @@ impl FlagInternal {
             | FastFlag::GlobalsReadonly
             | FastFlag::IsClient
             | FastFlag::IsStudio
-            | FastFlag::DebugMode => self.bool_value = v,
+            | FastFlag::DebugMode
+            | FastFlag::AnalyzeOnLoad => self.bool_value = v,
             _ => panic!("Invalid flag"),
         }
     }
     fn get_value(&self, flag: FastFlag) -> FastFlagValue {
         match flag {
-            FastFlag::JobId | FastFlag::GameName | FastFlag::PrivateServerId => unsafe {
+            FastFlag::JobId
+            | FastFlag::GameName
+            | FastFlag::PrivateServerId
+            | FastFlag::BytecodeCacheDirectory => unsafe {
                 FastFlagValue::String(String::clone(&self.str_value))
             },
             FastFlag::MaxPhysicsStepsPerFrame
@@ -135,14 +179,24 @@ impl FlagInternal {
             | FastFlag::PlaceId
             | FastFlag::PlaceVersion
             | FastFlag::PrivateServerOwnerId
-            | FastFlag::SignalBehavior => unsafe { FastFlagValue::Int(self.int_value) },
-            FastFlag::TargetFPS | FastFlag::TargetPhysicsFPS => unsafe {
+            | FastFlag::SignalBehavior
+            | FastFlag::MemoryLimitMb
+            | FastFlag::DebuggerPort
+            | FastFlag::NativeCodegen
+            | FastFlag::OptimizationLevelDebug
+            | FastFlag::DebugLevelDebug
+            | FastFlag::OptimizationLevelRelease
+            | FastFlag::DebugLevelRelease
+            | FastFlag::OptimizationLevelNative
+            | FastFlag::DebugLevelNative => unsafe { FastFlagValue::Int(self.int_value) },
+            FastFlag::TargetFPS | FastFlag::TargetPhysicsFPS | FastFlag::CloseTimeout => unsafe {
                 FastFlagValue::Float(self.float_value)
             },
             FastFlag::VSync
             | FastFlag::GlobalsReadonly
             | FastFlag::IsClient
             | FastFlag::DebugMode
+            | FastFlag::AnalyzeOnLoad
             | FastFlag::IsStudio => unsafe { FastFlagValue::Bool(self.bool_value) },
         }
     }
@@ -176,8 +230,23 @@ impl FastFlag {
             Self::IsClient => FlagInternal { bool_value: true },
             Self::IsStudio => FlagInternal { bool_value: false },
             Self::DebugMode => FlagInternal { bool_value: true },
+            Self::AnalyzeOnLoad => FlagInternal { bool_value: false },
 
             Self::SignalBehavior => FlagInternal { int_value: 0 },
+            Self::MemoryLimitMb => FlagInternal { int_value: 1024 },
+            Self::DebuggerPort => FlagInternal { int_value: 0 },
+            Self::CloseTimeout => FlagInternal { float_value: 30.0 },
+
+            Self::NativeCodegen => FlagInternal { int_value: 0 },
+            Self::OptimizationLevelDebug => FlagInternal { int_value: 1 },
+            Self::DebugLevelDebug => FlagInternal { int_value: 2 },
+            Self::OptimizationLevelRelease => FlagInternal { int_value: 2 },
+            Self::DebugLevelRelease => FlagInternal { int_value: 1 },
+            Self::OptimizationLevelNative => FlagInternal { int_value: 2 },
+            Self::DebugLevelNative => FlagInternal { int_value: 1 },
+            Self::BytecodeCacheDirectory => FlagInternal {
+                str_value: ManuallyDrop::new(String::new()),
+            },
         }
     }
     pub fn get_default(self) -> FastFlagValue {
@@ -0,0 +1,63 @@
+use crate::userdata::enums::DeveloperMemoryTag;
+
+/// Reads the value of a monitor out of the frame stats.
+pub type MonitorGetter = fn(&FrameStats) -> f64;
+
+/// Timings and counters collected by the global task scheduler on every frame step.
+/// Times are in seconds and memory in bytes.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct FrameStats {
+    /// Time the whole frame step took.
+    pub heartbeat_time: f64,
+    /// Time spent stepping physics. Nothing simulates physics yet, so this stays at zero.
+    pub physics_step_time: f64,
+    /// Time spent resuming deferred, delayed and waiting threads.
+    pub scheduler_time: f64,
+    /// Time spent collecting garbage across all Luau states.
+    pub gc_time: f64,
+    pub instance_count: usize,
+    /// Heap size of all Luau states.
+    pub lua_heap: usize,
+    /// The part of the Luau heaps attributed to scripts.
+    pub script_memory: usize,
+    /// Memory taken by the instance replication table.
+    pub instance_table_memory: usize,
+    /// Static memory reported by Godot.
+    pub engine_memory: usize,
+}
+
+impl FrameStats {
+    /// Godot `Performance` custom monitors mirroring these counters, as `(id, getter)` pairs.
+    pub const MONITORS: &'static [(&'static str, MonitorGetter)] = &[
+        ("RblxVM/Heartbeat Time (ms)", |x| x.heartbeat_time * 1000.0),
+        ("RblxVM/Physics Step Time (ms)", |x| {
+            x.physics_step_time * 1000.0
+        }),
+        ("RblxVM/Scheduler Time (ms)", |x| x.scheduler_time * 1000.0),
+        ("RblxVM/GC Time (ms)", |x| x.gc_time * 1000.0),
+        ("RblxVM/Instance Count", |x| x.instance_count as f64),
+        ("RblxVM/Luau Heap (MB)", |x| to_mb(x.lua_heap)),
+        ("RblxVM/Total Memory (MB)", |x| {
+            x.get_total_memory_usage_mb()
+        }),
+    ];
+
+    pub fn get_total_memory_usage_mb(&self) -> f64 {
+        to_mb(self.engine_memory + self.lua_heap)
+    }
+    /// Returns the memory of a category, in megabytes. Categories nothing is tracked for report zero.
+    pub fn get_memory_usage_mb_for_tag(&self, tag: DeveloperMemoryTag) -> f64 {
+        match tag {
+            DeveloperMemoryTag::Internal => to_mb(self.engine_memory),
+            DeveloperMemoryTag::Instances => to_mb(self.instance_table_memory),
+            DeveloperMemoryTag::LuaHeap => to_mb(self.lua_heap),
+            DeveloperMemoryTag::Script => to_mb(self.script_memory),
+            _ => 0.0,
+        }
+    }
+}
+
+#[inline]
+fn to_mb(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
@@ -12,10 +12,12 @@ use r2g_mlua::prelude::*;
 use crate::core::alloc::Allocator;
 use crate::core::lua_macros::lua_getter;
 use crate::core::{
-    get_state, get_task_scheduler_from_lua, IWeak, Irc, IrcHead, ParallelDispatch, RwLockReadGuard,
-    RwLockWriteGuard,
+    check_capability, check_grant_capabilities, get_class_registry, get_state,
+    get_task_scheduler_from_lua, IWeak, Irc, IrcHead, MemberKind, ParallelDispatch,
+    RwLockReadGuard, RwLockWriteGuard,
 };
-use crate::userdata::{ManagedRBXScriptSignal, RBXScriptSignal};
+use crate::userdata::enums::ScriptCapability;
+use crate::userdata::{ManagedRBXScriptSignal, RBXScriptSignal, SecurityCapabilities};
 
 use super::{IObject, Weak};
 
@@ -104,6 +106,14 @@ impl DynInstance {
         DynInstance::guard_set_archivable(&mut self.get_instance_component_mut(), val)
     }
     #[inline]
+    pub fn get_sandboxed(&self) -> bool {
+        DynInstance::guard_get_sandboxed(&self.get_instance_component())
+    }
+    #[inline]
+    pub fn get_capabilities(&self) -> SecurityCapabilities {
+        DynInstance::guard_get_capabilities(&self.get_instance_component())
+    }
+    #[inline]
     pub fn get_uniqueid(&self) -> usize {
         Self::guard_get_uniqueid(&self.get_instance_component())
     }
@@ -158,6 +168,19 @@ impl DynInstance {
     pub fn destroy(&self, lua: &Lua) -> LuaResult<()> {
         DynInstance::guard_destroy(&mut self.get_instance_component_mut(), lua)
     }
+    /// Disconnects everything connected to the signals of the instance, without destroying it.
+    pub(crate) fn disconnect_all_signals(&self) {
+        let signals = self
+            .get_instance_component()
+            .signal_list
+            .clone()
+            .unwrap_or_default();
+        for sig in signals {
+            if let Some(sig) = sig.upgrade() {
+                sig.write().disconnect_all();
+            }
+        }
+    }
 
     pub fn guard_get_parent(this: &impl IReadInstanceComponent) -> Option<ManagedInstance> {
         let parent = this.parent.as_ref();
@@ -279,6 +302,12 @@ impl DynInstance {
         this.name = val;
         Ok(())
     }
+    pub fn guard_get_sandboxed(this: &impl IReadInstanceComponent) -> bool {
+        this.sandboxed
+    }
+    pub fn guard_get_capabilities(this: &impl IReadInstanceComponent) -> SecurityCapabilities {
+        this.capabilities
+    }
     pub fn guard_get_archivable(this: &impl IReadInstanceComponent) -> bool {
         this.archivable
     }
@@ -449,6 +478,7 @@ impl DynInstance {
             let thread = lua.current_thread();
             if timeout.is_some() {
                 get_task_scheduler_from_lua(lua).delay_thread(
+                    lua,
                     thread.clone(),
                     ParallelDispatch::Default,
                     timeout.unwrap(),
@@ -645,6 +675,8 @@ pub struct InstanceComponent {
     children_cache: HashMap<String, WeakManagedInstance>,
     children_cache_dirty: bool,
     parent_locked: bool,
+    sandboxed: bool,
+    capabilities: SecurityCapabilities,
 
     pub ancestry_changed: ManagedRBXScriptSignal,
     pub attribute_changed: ManagedRBXScriptSignal,
@@ -702,6 +734,8 @@ impl IInstanceComponent for InstanceComponent {
             children: Vec::new(),
             children_cache: HashMap::new(),
             children_cache_dirty: false,
+            sandboxed: false,
+            capabilities: SecurityCapabilities::empty(),
 
             ancestry_changed: RBXScriptSignal::new(metadata),
             attribute_changed: RBXScriptSignal::new(metadata),
@@ -743,6 +777,8 @@ impl IInstanceComponent for InstanceComponent {
             children_cache_dirty: true,
             _ptr: metadata.ptr.clone(),
             parent_locked: false,
+            sandboxed: self.sandboxed,
+            capabilities: self.capabilities,
 
             ancestry_changed: RBXScriptSignal::new(metadata),
             attribute_changed: RBXScriptSignal::new(metadata),
@@ -777,12 +813,14 @@ impl InstanceComponent {
     ) -> LuaResult<LuaValue> {
         match key.as_str() {
             "Archivable" => lua_getter!(lua, self.archivable),
+            "Capabilities" => lua_getter!(lua, self.capabilities),
             "ClassName" => IntoLua::into_lua(
                 unsafe { self._ptr.upgrade().unwrap_unchecked().get_class_name() },
                 lua,
             ),
             "Name" => lua_getter!(string, lua, self.name),
             "Parent" => lua_getter!(opt_weak_clone, lua, self.parent),
+            "Sandboxed" => lua_getter!(lua, self.sandboxed),
 
             "AddTag" => lua_getter!(
                 function,
@@ -792,8 +830,11 @@ impl InstanceComponent {
             "ClearAllChildren" => lua_getter!(function, lua, |lua, (this,): (ManagedInstance,)| {
                 this.clear_all_children(lua)
             }),
-            "Clone" => lua_getter!(function, lua, |lua, (this,): (ManagedInstance,)| this
-                .clone_instance(lua)),
+            "Clone" => lua_getter!(function, lua, |lua, (this,): (ManagedInstance,)| {
+                let clone = this.clone_instance(lua)?;
+                get_state(lua).get_vm().register_instance_tree(&clone);
+                Ok(clone)
+            }),
             "Destroy" => lua_getter!(function, lua, |lua, (this,): (ManagedInstance,)| {
                 this.destroy(lua)
             }),
@@ -833,7 +874,7 @@ impl InstanceComponent {
                 ManagedInstance,
                 String
             )| this
-                .find_first_ancestor_which_is_a(class)),
+                .find_first_child_which_is_a(class)),
             "GetActor" => lua_getter!(function, lua, |_, (this,): (ManagedInstance,)| this
                 .get_actor()),
             "GetAttribute" => lua_getter!(function, lua, |_,
@@ -873,6 +914,11 @@ impl InstanceComponent {
                 lua,
                 |_, (this, tag): (ManagedInstance, String)| this.has_tag(tag)
             ),
+            "IsA" => lua_getter!(
+                function,
+                lua,
+                |_, (this, class): (ManagedInstance, String)| Ok(this.is_a(&class))
+            ),
             "IsAncestorOf" => lua_getter!(function, lua, |_,
                                                           (this, inst): (
                 ManagedInstance,
@@ -913,7 +959,13 @@ impl InstanceComponent {
             "DescendantRemoving" => lua_getter!(clone, lua, self.descendant_removing),
             "Destroying" => lua_getter!(clone, lua, self.destroying),
 
-            _ => lua_getter!(lua, self.find_first_child(key)),
+            _ => {
+                let class_name = unsafe { self._ptr.upgrade().unwrap_unchecked().get_class_name() };
+                if let Some(e) = get_class_registry().not_implemented_error(class_name, key) {
+                    return Err(e);
+                }
+                lua_getter!(lua, self.find_first_child(key))
+            }
         }
     }
     pub(crate) fn get_signal_list(&mut self) -> &mut Vec<Weak<RBXScriptSignal>> {
@@ -1001,10 +1053,35 @@ impl InstanceComponent {
                 Self::emit_property_changed(self, lua, "Name", &value)
             }
             "Parent" => DynInstance::guard_set_parent(self, lua, FromLua::from_lua(value, lua)?),
-            _ => Err(LuaError::RuntimeError(format!(
-                "can't set property {} on object of type Instance",
-                key.as_str()
-            ))),
+            "Sandboxed" => {
+                check_capability(
+                    lua,
+                    ScriptCapability::CapabilityControl,
+                    "Instance.Sandboxed",
+                )?;
+                self.sandboxed = value.as_boolean().ok_or(LuaError::RuntimeError(
+                    "bad argument to setting Sandboxed".into(),
+                ))?;
+                Self::emit_property_changed(self, lua, "Sandboxed", &value)
+            }
+            "Capabilities" => {
+                let capabilities = FromLua::from_lua(value.clone(), lua)?;
+                check_grant_capabilities(lua, capabilities, "Instance.Capabilities")?;
+                self.capabilities = capabilities;
+                Self::emit_property_changed(self, lua, "Capabilities", &value)
+            }
+            _ => {
+                let class_name = unsafe { self._ptr.upgrade().unwrap_unchecked().get_class_name() };
+                let registry = get_class_registry();
+                if let Some((_, MemberKind::Property)) = registry.find_known_member(class_name, key)
+                {
+                    return Err(registry.not_implemented_error(class_name, key).unwrap());
+                }
+                Err(LuaError::RuntimeError(format!(
+                    "can't set property {} on object of type Instance",
+                    key.as_str()
+                )))
+            }
         }
     }
 }
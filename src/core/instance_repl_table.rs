@@ -19,6 +19,15 @@ impl InstanceReplicationTable {
             .map(|x| x.clone())
             .or_else(|| self.secondary.read().unwrap().get(&id).map(|x| x.clone()))
     }
+    /// Returns every instance of the table that still exists.
+    pub fn get_instances(&self) -> Vec<ManagedInstance> {
+        let main = self.main.read().unwrap();
+        let secondary = self.secondary.read().unwrap();
+        main.values()
+            .chain(secondary.values())
+            .filter_map(|x| x.upgrade())
+            .collect()
+    }
     pub fn add_instance(&self, instance: ManagedInstance) {
         let mut instance_write = instance.get_instance_component_mut();
         if DynInstance::guard_get_uniqueid(&instance_write) == 0 {
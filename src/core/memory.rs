@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::ffi::c_int;
+
+use r2g_mlua::ffi::{self, lua_State};
+use r2g_mlua::prelude::*;
+
+use super::WeakManagedInstance;
+
+/// Luau has 256 memory categories, the first one holds everything that isn't allocated by a script.
+const MAX_CATEGORY: u8 = u8::MAX;
+
+/// Tracks how much of a Luau state's heap each script is responsible for.
+///
+/// Every script gets a Luau memory category, which is set on the threads running for it. The allocator charges
+/// each allocation to the category of the thread making it, and takes it back from the same category when the
+/// block is freed, whenever and by whichever thread that happens. Past 255 live scripts, new ones share the
+/// first category and report no memory.
+#[derive(Debug, Default)]
+pub struct ScriptMemoryTracker {
+    categories: HashMap<WeakManagedInstance, u8>,
+    /// Categories of scripts that are gone, handed out again once everything allocated under them is freed.
+    released: Vec<u8>,
+    /// The last category handed out for the first time.
+    last: u8,
+}
+
+fn category_bytes(lua: &Lua, category: u8) -> usize {
+    // The counters are shared by every thread of the state, any of them will do.
+    unsafe {
+        ffi::lua_totalbytes(
+            lua.current_thread().to_pointer() as *mut lua_State,
+            category as c_int,
+        )
+    }
+}
+
+impl ScriptMemoryTracker {
+    /// Returns the memory category of `script`, giving it one if it doesn't have any yet.
+    pub(super) fn category(&mut self, lua: &Lua, script: &WeakManagedInstance) -> u8 {
+        if let Some(category) = self.categories.get(script) {
+            return *category;
+        }
+        let category = if let Some(index) = self
+            .released
+            .iter()
+            .position(|x| category_bytes(lua, *x) == 0)
+        {
+            self.released.swap_remove(index)
+        } else if self.last < MAX_CATEGORY {
+            self.last += 1;
+            self.last
+        } else {
+            return 0;
+        };
+        self.categories.insert(script.clone(), category);
+        category
+    }
+    /// Called after a garbage collection step, forgets the scripts that no longer exist.
+    pub fn collected(&mut self) {
+        let released = &mut self.released;
+        self.categories.retain(|script, category| {
+            let alive = script.strong_count() != 0;
+            if !alive {
+                released.push(*category);
+            }
+            alive
+        });
+    }
+    pub fn get(&self, lua: &Lua, script: &WeakManagedInstance) -> usize {
+        self.categories
+            .get(script)
+            .map_or(0, |x| category_bytes(lua, *x))
+    }
+    pub fn iter<'a>(
+        &'a self,
+        lua: &'a Lua,
+    ) -> impl Iterator<Item = (&'a WeakManagedInstance, usize)> + 'a {
+        self.categories
+            .iter()
+            .map(move |(script, category)| (script, category_bytes(lua, *category)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r2g_mlua::prelude::*;
+
+    use crate::core::{ManagedInstance, RblxVM, ThreadIdentity, ThreadIdentityType};
+
+    #[test]
+    fn frees_are_taken_back_from_the_allocating_script() {
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let workspace: ManagedInstance = vm.get_workspace().cast_from_sized().unwrap();
+        let script = workspace.downgrade();
+        let state = vm.get_main_state();
+        let lua = state.get_lua().clone();
+        let func = lua
+            .load("blob = table.create(100000, 0)")
+            .into_function()
+            .unwrap();
+        let thread = lua.create_thread(func).unwrap();
+        state.set_thread_identity(
+            thread.clone(),
+            ThreadIdentity {
+                security_identity: ThreadIdentityType::Script,
+                script: Some(script.clone()),
+                sandbox: None,
+            },
+        );
+        thread.resume::<()>(()).unwrap();
+        assert!(state.get_script_memory_usage(&script) >= 100000 * 16);
+        // Dropped by another thread, the table still leaves the usage of the script that made it.
+        lua.load("blob = nil").exec().unwrap();
+        state.gc();
+        assert!(state.get_script_memory_usage(&script) < 100000 * 16);
+    }
+}
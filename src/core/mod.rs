@@ -1,24 +1,38 @@
 pub mod alloc;
 mod assert_gdext_api;
+mod bytecode_cache;
+mod class_registry;
+mod clock;
 pub(crate) mod debug;
 mod fastflags;
+mod frame_stats;
 mod inheritance;
 mod instance;
 mod instance_repl_table;
 mod instance_tag_collection;
 pub mod lua_macros;
+mod memory;
+mod native;
 mod object;
 mod pointers;
+mod profiler;
 mod rc;
+mod require;
 mod rw_lock;
 mod scheduler;
+mod script_error;
 mod security;
 mod state;
 mod vm;
 mod watchdog;
 
 pub(crate) use assert_gdext_api::verify_gdext_api_compat;
+pub use bytecode_cache::{get_bytecode_cache, BytecodeCache};
+pub use class_registry::*;
+pub use clock::{real_clock, ClockMode, SchedulerClock};
+pub use debug::{Debugger, StateDebugger};
 pub use fastflags::*;
+pub use frame_stats::{FrameStats, MonitorGetter};
 pub use inheritance::*;
 pub(crate) use instance::InstanceCreationSignalList;
 pub use instance::{
@@ -27,20 +41,27 @@ pub use instance::{
 };
 pub(self) use instance_repl_table::InstanceReplicationTable;
 pub(self) use instance_tag_collection::InstanceTagCollectionTable;
+pub use memory::ScriptMemoryTracker;
 pub use object::IObject;
 pub(self) use pointers::*;
+pub use profiler::{ProfileSample, ScriptProfile, ScriptProfiler, DEFAULT_PROFILER_FREQUENCY};
 pub use rc::*;
+pub(crate) use require::{
+    check_cycle, read_luaurc, resolve_require, run_module, ModuleResult, RequireTarget,
+};
+pub use require::{require_file, ModuleKey, RequireAlias};
 pub use rw_lock::*;
 pub use scheduler::{
     get_task_scheduler_from_lua, GlobalTaskScheduler, ITaskScheduler, ParallelDispatch,
     TaskScheduler,
 };
+pub use script_error::{chunk_display_name, ScriptError, StackFrame};
 pub use security::*;
 pub use state::{
     get_current_identity, get_state, get_state_with_rwlock, get_thread_identity, registry_keys,
-    LuauState, ThreadIdentity,
+    CompileMode, LuauState, NativeChunk, ThreadIdentity,
 };
-pub use vm::RblxVM;
+pub use vm::{RblxVM, RunState};
 pub use watchdog::Watchdog;
 
 /// Provides a way to ignore borrowck for a specific borrow.
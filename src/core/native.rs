@@ -0,0 +1,205 @@
+/// Returns true if `chunk` is marked `--!native`. Like the other directives, it only counts in the comments before
+/// the first statement.
+pub(super) fn is_native_module(chunk: &str) -> bool {
+    for line in chunk.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.strip_prefix("--") {
+            Some(comment) if comment.trim_end() == "!native" => return true,
+            Some(_) => continue,
+            None => return false,
+        }
+    }
+    false
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Name(&'a str),
+    /// An attribute, `@native` or one of the names of `@[native, ...]`.
+    Attribute(&'a str),
+    Symbol(char),
+}
+
+/// Counts the `=` of a long bracket opening at the start of `rest`, `[[` or `[==[`.
+fn long_bracket_level(rest: &str) -> Option<usize> {
+    let inner = rest.strip_prefix('[')?;
+    let level = inner.bytes().take_while(|x| *x == b'=').count();
+    (inner.as_bytes().get(level) == Some(&b'[')).then_some(level)
+}
+
+/// Splits the code of `chunk` into tokens with their line, leaving out comments, strings and numbers. The Luau
+/// build the VM uses doesn't expose its parser, this only goes as far as telling comments and strings from code.
+fn tokenize(chunk: &str) -> Vec<(usize, Token<'_>)> {
+    let bytes = chunk.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    // Skips to the end of a long bracket of `level` starting at `i`, counting lines.
+    let skip_long = |i: &mut usize, line: &mut usize, level: usize| {
+        let close = format!("]{}]", "=".repeat(level));
+        let start = *i + level + 2;
+        let end = chunk[start..]
+            .find(&close)
+            .map_or(chunk.len(), |x| start + x + close.len());
+        *line += chunk[*i..end].matches('\n').count();
+        *i = end;
+    };
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i += 2;
+                if let Some(level) = long_bracket_level(&chunk[i..]) {
+                    skip_long(&mut i, &mut line, level);
+                } else {
+                    i = chunk[i..].find('\n').map_or(chunk.len(), |x| i + x);
+                }
+            }
+            b'[' if long_bracket_level(&chunk[i..]).is_some() => {
+                let level = long_bracket_level(&chunk[i..]).unwrap();
+                skip_long(&mut i, &mut line, level);
+            }
+            b'"' | b'\'' | b'`' => {
+                i += 1;
+                // Interpolated strings hold expressions between braces, those can't hold attributes.
+                let mut depth = 0;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'\\' => i += 1,
+                        b'\n' => line += 1,
+                        b'{' if c == b'`' => depth += 1,
+                        b'}' if c == b'`' && depth > 0 => depth -= 1,
+                        x if x == c && depth == 0 => break,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'@' => {
+                i += 1;
+                if bytes.get(i) == Some(&b'[') {
+                    let end = chunk[i..].find(']').map_or(chunk.len(), |x| i + x);
+                    for name in chunk[i + 1..end].split(',') {
+                        tokens.push((line, Token::Attribute(name.trim())));
+                    }
+                    i = (end + 1).min(chunk.len());
+                } else {
+                    let start = i;
+                    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_')
+                    {
+                        i += 1;
+                    }
+                    tokens.push((line, Token::Attribute(&chunk[start..i])));
+                }
+            }
+            x if x.is_ascii_alphanumeric() || x == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                if !x.is_ascii_digit() {
+                    tokens.push((line, Token::Name(&chunk[start..i])));
+                }
+            }
+            x if x.is_ascii_whitespace() => i += 1,
+            x => {
+                // Multibyte characters only show up in strings and comments, anything else is left to the compiler.
+                if x.is_ascii() {
+                    tokens.push((line, Token::Symbol(x as char)));
+                }
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Finds the functions marked with the `@native` attribute, as the line of their declaration and their name.
+/// Anonymous functions are named `<anonymous>`.
+pub(super) fn native_functions(chunk: &str) -> Vec<(usize, String)> {
+    let tokens = tokenize(chunk);
+    let mut functions = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i].1 != Token::Attribute("native") {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        while matches!(tokens.get(i), Some((_, Token::Attribute(_)))) {
+            i += 1;
+        }
+        if matches!(tokens.get(i), Some((_, Token::Name("local")))) {
+            i += 1;
+        }
+        let Some((line, Token::Name("function"))) = tokens.get(i) else {
+            continue;
+        };
+        let line = *line;
+        i += 1;
+        let mut name = String::new();
+        while let Some((_, token)) = tokens.get(i) {
+            match token {
+                Token::Name(x) => name.push_str(x),
+                Token::Symbol(x @ ('.' | ':')) if !name.is_empty() => name.push(*x),
+                _ => break,
+            }
+            i += 1;
+        }
+        if name.is_empty() {
+            name.push_str("<anonymous>");
+        }
+        functions.push((line, name));
+    }
+    functions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_native_module, native_functions};
+
+    #[test]
+    fn finds_native_functions_outside_comments_and_strings() {
+        let chunk = r#"
+-- @native function commented()
+--[[
+@native
+function block_commented() end
+]]
+local s = "@native function quoted()"
+local t = [==[
+@native function long_string()
+]==]
+local u = `{"@native"} function interpolated()`
+@native
+local function first() end
+@native @checked function Module.second() end
+@[native] function Class:third() end
+local f = @native function() end
+"#;
+        assert_eq!(
+            native_functions(chunk),
+            vec![
+                (13, String::from("first")),
+                (14, String::from("Module.second")),
+                (15, String::from("Class:third")),
+                (16, String::from("<anonymous>")),
+            ]
+        );
+    }
+
+    #[test]
+    fn native_module_directive_only_counts_before_code() {
+        assert!(is_native_module("-- A module\n--!native\nreturn {}"));
+        assert!(!is_native_module("return {}\n--!native"));
+        assert!(!is_native_module("local s = [[\n--!native\n]]"));
+    }
+}
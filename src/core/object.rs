@@ -1,14 +1,20 @@
 use r2g_mlua::prelude::*;
 
-use crate::core::InheritanceBase;
+use crate::core::{get_class_registry, InheritanceBase, MemberDescriptor};
 use crate::userdata::ManagedRBXScriptSignal;
 
 pub trait IObject: InheritanceBase + Send + Sync {
     fn lua_get(&self, lua: &Lua, name: String) -> LuaResult<LuaValue>;
     fn get_class_name(&self) -> &'static str;
     fn get_property_changed_signal(&self, property: String) -> ManagedRBXScriptSignal;
-    fn is_a(&self, class_name: &String) -> bool;
+    fn is_a(&self, class_name: &String) -> bool {
+        get_class_registry().is_a(self.get_class_name(), class_name)
+    }
     fn get_changed_signal(&self) -> ManagedRBXScriptSignal;
+    /// Lists the properties, functions, events and callbacks of the object's class, sorted by name.
+    fn get_members(&self) -> Vec<MemberDescriptor> {
+        get_class_registry().get_members(self.get_class_name())
+    }
 }
 
 impl PartialEq for dyn IObject {
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use r2g_mlua::prelude::*;
+
+pub const DEFAULT_PROFILER_FREQUENCY: f64 = 1000.0;
+
+/// A sampled call stack, outermost frame first. The first frame is the script owning the thread.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ProfileSample {
+    /// Microseconds since profiling started.
+    pub time: f64,
+    pub stack: Vec<String>,
+}
+
+/// Samples the call stack of a Luau state from its interrupt callback, at a fixed frequency.
+#[derive(Debug)]
+pub struct ScriptProfiler {
+    interval: Duration,
+    started: Instant,
+    next_sample: Instant,
+    samples: Vec<ProfileSample>,
+}
+
+impl ScriptProfiler {
+    pub fn new(frequency: f64) -> ScriptProfiler {
+        let now = Instant::now();
+        ScriptProfiler {
+            interval: Duration::from_secs_f64(1.0 / frequency.clamp(1.0, 1_000_000.0)),
+            started: now,
+            next_sample: now,
+            samples: Vec::new(),
+        }
+    }
+    /// Returns true once per interval.
+    pub fn should_sample(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next_sample {
+            return false;
+        }
+        self.next_sample = now + self.interval;
+        true
+    }
+    /// Records the stack of the thread currently running on `lua`.
+    pub fn sample(&mut self, lua: &Lua, script: String) {
+        let mut stack = Vec::new();
+        let mut level = 0;
+        while let Some(debug) = lua.inspect_stack(level) {
+            let source = debug.source();
+            let name = debug.names().name.map(|x| x.into_owned());
+            stack.push(match source.what {
+                "C" => format!("[C] {}", name.as_deref().unwrap_or("?")),
+                _ => format!(
+                    "{} ({}:{})",
+                    name.as_deref().unwrap_or("<anonymous>"),
+                    source.short_src.as_deref().unwrap_or("?"),
+                    source.line_defined.unwrap_or(0)
+                ),
+            });
+            level += 1;
+        }
+        stack.push(script);
+        stack.reverse();
+        self.samples.push(ProfileSample {
+            time: self.started.elapsed().as_secs_f64() * 1_000_000.0,
+            stack,
+        });
+    }
+    pub fn finish(self) -> ScriptProfile {
+        ScriptProfile {
+            interval: self.interval.as_secs_f64() * 1_000_000.0,
+            threads: vec![self.samples],
+        }
+    }
+}
+
+/// Samples collected from one or more Luau states, each state being its own thread in the exports.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptProfile {
+    /// Sampling interval, in microseconds.
+    pub interval: f64,
+    pub threads: Vec<Vec<ProfileSample>>,
+}
+
+impl ScriptProfile {
+    pub fn merge(&mut self, other: ScriptProfile) {
+        self.interval = self.interval.max(other.interval);
+        self.threads.extend(other.threads);
+    }
+    /// Exports the profile as collapsed stacks, as consumed by `flamegraph.pl` and inferno.
+    pub fn to_collapsed_stacks(&self) -> String {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for sample in self.threads.iter().flatten() {
+            let key = sample
+                .stack
+                .iter()
+                .map(|x| x.replace(';', ":"))
+                .collect::<Vec<_>>()
+                .join(";");
+            *counts.entry(key).or_default() += 1;
+        }
+        let mut lines: Vec<_> = counts.into_iter().collect();
+        lines.sort();
+        let mut out = String::new();
+        for (stack, count) in lines {
+            let _ = writeln!(out, "{} {}", stack, count);
+        }
+        out
+    }
+    /// Exports the profile in the Chrome trace event format, for `chrome://tracing` and Perfetto.
+    /// A frame spans consecutive samples it appears in, gaps longer than two intervals end it.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+        for (tid, samples) in self.threads.iter().enumerate() {
+            let mut open: Vec<(&str, f64)> = Vec::new();
+            let mut last_time = f64::NEG_INFINITY;
+            for sample in samples {
+                if sample.time - last_time > self.interval * 2.0 {
+                    close_frames(&mut events, tid, &mut open, 0, last_time + self.interval);
+                }
+                let common = open
+                    .iter()
+                    .zip(sample.stack.iter())
+                    .take_while(|((a, _), b)| *a == b.as_str())
+                    .count();
+                close_frames(&mut events, tid, &mut open, common, sample.time);
+                for frame in &sample.stack[common..] {
+                    open.push((frame, sample.time));
+                }
+                last_time = sample.time;
+            }
+            close_frames(&mut events, tid, &mut open, 0, last_time + self.interval);
+        }
+        format!(
+            "{{\"traceEvents\":[{}],\"displayTimeUnit\":\"ms\"}}",
+            events.join(",")
+        )
+    }
+}
+
+fn close_frames(
+    events: &mut Vec<String>,
+    tid: usize,
+    open: &mut Vec<(&str, f64)>,
+    keep: usize,
+    end: f64,
+) {
+    while open.len() > keep {
+        let (name, start) = open.pop().unwrap();
+        events.push(format!(
+            "{{\"name\":\"{}\",\"cat\":\"luau\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":{}}}",
+            escape_json(name),
+            start,
+            end - start,
+            tid
+        ));
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
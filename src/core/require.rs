@@ -0,0 +1,668 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs;
+use std::mem::zeroed;
+use std::path::{Path, PathBuf};
+
+use r2g_mlua::ffi::{self, lua_Debug};
+use r2g_mlua::prelude::*;
+
+use super::{get_current_identity, get_state, FastFlag, ManagedInstance, WeakManagedInstance};
+use crate::project::{check_mode_directive, find_child};
+
+/// A module being required, either a ModuleScript or a file loaded by the filesystem module loader.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModuleKey {
+    Instance(WeakManagedInstance),
+    File(PathBuf),
+}
+
+impl ModuleKey {
+    fn display_name(&self) -> String {
+        match self {
+            ModuleKey::Instance(x) => x
+                .upgrade()
+                .and_then(|x| x.get_full_name().ok())
+                .unwrap_or_else(|| String::from("<destroyed module>")),
+            ModuleKey::File(x) => x.display().to_string(),
+        }
+    }
+}
+
+/// What an `@alias` of a require path stands for.
+#[derive(Clone, Debug)]
+pub enum RequireAlias {
+    Instance(WeakManagedInstance),
+    /// A directory or a module file, only reachable when the filesystem module loader is enabled.
+    Path(PathBuf),
+}
+
+/// The outcome of running the body of a module, kept so it only ever runs once.
+#[derive(Clone, Debug)]
+pub(crate) enum ModuleResult {
+    Loaded(LuaValue),
+    Failed,
+}
+
+impl ModuleResult {
+    pub(crate) fn get(&self) -> LuaResult<LuaValue> {
+        match self {
+            ModuleResult::Loaded(x) => Ok(x.clone()),
+            ModuleResult::Failed => Err(LuaError::RuntimeError(
+                "Requested module experienced an error while loading".into(),
+            )),
+        }
+    }
+}
+
+/// The module a require path resolved to.
+#[derive(Debug)]
+pub(crate) enum RequireTarget {
+    Instance(ManagedInstance),
+    File(PathBuf),
+}
+
+enum Location {
+    Instance(ManagedInstance),
+    Path(PathBuf),
+}
+
+unsafe fn to_string(s: *const std::ffi::c_char) -> Option<String> {
+    (!s.is_null()).then(|| CStr::from_ptr(s).to_string_lossy().into_owned())
+}
+
+/// Finds the module whose code called the running function, skipping C functions such as `pcall`.
+/// File modules are recognised by their chunk name, scripts by the `script` global of their environment.
+fn calling_module(lua: &Lua) -> Option<ModuleKey> {
+    let mut chunk = None;
+    // SAFETY: Callbacks run on the thread that called them, which is the state `exec_raw` hands out.
+    // The values pushed while walking the stack are popped, only the `script` global is left as the result.
+    let script = unsafe {
+        lua.exec_raw::<Option<ManagedInstance>>((), |state| {
+            let mut ar: lua_Debug = zeroed();
+            let mut level = 0;
+            while ffi::lua_getinfo(state, level, c"sf".as_ptr(), &mut ar) != 0 {
+                level += 1;
+                if to_string(ar.what).is_some_and(|x| x == "C") {
+                    ffi::lua_pop(state, 1);
+                    continue;
+                }
+                chunk = to_string(ar.source);
+                ffi::lua_getfenv(state, -1);
+                if ffi::lua_istable(state, -1) != 0 {
+                    ffi::lua_rawgetfield(state, -1, c"script".as_ptr());
+                } else {
+                    ffi::lua_pushnil(state);
+                }
+                ffi::lua_remove(state, -2);
+                ffi::lua_remove(state, -2);
+                return;
+            }
+            ffi::lua_pushnil(state);
+        })
+    }
+    .ok()
+    .flatten();
+    if let Some(path) = chunk.as_deref().and_then(|x| x.strip_prefix('@')) {
+        return Some(ModuleKey::File(PathBuf::from(path)));
+    }
+    script
+        .map(|x| x.downgrade())
+        .or_else(|| get_current_identity(lua).and_then(|x| x.script.clone()))
+        .map(ModuleKey::Instance)
+}
+
+/// Reads the aliases of a `.luaurc` file, their paths made relative to the directory holding it.
+/// Alias names are case insensitive, they are lowercased.
+pub(crate) fn read_luaurc(file: &Path) -> LuaResult<HashMap<String, PathBuf>> {
+    let text = fs::read_to_string(file).map_err(|err| {
+        LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+    })?;
+    let config: serde_json::Value = serde_json::from_str(&strip_comments(&text))
+        .map_err(|err| LuaError::RuntimeError(format!("invalid {}: {}", file.display(), err)))?;
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let mut aliases = HashMap::new();
+    if let Some(x) = config.get("aliases").and_then(|x| x.as_object()) {
+        for (name, path) in x {
+            let Some(path) = path.as_str() else {
+                return Err(LuaError::RuntimeError(format!(
+                    "invalid {}: the alias {} isn't a path",
+                    file.display(),
+                    name
+                )));
+            };
+            aliases.insert(name.to_lowercase(), dir.join(path));
+        }
+    }
+    Ok(aliases)
+}
+
+/// `.luaurc` files are JSON that may hold `//` comments and trailing commas, which are taken out.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|x| *x != '\n').is_some() {},
+            '}' | ']' => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Finds the alias `name` for a module under `dir`, looking through the `.luaurc` files from `dir` up.
+fn find_file_alias(dir: &Path, name: &str) -> LuaResult<Option<PathBuf>> {
+    for dir in dir.ancestors() {
+        let file = dir.join(".luaurc");
+        if file.is_file() {
+            if let Some(x) = read_luaurc(&file)?.remove(name) {
+                return Ok(Some(x));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn parent_of(instance: &ManagedInstance) -> LuaResult<ManagedInstance> {
+    let name = instance.get_full_name()?;
+    instance
+        .get_parent()
+        .ok_or_else(|| LuaError::RuntimeError(format!("{} has no parent", name)))
+}
+
+/// Where `./`, `../` and `@self` lead from a file, `init` modules standing for the directory holding them.
+fn file_self(file: &Path) -> PathBuf {
+    match file.file_stem().and_then(|x| x.to_str()) {
+        Some("init") => file.parent().unwrap_or(Path::new("")).to_path_buf(),
+        _ => file.with_extension(""),
+    }
+}
+
+/// Turns a require path into the module it points to, following Luau's require-by-string rules.
+///
+/// `./` and `../` start from the parent and grandparent of the calling module, `@self` from the module
+/// itself, `@game` from the DataModel and any other `@alias` from the `.luaurc` aliases. Within the instance
+/// tree each following component names a child, `..` going up a level. Paths of file modules resolve to
+/// `name.luau`, `name.lua` or the `init` module of the directory `name`.
+pub(crate) fn resolve_require(lua: &Lua, path: &str) -> LuaResult<RequireTarget> {
+    let mut components = path.split('/');
+    let first = components.next().unwrap_or_default();
+    let origin = || {
+        calling_module(lua).ok_or_else(|| {
+            LuaError::RuntimeError(format!(
+                "cannot require {} from code that doesn't belong to a script",
+                path
+            ))
+        })
+    };
+    let mut location = match first {
+        "." | ".." | "@self" => match origin()? {
+            ModuleKey::Instance(x) => {
+                let script = x.upgrade().ok_or_else(|| {
+                    LuaError::RuntimeError("the calling script was destroyed".into())
+                })?;
+                Location::Instance(match first {
+                    "." => parent_of(&script)?,
+                    ".." => parent_of(&parent_of(&script)?)?,
+                    _ => script,
+                })
+            }
+            ModuleKey::File(x) => {
+                let own = file_self(&x);
+                Location::Path(match first {
+                    "." => own.parent().unwrap_or(Path::new("")).to_path_buf(),
+                    ".." => own
+                        .parent()
+                        .and_then(Path::parent)
+                        .unwrap_or(Path::new(""))
+                        .to_path_buf(),
+                    _ => own,
+                })
+            }
+        },
+        "@game" => Location::Instance(
+            get_state(lua)
+                .get_vm()
+                .get_game_instance()
+                .cast_from_sized()
+                .unwrap(),
+        ),
+        x if x.len() > 1 && x.starts_with('@') => {
+            let name = x[1..].to_lowercase();
+            let file_alias = match calling_module(lua) {
+                Some(ModuleKey::File(file)) => {
+                    find_file_alias(file.parent().unwrap_or(Path::new("")), &name)?
+                }
+                _ => None,
+            };
+            match file_alias {
+                Some(x) => Location::Path(x),
+                None => match get_state(lua).get_vm().get_require_alias(&name) {
+                    Some(RequireAlias::Instance(x)) => {
+                        Location::Instance(x.upgrade().ok_or_else(|| {
+                            LuaError::RuntimeError(format!("the target of @{} was destroyed", name))
+                        })?)
+                    }
+                    Some(RequireAlias::Path(x)) => Location::Path(x),
+                    None => {
+                        return Err(LuaError::RuntimeError(format!(
+                            "@{} is not a valid alias",
+                            name
+                        )))
+                    }
+                },
+            }
+        }
+        _ => {
+            return Err(LuaError::RuntimeError(format!(
+                "require path {} must start with a valid prefix: ./, ../, or @",
+                path
+            )))
+        }
+    };
+    for component in components {
+        match (component, &mut location) {
+            ("", _) => {
+                return Err(LuaError::RuntimeError(format!(
+                    "require path {} has an empty component",
+                    path
+                )))
+            }
+            (".", _) => {}
+            ("..", Location::Instance(x)) => *x = parent_of(x)?,
+            ("..", Location::Path(x)) => {
+                x.pop();
+            }
+            (name, Location::Instance(x)) => {
+                *x = find_child(x, name)?.ok_or_else(|| {
+                    LuaError::RuntimeError(format!(
+                        "{} is not a valid member of {}",
+                        name,
+                        x.get_full_name().unwrap_or_default()
+                    ))
+                })?
+            }
+            (name, Location::Path(x)) => x.push(name),
+        }
+    }
+    match location {
+        Location::Instance(x) => Ok(RequireTarget::Instance(x)),
+        Location::Path(x) => {
+            let file = find_module_file(&x).ok_or_else(|| {
+                LuaError::RuntimeError(format!("could not find a module at {}", x.display()))
+            })?;
+            check_module_root(lua, &file).map(RequireTarget::File)
+        }
+    }
+}
+
+/// Finds the file a module path stands for, `name.luau`, `name.lua` or the `init` module of the directory `name`.
+fn find_module_file(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy().into_owned();
+    [
+        path.with_file_name(format!("{}.luau", name)),
+        path.with_file_name(format!("{}.lua", name)),
+        path.join("init.luau"),
+        path.join("init.lua"),
+    ]
+    .into_iter()
+    .find(|x| x.is_file())
+}
+
+/// Makes sure the filesystem module loader is enabled and `file` lies under its root.
+fn check_module_root(lua: &Lua, file: &Path) -> LuaResult<PathBuf> {
+    let Some(root) = get_state(lua)
+        .get_vm()
+        .get_module_root()
+        .map(Path::to_path_buf)
+    else {
+        return Err(LuaError::RuntimeError(format!(
+            "cannot require {}, filesystem modules are disabled",
+            file.display()
+        )));
+    };
+    let file = fs::canonicalize(file).map_err(|err| {
+        LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+    })?;
+    if !file.starts_with(&root) {
+        return Err(LuaError::RuntimeError(format!(
+            "{} is outside of the module root {}",
+            file.display(),
+            root.display()
+        )));
+    }
+    Ok(file)
+}
+
+/// Errors out when `key` is already being required further up, which would otherwise never finish.
+pub(crate) fn check_cycle(lua: &Lua, key: &ModuleKey) -> LuaResult<()> {
+    let requiring = get_state(lua).get_requiring();
+    let Some(start) = requiring.iter().position(|x| x == key) else {
+        return Ok(());
+    };
+    let chain: Vec<String> = requiring[start..]
+        .iter()
+        .chain(std::iter::once(key))
+        .map(ModuleKey::display_name)
+        .collect();
+    Err(LuaError::RuntimeError(format!(
+        "Cyclic module dependency detected: {}",
+        chain.join(" -> ")
+    )))
+}
+
+/// Runs the body of a module on a thread of its own, returning the value it gave back.
+pub(crate) fn run_module(lua: &Lua, key: ModuleKey, func: LuaFunction) -> LuaResult<LuaValue> {
+    let thread = lua.create_thread(func)?;
+    get_state(lua).get_requiring().push(key.clone());
+    let result = thread.resume::<LuaMultiValue>(());
+    get_state(lua).get_requiring().pop();
+    match result {
+        Ok(x) => {
+            let mut iter = x.into_iter();
+            iter.next();
+            Ok(iter.next().unwrap_or(LuaValue::Nil))
+        }
+        Err(err) => {
+            get_state(lua).get_log_service().log_err(
+                lua,
+                format!(
+                    "Error occured while requiring module {}: {:?}",
+                    key.display_name(),
+                    err
+                ),
+            );
+            Err(err)
+        }
+    }
+}
+
+/// Requires a Luau file through the filesystem module loader, the way a headless runner starts its entry point.
+/// The file has to lie under the module root of the VM. Like ModuleScripts, its body only runs once per Luau state,
+/// an error it raised is raised again by later requires.
+pub fn require_file(lua: &Lua, path: &Path) -> LuaResult<LuaValue> {
+    let file = match path.is_file() {
+        true => path.to_path_buf(),
+        false => find_module_file(path).ok_or_else(|| {
+            LuaError::RuntimeError(format!("could not find a module at {}", path.display()))
+        })?,
+    };
+    let file = check_module_root(lua, &file)?;
+    let key = ModuleKey::File(file.clone());
+    check_cycle(lua, &key)?;
+    if let Some(x) = get_state(lua).get_file_modules().get(&file) {
+        return x.get();
+    }
+    let result = fs::read_to_string(&file)
+        .map_err(|err| {
+            LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+        })
+        .and_then(|source| {
+            let state = get_state(lua);
+            if state.flags().get_bool(FastFlag::AnalyzeOnLoad) {
+                if let Some(diagnostic) = check_mode_directive(&file.display().to_string(), &source)
+                {
+                    state
+                        .get_log_service()
+                        .log_warn(lua, diagnostic.to_output_line());
+                }
+            }
+            let env = if get_current_identity(lua).is_some_and(|x| x.sandbox.is_some()) {
+                state.create_sandboxed_env()?
+            } else {
+                state.create_env_from_global()?
+            };
+            state.compile(&format!("@{}", file.display()), &source, env)
+        })
+        .and_then(|func| run_module(lua, key, func));
+    let cached = match &result {
+        Ok(x) => ModuleResult::Loaded(x.clone()),
+        Err(_) => ModuleResult::Failed,
+    };
+    get_state(lua).get_file_modules().insert(file, cached);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use r2g_mlua::prelude::*;
+
+    use super::{require_file, resolve_require, strip_comments, RequireAlias, RequireTarget};
+    use crate::core::{ManagedInstance, RblxVM};
+
+    /// Makes a fresh directory under the temporary directory, canonicalized like the module root.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rblx-godot-require-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::canonicalize(dir).unwrap()
+    }
+
+    fn write(file: &Path, text: &str) {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, text).unwrap();
+    }
+
+    /// Resolves `path` from a chunk named `chunk_name` whose `script` global is `script`, naming the target.
+    fn resolve(
+        lua: &Lua,
+        script: Option<ManagedInstance>,
+        chunk_name: &str,
+        path: &str,
+    ) -> LuaResult<String> {
+        let resolve =
+            lua.create_function(|lua, path: String| match resolve_require(lua, &path)? {
+                RequireTarget::Instance(x) => x.get_full_name(),
+                RequireTarget::File(x) => Ok(x.display().to_string()),
+            })?;
+        let env = lua.create_table()?;
+        env.raw_set("resolve", resolve)?;
+        env.raw_set("script", script)?;
+        lua.load("return resolve(...)")
+            .set_name(chunk_name)
+            .set_environment(env)
+            .call(path)
+    }
+
+    #[test]
+    fn strips_comments_and_trailing_commas_outside_strings() {
+        let text = r#"{
+            // The aliases.
+            "aliases": {
+                "lib": "./lib", // trailing
+                "url": "http://example.com/a,}",
+            },
+        }"#;
+        let config: serde_json::Value = serde_json::from_str(&strip_comments(text)).unwrap();
+        assert_eq!(config["aliases"]["lib"], "./lib");
+        assert_eq!(config["aliases"]["url"], "http://example.com/a,}");
+    }
+
+    #[test]
+    fn resolves_paths_in_the_instance_tree() {
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let lua = vm.get_main_state().get_lua().clone();
+        let (module, lib): (ManagedInstance, ManagedInstance) = lua
+            .load(
+                r#"
+                local a = Instance.new("Folder")
+                a.Name = "A"
+                a.Parent = workspace
+                local b = Instance.new("Folder")
+                b.Name = "B"
+                b.Parent = a
+                local module = Instance.new("ModuleScript")
+                module.Name = "Module"
+                module.Parent = b
+                local child = Instance.new("ModuleScript")
+                child.Name = "Child"
+                child.Parent = module
+                local sibling = Instance.new("ModuleScript")
+                sibling.Name = "Sibling"
+                sibling.Parent = b
+                local lib = Instance.new("Folder")
+                lib.Name = "Lib"
+                lib.Parent = workspace
+                return module, lib
+                "#,
+            )
+            .eval()
+            .unwrap();
+        vm.set_require_alias("Lib", RequireAlias::Instance(lib.downgrade()));
+        let lua = vm.get_main_state().get_lua().clone();
+        let from_module = |path: &str| resolve(&lua, Some(module.clone()), "=test", path);
+        assert_eq!(
+            from_module("./Sibling").unwrap(),
+            "rblx-godot.Workspace.A.B.Sibling"
+        );
+        assert_eq!(
+            from_module("../B/Module").unwrap(),
+            "rblx-godot.Workspace.A.B.Module"
+        );
+        assert_eq!(
+            from_module("@self/Child").unwrap(),
+            "rblx-godot.Workspace.A.B.Module.Child"
+        );
+        assert_eq!(
+            from_module("./Sibling/../Module/./Child").unwrap(),
+            "rblx-godot.Workspace.A.B.Module.Child"
+        );
+        assert_eq!(
+            from_module("@game/Workspace/A").unwrap(),
+            "rblx-godot.Workspace.A"
+        );
+        assert_eq!(from_module("@lib").unwrap(), "rblx-godot.Workspace.Lib");
+        for (path, message) in [
+            ("Sibling", "must start with a valid prefix"),
+            (
+                "./Missing",
+                "Missing is not a valid member of rblx-godot.Workspace.A.B",
+            ),
+            ("./Sibling//Child", "has an empty component"),
+            ("@unknown/Module", "@unknown is not a valid alias"),
+        ] {
+            let err = from_module(path).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", path, err);
+        }
+        let err = resolve(&lua, None, "=test", "./Sibling")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("doesn't belong to a script"), "{}", err);
+    }
+
+    #[test]
+    fn resolves_paths_between_files() {
+        let dir = temp_dir("resolve");
+        write(&dir.join("main.luau"), "");
+        write(&dir.join("util.lua"), "");
+        write(&dir.join("pkg/init.luau"), "");
+        write(&dir.join("pkg/inner.luau"), "");
+        write(&dir.join("vendor/lib.luau"), "");
+        write(
+            &dir.join("pkg/.luaurc"),
+            r#"{ "aliases": { "Vendor": "../vendor", }, }"#,
+        );
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let lua = vm.get_main_state().get_lua().clone();
+        let from = |file: &str, path: &str| {
+            resolve(&lua, None, &format!("@{}", dir.join(file).display()), path)
+        };
+        let err = from("main.luau", "./util").unwrap_err().to_string();
+        assert!(err.contains("filesystem modules are disabled"), "{}", err);
+
+        vm.set_module_root(Some(dir.clone())).unwrap();
+        let name = |file: &str| dir.join(file).display().to_string();
+        assert_eq!(from("main.luau", "./util").unwrap(), name("util.lua"));
+        assert_eq!(from("main.luau", "./pkg").unwrap(), name("pkg/init.luau"));
+        // An init module stands for its directory.
+        assert_eq!(from("pkg/init.luau", "./util").unwrap(), name("util.lua"));
+        assert_eq!(
+            from("pkg/init.luau", "@self/inner").unwrap(),
+            name("pkg/inner.luau")
+        );
+        assert_eq!(
+            from("pkg/inner.luau", "../main").unwrap(),
+            name("main.luau")
+        );
+        assert_eq!(
+            from("pkg/inner.luau", "@vendor/lib").unwrap(),
+            name("vendor/lib.luau")
+        );
+        let err = from("main.luau", "@vendor/lib").unwrap_err().to_string();
+        assert!(err.contains("@vendor is not a valid alias"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_cycles_and_raises_module_errors_again() {
+        let dir = temp_dir("cycle");
+        write(&dir.join("a.luau"), "return require(\"./b\")");
+        write(&dir.join("b.luau"), "return require(\"./a\")");
+        write(
+            &dir.join("failing.luau"),
+            "shared.runs = (shared.runs or 0) + 1\nerror(\"boom\")",
+        );
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        vm.set_module_root(Some(dir.clone())).unwrap();
+        let lua = vm.get_main_state().get_lua().clone();
+
+        let err = require_file(&lua, &dir.join("a.luau"))
+            .unwrap_err()
+            .to_string();
+        let chain = format!(
+            "Cyclic module dependency detected: {a} -> {b} -> {a}",
+            a = dir.join("a.luau").display(),
+            b = dir.join("b.luau").display()
+        );
+        assert!(err.contains(&chain), "{}", err);
+
+        let first = require_file(&lua, &dir.join("failing"))
+            .unwrap_err()
+            .to_string();
+        assert!(first.contains("boom"), "{}", first);
+        let second = require_file(&lua, &dir.join("failing.luau"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            second.contains("Requested module experienced an error while loading"),
+            "{}",
+            second
+        );
+        let runs: i32 = lua.load("return shared.runs").eval().unwrap();
+        assert_eq!(runs, 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,15 +1,69 @@
-use std::{ffi::c_int, mem::take, ptr::slice_from_raw_parts};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use std::{ffi::c_int, mem::take};
 
 use crate::core::WeakManagedInstance;
+use crate::instance::{IDataModel, IGodotBridge};
 use r2g_mlua::{
     ffi::{
-        self, luaL_checknumber, lua_State, lua_gettop, lua_pushnumber, lua_remove, lua_resume,
-        lua_settop, lua_tothread, lua_type, lua_typename, lua_xmove, lua_yield, LUA_ERRRUN,
+        self, luaL_checknumber, lua_State, lua_gettop, lua_pushnumber, lua_resume, lua_settop,
+        lua_tothread, lua_xmove, lua_yield, LUA_ERRERR, LUA_ERRMEM, LUA_ERRRUN,
     },
     prelude::*,
 };
 
-use super::{borrowck_ignore_mut, get_state, get_thread_identity, RblxVM, RwLockWriteGuard};
+use super::{
+    borrowck_ignore_mut, get_current_identity, get_state, get_thread_identity, RblxVM,
+    RwLockWriteGuard, ScriptError, ThreadIdentity,
+};
+
+/// Creates a thread running `func` with the given identity. Threads made from Rust have no Luau parent
+/// to inherit one from, so without this they would run unrestricted.
+fn create_thread_as(
+    lua: &Lua,
+    func: LuaFunction,
+    identity: Option<ThreadIdentity>,
+) -> LuaResult<LuaThread> {
+    let thread = lua.create_thread(func)?;
+    if let Some(identity) = identity {
+        get_state(lua).set_thread_identity(thread.clone(), identity);
+    }
+    Ok(thread)
+}
+
+/// The identity of the running thread, which the threads it starts inherit.
+#[inline]
+fn inherited_identity(lua: &Lua) -> Option<ThreadIdentity> {
+    get_current_identity(lua).map(|x| x.clone())
+}
+
+/// Resumes `thread` with the `nargs` values on top of its stack.
+/// Returns the error it died with, captured while its stack is still there to tell where it came from.
+unsafe fn resume_capturing(
+    lua: &Lua,
+    lua_raw: *mut lua_State,
+    thread: *mut lua_State,
+    nargs: c_int,
+) -> Option<ScriptError> {
+    let mut _nres = 0;
+    match lua_resume(thread, lua_raw, nargs, &raw mut _nres) {
+        LUA_ERRRUN | LUA_ERRMEM | LUA_ERRERR => Some(ScriptError::capture(lua, lua_raw, thread)),
+        _ => None,
+    }
+}
+
+/// Hands an uncaught error to ScriptContext, which fires `Error` and writes it to the output.
+fn report_error(lua: &Lua, error: Option<ScriptError>) {
+    if let Some(error) = error {
+        let script_context = get_state(lua).get_vm().get_script_context();
+        if let Err(err) = script_context.report_error(lua, error) {
+            get_state(lua)
+                .get_vm()
+                .get_log_service()
+                .log_err(lua, format!("ScriptContext.Error failed: {}", err));
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct TaskScheduler {
@@ -21,6 +75,9 @@ pub struct TaskScheduler {
     parallel_dispatch: bool,
 }
 
+/// How long shutdown sleeps between scheduler cycles while the BindToClose functions are running.
+const CLOSE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum ParallelDispatch {
     Desynchronized,
@@ -69,9 +126,10 @@ impl ITaskScheduler for TaskScheduler {
     }
 }
 impl dyn ITaskScheduler {
+    /// The scheduler clock of the VM `lua` belongs to.
     #[inline(always)]
-    fn clock() -> f64 {
-        unsafe { ffi::lua_clock() }
+    fn clock(lua: &Lua) -> f64 {
+        get_state(lua).get_clock()
     }
     #[inline(always)]
     fn dispatch_to_table(&self, parallel: ParallelDispatch) -> usize {
@@ -87,12 +145,42 @@ impl dyn ITaskScheduler {
         func: LuaFunction,
         args: impl IntoLuaMulti,
     ) -> LuaResult<LuaThread> {
-        let thread = lua.create_thread(func)?;
-        let _: LuaResult<()> = thread.resume(args);
+        self.spawn_func_as(lua, func, args, inherited_identity(lua))
+    }
+    /// Same as [`spawn_func`](Self::spawn_func), with the thread running as `identity`.
+    pub fn spawn_func_as(
+        &self,
+        lua: &Lua,
+        func: LuaFunction,
+        args: impl IntoLuaMulti,
+        identity: Option<ThreadIdentity>,
+    ) -> LuaResult<LuaThread> {
+        let thread = create_thread_as(lua, func, identity)?;
+        let mut error = None;
+        unsafe {
+            lua.exec_raw::<()>((thread.clone(), args), |lua_raw: *mut lua_State| {
+                let nargs = lua_gettop(lua_raw) - 1;
+                let thread = lua_tothread(lua_raw, 1);
+                lua_xmove(lua_raw, thread, nargs);
+                error = resume_capturing(lua, lua_raw, thread, nargs);
+                lua_settop(lua_raw, 0);
+            })?;
+        }
+        report_error(lua, error);
         Ok(thread)
     }
-    pub fn spawn_thread(&self, thr: LuaThread) -> LuaResult<LuaThread> {
-        let _ = thr.resume::<()>(());
+    pub fn spawn_thread(&self, lua: &Lua, thr: LuaThread) -> LuaResult<LuaThread> {
+        if thr.status() == LuaThreadStatus::Resumable {
+            let mut error = None;
+            unsafe {
+                lua.exec_raw::<()>((thr.clone(),), |lua_raw: *mut lua_State| {
+                    let thread = lua_tothread(lua_raw, 1);
+                    error = resume_capturing(lua, lua_raw, thread, 0);
+                    lua_settop(lua_raw, 0);
+                })?;
+            }
+            report_error(lua, error);
+        }
         Ok(thr)
     }
     pub fn defer_high_priority<F, A, R>(
@@ -109,7 +197,7 @@ impl dyn ITaskScheduler {
     {
         let parallel = self.dispatch_to_table(parallel);
         let func = lua.create_function_mut(f)?;
-        let thread = lua.create_thread(func)?;
+        let thread = create_thread_as(lua, func, inherited_identity(lua))?;
         let mut args_count: i32 = 0;
         unsafe {
             lua.exec_raw::<()>((thread.clone(), args), |lua_raw: *mut lua_State| {
@@ -138,7 +226,7 @@ impl dyn ITaskScheduler {
     {
         let parallel = self.dispatch_to_table(parallel);
         let func = lua.create_function_mut(f)?;
-        let thread = lua.create_thread(func)?;
+        let thread = create_thread_as(lua, func, inherited_identity(lua))?;
         let mut args_count: i32 = 0;
         unsafe {
             lua.exec_raw::<()>((thread.clone(), args), |lua_raw: *mut lua_State| {
@@ -159,9 +247,20 @@ impl dyn ITaskScheduler {
         func: LuaFunction,
         args: impl IntoLuaMulti,
         parallel: ParallelDispatch,
+    ) -> LuaResult<LuaThread> {
+        self.defer_func_as(lua, func, args, parallel, inherited_identity(lua))
+    }
+    /// Same as [`defer_func`](Self::defer_func), with the thread running as `identity`.
+    pub fn defer_func_as(
+        &mut self,
+        lua: &Lua,
+        func: LuaFunction,
+        args: impl IntoLuaMulti,
+        parallel: ParallelDispatch,
+        identity: Option<ThreadIdentity>,
     ) -> LuaResult<LuaThread> {
         let parallel = self.dispatch_to_table(parallel);
-        let thread = lua.create_thread(func)?;
+        let thread = create_thread_as(lua, func, identity)?;
         let mut args_count: i32 = 0;
         unsafe {
             lua.exec_raw::<()>((thread.clone(), args), |lua_raw: *mut lua_State| {
@@ -194,7 +293,7 @@ impl dyn ITaskScheduler {
         seconds: f64,
     ) -> LuaResult<LuaThread> {
         let parallel = self.dispatch_to_table(parallel);
-        let thread = lua.create_thread(func)?;
+        let thread = create_thread_as(lua, func, inherited_identity(lua))?;
         let mut args_count: i32 = 0;
         unsafe {
             lua.exec_raw::<()>((thread.clone(), args), |lua_raw: *mut lua_State| {
@@ -208,12 +307,13 @@ impl dyn ITaskScheduler {
         self.get_task_scheduler_mut().delay_threads[parallel].push((
             thread.clone(),
             args_count as u32,
-            Self::clock() + seconds,
+            Self::clock(lua) + seconds,
         ));
         Ok(thread)
     }
     pub fn delay_thread(
         &mut self,
+        lua: &Lua,
         thread: LuaThread,
         parallel: ParallelDispatch,
         seconds: f64,
@@ -222,7 +322,7 @@ impl dyn ITaskScheduler {
         self.get_task_scheduler_mut().delay_threads[parallel].push((
             thread.clone(),
             0,
-            Self::clock() + seconds,
+            Self::clock(lua) + seconds,
         ));
         Ok(thread)
     }
@@ -232,44 +332,13 @@ impl dyn ITaskScheduler {
         for (thread, args) in threads {
             unsafe {
                 if thread.status() == LuaThreadStatus::Resumable {
+                    let mut error = None;
                     lua.exec_raw::<()>((thread,), |lua_raw: *mut lua_State| {
                         let thread = lua_tothread(lua_raw, 1);
-                        let mut _nres = 0;
-                        if lua_resume(thread, lua_raw, args as i32, &raw mut _nres) == LUA_ERRRUN {
-                            lua_xmove(thread, lua_raw, 1);
-                            if lua_gettop(lua_raw) == 0 {
-                                ffi::lua_pushstring(lua_raw, c"unknown error".as_ptr());
-                            }
-                            let e_type = lua_type(lua_raw, -1);
-                            if e_type == ffi::LUA_TUSERDATA {
-                                ffi::lua_getfield(lua_raw, -1, c"__tostring".as_ptr());
-                                ffi::lua_pushvalue(lua_raw, -2);
-                                ffi::lua_call(lua_raw, 1, 1);
-                            }
-                            dbg!(lua_gettop(lua_raw));
-                            let typename = std::ffi::CStr::from_ptr(lua_typename(
-                                lua_raw,
-                                lua_type(lua_raw, -1),
-                            ));
-                            dbg!(typename);
-                            let mut len = 0;
-                            let string_slice = slice_from_raw_parts(
-                                ffi::lua_tolstring(lua_raw, -1, &raw mut len).cast(),
-                                len,
-                            )
-                            .as_ref()
-                            .unwrap();
-                            let string = std::str::from_utf8(string_slice).unwrap();
-                            get_state(lua).get_vm().get_log_service().log_err(
-                                lua,
-                                format!(
-                                    "Error in thread 0x{:x} during defer: {}",
-                                    thread as usize, string
-                                ),
-                            );
-                        }
+                        error = resume_capturing(lua, lua_raw, thread, args as i32);
                         lua_settop(lua_raw, 0);
                     })?;
+                    report_error(lua, error);
                 }
             }
         }
@@ -285,14 +354,15 @@ impl dyn ITaskScheduler {
             new_threads.reserve(threads.len());
             for (thread, args, time) in threads {
                 unsafe {
-                    if Self::clock() >= time {
+                    if Self::clock(lua) >= time {
                         if thread.status() == LuaThreadStatus::Resumable {
+                            let mut error = None;
                             lua.exec_raw::<()>((thread,), |lua_raw: *mut lua_State| {
                                 let thread = lua_tothread(lua_raw, 1);
-                                let mut _nres = 0;
-                                lua_resume(thread, lua_raw, args as i32, &raw mut _nres);
+                                error = resume_capturing(lua, lua_raw, thread, args as i32);
                                 lua_settop(lua_raw, 0);
                             })?;
+                            report_error(lua, error);
                         }
                     } else if thread.status() == LuaThreadStatus::Resumable {
                         min = min.min(time);
@@ -309,49 +379,16 @@ impl dyn ITaskScheduler {
             for (thread, args, time, started) in threads {
                 unsafe {
                     debug_assert!(thread.status() != LuaThreadStatus::Running);
-                    if Self::clock() >= time {
+                    if Self::clock(lua) >= time {
                         if thread.status() == LuaThreadStatus::Resumable {
+                            let mut error = None;
                             lua.exec_raw::<()>((thread,), |lua_raw: *mut lua_State| {
                                 let thread = lua_tothread(lua_raw, 1);
-                                let mut _nres = 0;
-                                lua_pushnumber(thread, Self::clock() - started);
-                                if lua_resume(thread, lua_raw, 1, &raw mut _nres) == LUA_ERRRUN {
-                                    lua_xmove(thread, lua_raw, 1);
-                                    if lua_gettop(lua_raw) == 0 {
-                                        ffi::lua_pushstring(lua_raw, c"unknown error".as_ptr());
-                                    }
-                                    let e_type = lua_type(lua_raw, -1);
-                                    if e_type == ffi::LUA_TUSERDATA {
-                                        ffi::lua_getmetatable(lua_raw, -1);
-                                        ffi::lua_getfield(lua_raw, -1, c"__tostring".as_ptr());
-                                        lua_remove(lua_raw, -2);
-                                        ffi::lua_pushvalue(lua_raw, -2);
-                                        ffi::lua_call(lua_raw, 1, 1);
-                                    }
-                                    dbg!(lua_gettop(lua_raw));
-                                    let typename = std::ffi::CStr::from_ptr(lua_typename(
-                                        lua_raw,
-                                        lua_type(lua_raw, -1),
-                                    ));
-                                    dbg!(typename);
-                                    let mut len = 0;
-                                    let string_slice = slice_from_raw_parts(
-                                        ffi::lua_tolstring(lua_raw, -1, &raw mut len).cast(),
-                                        len,
-                                    )
-                                    .as_ref()
-                                    .unwrap();
-                                    let string = std::str::from_utf8(string_slice).unwrap();
-                                    get_state(lua).get_vm().get_log_service().log_err(
-                                        lua,
-                                        format!(
-                                            "Error in thread 0x{:x} during wait: {}",
-                                            thread as usize, string
-                                        ),
-                                    );
-                                }
+                                lua_pushnumber(thread, Self::clock(lua) - started);
+                                error = resume_capturing(lua, lua_raw, thread, 1);
                                 lua_settop(lua_raw, 0);
                             })?;
+                            report_error(lua, error);
                         }
                     } else if thread.status() == LuaThreadStatus::Resumable {
                         min = min.min(time);
@@ -361,7 +398,7 @@ impl dyn ITaskScheduler {
             }
         }
 
-        Ok(min > Self::clock())
+        Ok(min > Self::clock(lua))
     }
     #[inline]
     fn watchdog_check(&self, lua: &Lua) -> bool {
@@ -397,6 +434,33 @@ impl dyn ITaskScheduler {
             _ => Ok(()),
         }
     }
+    /// Closes every thread waiting in the scheduler, nothing gets resumed afterwards.
+    pub fn cancel_all(&mut self) {
+        let task = self.get_task_scheduler_mut();
+        let mut threads = Vec::new();
+        for parallel in 0..2 {
+            threads.extend(
+                take(&mut task.defer_threads[parallel])
+                    .into_iter()
+                    .map(|x| x.0),
+            );
+            threads.extend(
+                take(&mut task.delay_threads[parallel])
+                    .into_iter()
+                    .map(|x| x.0),
+            );
+            threads.extend(
+                take(&mut task.wait_threads[parallel])
+                    .into_iter()
+                    .map(|x| x.0),
+            );
+        }
+        for thread in threads {
+            if thread.status() == LuaThreadStatus::Resumable {
+                let _ = thread.close();
+            }
+        }
+    }
     pub fn cancel_script(&mut self, lua: &Lua, script: &WeakManagedInstance) -> LuaResult<()> {
         for parallel in 0..1 {
             let mut v: Vec<LuaThread> = self.get_task_scheduler().defer_threads[parallel]
@@ -430,8 +494,8 @@ impl dyn ITaskScheduler {
         task.get_task_scheduler_mut().wait_threads[dispatch].push((
             lua.current_thread(),
             0,
-            time + Self::clock(),
-            Self::clock(),
+            time + Self::clock(lua),
+            Self::clock(lua),
         ));
         Ok(())
     }
@@ -500,28 +564,91 @@ impl GlobalTaskScheduler {
     pub fn as_dyn_mut(&mut self) -> &mut dyn ITaskScheduler {
         unsafe { &mut *((&raw mut self.task) as *mut dyn ITaskScheduler) }
     }
-    pub fn frame_step(mut vm: RwLockWriteGuard<RblxVM>, _: f64) -> LuaResult<()> {
+    pub fn frame_step(mut vm: RwLockWriteGuard<RblxVM>, delta: f64) -> LuaResult<()> {
         // SAFETY: This function avoids the borrow checker since the main state outlives global task scheduler.
         let main_state = unsafe { borrowck_ignore_mut(vm.get_main_state()) };
 
+        let frame_start = Instant::now();
         let lua = main_state.get_lua().clone();
         let task = main_state.get_task_scheduler_mut();
         unsafe { vm.set_global_lock_state(false) };
         vm.push_global_lock_atomic();
-        vm.watchdog_reset();
-        task.defer_cycle(&lua, false)?;
-        task.delay_cycle(&lua, false)?;
-        // todo! run service events
+        // The lock stack has to be popped even if the frame fails, other VMs of the thread would use it otherwise.
+        let result = (|| -> LuaResult<()> {
+            vm.watchdog_reset();
+            vm.poll_debugger();
+            // Outside of a running simulation, frames only run when stepped.
+            let Some(delta) = vm.next_frame_delta(delta) else {
+                return Ok(());
+            };
+            vm.advance_game_time(delta);
+            vm.poll_source_watchers();
+            <dyn IGodotBridge>::fire_pending_signals(&*vm.get_godot_bridge(), &lua)?;
+            task.defer_cycle(&lua, false)?;
+            task.delay_cycle(&lua, false)?;
+            // todo! run service events
+            let scheduler_time = frame_start.elapsed().as_secs_f64();
 
-        vm.get_all_states().iter().for_each(|state| {
-            let write = state.write();
-            write.gc();
-        });
+            let gc_start = Instant::now();
+            vm.get_all_states().iter().for_each(|state| {
+                let mut write = state.write();
+                write.gc();
+                write.apply_memory_limit();
+            });
+            let gc_time = gc_start.elapsed().as_secs_f64();
+            vm.update_frame_stats(frame_start.elapsed().as_secs_f64(), scheduler_time, gc_time);
+            Ok(())
+        })();
 
         vm.pop_global_lock_atomic();
         unsafe { vm.set_global_lock_state(true) };
         drop(vm);
-        Ok(())
+        result
+    }
+    /// Shuts the VM down. The functions bound with BindToClose run until they all returned or `timeout` seconds
+    /// passed, then Close fires and every thread gets cancelled. The Luau states are only kept alive by the VM
+    /// afterwards, so dropping it drops them.
+    pub fn close(mut vm: RwLockWriteGuard<RblxVM>, timeout: f64) -> LuaResult<()> {
+        // SAFETY: Same as frame_step.
+        let main_state = unsafe { borrowck_ignore_mut(vm.get_main_state()) };
+
+        let lua = main_state.get_lua().clone();
+        let task = main_state.get_task_scheduler_mut();
+        let deadline = Instant::now() + Duration::try_from_secs_f64(timeout).unwrap_or_default();
+        unsafe { vm.set_global_lock_state(false) };
+        vm.push_global_lock_atomic();
+        let result = (|| -> LuaResult<()> {
+            vm.watchdog_reset();
+            let game = vm.get_game_instance();
+            let threads = <dyn IDataModel>::fire_bind_to_close(&*game, &lua)?;
+            let mut last_cycle = Instant::now();
+            loop {
+                task.defer_cycle(&lua, false)?;
+                task.delay_cycle(&lua, false)?;
+                if Instant::now() >= deadline
+                    || threads
+                        .iter()
+                        .all(|x| x.status() != LuaThreadStatus::Resumable)
+                {
+                    break;
+                }
+                sleep(CLOSE_POLL_INTERVAL);
+                // The clock has to move for the functions to finish, even if the simulation is paused or the
+                // clock is manual.
+                let now = Instant::now();
+                vm.advance_clock(now.duration_since(last_cycle).as_secs_f64());
+                last_cycle = now;
+                vm.watchdog_reset();
+            }
+            game.fire_close(&lua)?;
+            task.defer_cycle(&lua, false)
+        })();
+        vm.release_all();
+
+        vm.pop_global_lock_atomic();
+        unsafe { vm.set_global_lock_state(true) };
+        drop(vm);
+        result
     }
     pub fn new() -> GlobalTaskScheduler {
         GlobalTaskScheduler {
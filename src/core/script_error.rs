@@ -0,0 +1,121 @@
+use std::ffi::{c_char, c_int, CStr};
+use std::mem::zeroed;
+
+use r2g_mlua::ffi::{self, lua_Debug, lua_State};
+use r2g_mlua::prelude::*;
+
+use super::{get_state, ManagedInstance};
+
+/// A frame of the traceback of a script error.
+#[derive(Clone, Debug)]
+pub struct StackFrame {
+    /// The full name of the script, or the chunk name for code that doesn't come from one.
+    pub source: String,
+    pub line: c_int,
+    pub function: Option<String>,
+}
+
+impl StackFrame {
+    /// Formats the frame like Studio's output does, `Script 'Workspace.Script', Line 3 - function f`.
+    pub fn to_output_line(&self) -> String {
+        match &self.function {
+            Some(function) => format!(
+                "Script '{}', Line {} - function {}",
+                self.source, self.line, function
+            ),
+            None => format!("Script '{}', Line {}", self.source, self.line),
+        }
+    }
+}
+
+/// An uncaught error, along with the traceback of the thread that raised it.
+#[derive(Clone, Debug)]
+pub struct ScriptError {
+    pub message: String,
+    pub traceback: Vec<StackFrame>,
+    pub script: Option<ManagedInstance>,
+}
+
+unsafe fn to_string(s: *const c_char) -> Option<String> {
+    (!s.is_null()).then(|| CStr::from_ptr(s).to_string_lossy().into_owned())
+}
+
+/// Turns a chunk name into the name shown to users, `<script at Workspace.Script>` becomes `Workspace.Script`.
+pub fn chunk_display_name(chunk: &str) -> &str {
+    chunk
+        .strip_prefix("<script at ")
+        .and_then(|x| x.strip_suffix('>'))
+        .or_else(|| chunk.strip_prefix('='))
+        .or_else(|| chunk.strip_prefix('@'))
+        .unwrap_or(chunk)
+}
+
+impl ScriptError {
+    /// Takes the error `thread` died with, moving it to `lua_raw` to turn it into a message.
+    /// Luau leaves the call stack of a dead thread as it was when the error got raised, the traceback
+    /// is read from it, so this has to be called before the thread gets closed or reused.
+    pub(crate) unsafe fn capture(
+        lua: &Lua,
+        lua_raw: *mut lua_State,
+        thread: *mut lua_State,
+    ) -> ScriptError {
+        let mut traceback = Vec::new();
+        let mut ar: lua_Debug = zeroed();
+        let mut level = 0;
+        while ffi::lua_getinfo(thread, level, c"sln".as_ptr(), &mut ar) != 0 {
+            level += 1;
+            if to_string(ar.what).is_some_and(|x| x == "C") {
+                continue;
+            }
+            let source = to_string(ar.source).unwrap_or_default();
+            traceback.push(StackFrame {
+                source: chunk_display_name(&source).into(),
+                line: ar.currentline,
+                function: to_string(ar.name),
+            });
+        }
+        let message = if ffi::lua_gettop(thread) == 0 {
+            String::from("unknown error")
+        } else {
+            ffi::lua_xmove(thread, lua_raw, 1);
+            let mut len = 0;
+            let s = ffi::luaL_tolstring(lua_raw, -1, &mut len);
+            let message = String::from_utf8_lossy(std::slice::from_raw_parts(s.cast(), len));
+            let message = clean_message(&message);
+            ffi::lua_pop(lua_raw, 2);
+            message
+        };
+        // The identity of the thread says which script it runs for. The `script` global of a function's environment
+        // can't be trusted, scripts are free to replace it.
+        let script = get_state(lua)
+            .get_thread_identity_pointer(thread.cast())
+            .and_then(|x| x.script.as_ref()?.upgrade());
+        ScriptError {
+            message,
+            traceback,
+            script,
+        }
+    }
+    /// Formats the traceback the way `ScriptContext.Error` hands it out, a `Source:line function name` per frame.
+    pub fn stack_trace(&self) -> String {
+        self.traceback
+            .iter()
+            .map(|x| match &x.function {
+                Some(function) => format!("{}:{} function {}\n", x.source, x.line, function),
+                None => format!("{}:{}\n", x.source, x.line),
+            })
+            .collect()
+    }
+}
+
+/// Luau prefixes messages with the quoted chunk name, which doesn't read well for scripts.
+fn clean_message(message: &str) -> String {
+    if let Some((chunk, rest)) = message
+        .strip_prefix("[string \"")
+        .and_then(|x| x.split_once("\"]"))
+    {
+        format!("{}{}", chunk_display_name(chunk), rest)
+    } else {
+        message.into()
+    }
+}
@@ -1,5 +1,15 @@
+use std::collections::HashMap;
 use std::ops::BitOr;
-#[derive(Eq, PartialEq, Clone, Copy, PartialOrd, Ord)]
+
+use r2g_mlua::prelude::*;
+
+use super::{
+    get_class_registry, get_current_identity, get_state, DynInstance, ManagedInstance,
+    WeakManagedInstance,
+};
+use crate::userdata::{enums::ScriptCapability, SecurityCapabilities};
+
+#[derive(Eq, PartialEq, Clone, Copy, PartialOrd, Ord, Debug)]
 pub struct SecurityContext(u8);
 impl SecurityContext {
     pub const NONE: SecurityContext = SecurityContext(0);
@@ -23,6 +33,18 @@ impl SecurityContext {
     pub const fn has(self, other: SecurityContext) -> bool {
         (self.0 & other.0) == other.0
     }
+    pub const fn capability_name(self) -> &'static str {
+        match self.0 {
+            0x0 => "None",
+            0x1 => "Plugin",
+            0x2 => "RobloxPlace",
+            0x4 => "WritePlayer",
+            0x10 => "LocalUser",
+            0x1D => "RobloxScript",
+            0x1F => "RobloxEngine",
+            _ => "Unknown",
+        }
+    }
 }
 
 impl Into<u8> for SecurityContext {
@@ -91,3 +113,345 @@ impl ThreadIdentityType {
         }
     }
 }
+
+/// Errors if the identity of the running thread lacks `required`. Threads without an identity are treated as `Anon`.
+pub fn check_security_context(lua: &Lua, required: SecurityContext, member: &str) -> LuaResult<()> {
+    let contexts = get_current_identity(lua)
+        .map(|x| x.security_identity)
+        .unwrap_or_default()
+        .get_security_contexts();
+    if contexts.has(required) {
+        Ok(())
+    } else {
+        Err(LuaError::RuntimeError(format!(
+            "The current thread cannot access '{}' (lacking capability {})",
+            member,
+            required.capability_name()
+        )))
+    }
+}
+
+/// The capabilities a script runs with when it is inside a sandboxed container.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScriptSandbox {
+    pub capabilities: SecurityCapabilities,
+    /// The nearest sandboxed ancestor of the script. Without `AccessOutsideWrite`, only it and its descendants can be modified.
+    pub container: WeakManagedInstance,
+}
+
+impl ScriptSandbox {
+    /// Computes the sandbox of a script. Nested containers can only restrict the capabilities further.
+    pub fn of(script: &ManagedInstance) -> Option<ScriptSandbox> {
+        let mut sandbox: Option<ScriptSandbox> = None;
+        let mut current = Some(script.clone());
+        while let Some(instance) = current {
+            if instance.get_sandboxed() {
+                let capabilities = instance.get_capabilities();
+                match sandbox.as_mut() {
+                    Some(x) => x.capabilities = x.capabilities.intersection(capabilities),
+                    None => {
+                        sandbox = Some(ScriptSandbox {
+                            capabilities,
+                            container: instance.downgrade(),
+                        })
+                    }
+                }
+            }
+            current = instance.get_parent();
+        }
+        sandbox
+    }
+    pub fn contains(&self, instance: &DynInstance) -> bool {
+        match self.container.upgrade() {
+            Some(container) => {
+                *container == *instance || instance.is_descendant_of(container).unwrap_or(false)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Reports a capability violation to `LogService` and returns the error raised in the offending thread.
+pub fn capability_violation(lua: &Lua, capability: ScriptCapability, member: &str) -> LuaError {
+    let message = format!(
+        "The current thread cannot access '{}' (lacking capability {})",
+        member,
+        capability.name()
+    );
+    get_state(lua)
+        .get_log_service()
+        .log_warn(lua, message.clone());
+    LuaError::RuntimeError(message)
+}
+
+fn get_current_sandbox(lua: &Lua) -> Option<ScriptSandbox> {
+    get_current_identity(lua).and_then(|x| x.sandbox.clone())
+}
+
+/// Errors if the running thread is sandboxed without `capability`.
+pub fn check_capability(lua: &Lua, capability: ScriptCapability, member: &str) -> LuaResult<()> {
+    match get_current_sandbox(lua) {
+        Some(sandbox) if !sandbox.capabilities.contains(capability) => {
+            Err(capability_violation(lua, capability, member))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Errors if a sandboxed thread tries to hand out capabilities it doesn't have itself.
+pub fn check_grant_capabilities(
+    lua: &Lua,
+    capabilities: SecurityCapabilities,
+    member: &str,
+) -> LuaResult<()> {
+    match get_current_sandbox(lua) {
+        Some(sandbox) => {
+            if !sandbox
+                .capabilities
+                .contains(ScriptCapability::CapabilityControl)
+            {
+                return Err(capability_violation(
+                    lua,
+                    ScriptCapability::CapabilityControl,
+                    member,
+                ));
+            }
+            match capabilities
+                .iter()
+                .find(|x| !sandbox.capabilities.contains(*x))
+            {
+                Some(missing) => Err(capability_violation(lua, missing, member)),
+                None => Ok(()),
+            }
+        }
+        None => Ok(()),
+    }
+}
+
+/// The security the API dump requires to read and write a member.
+#[derive(Clone, Copy, Debug)]
+struct MemberAccess {
+    read: SecurityContext,
+    write: SecurityContext,
+}
+
+/// Caches what [`check_member_access`] reads from the class registry, so indexing an instance doesn't take the
+/// registry lock. Classes registered after the state was created aren't seen if a member was already accessed.
+#[derive(Debug, Default)]
+pub struct MemberAccessCache {
+    classes: HashMap<&'static str, (ScriptCapability, HashMap<String, MemberAccess>)>,
+}
+
+impl MemberAccessCache {
+    fn get(&mut self, class_name: &'static str, member: &str) -> (ScriptCapability, MemberAccess) {
+        let (capability, members) = self.classes.entry(class_name).or_insert_with(|| {
+            (
+                get_class_registry().get_required_capability(class_name),
+                HashMap::new(),
+            )
+        });
+        let access = match members.get(member) {
+            Some(access) => *access,
+            None => {
+                let registry = get_class_registry();
+                let security = |write| {
+                    registry
+                        .get_reflected_member_security(class_name, member, write)
+                        .unwrap_or(SecurityContext::NONE)
+                };
+                let access = MemberAccess {
+                    read: security(false),
+                    write: security(true),
+                };
+                members.insert(member.into(), access);
+                access
+            }
+        };
+        (*capability, access)
+    }
+}
+
+/// Errors if the running thread may not access `member` of `instance`, as required by the API dump and the
+/// sandbox of the thread. The security of implemented members is checked by their accessors.
+pub fn check_member_access(
+    lua: &Lua,
+    instance: &DynInstance,
+    member: &str,
+    write: bool,
+) -> LuaResult<()> {
+    let class_name = instance.get_class_name();
+    let (capability, access) = get_state(lua)
+        .get_member_access_cache()
+        .get(class_name, member);
+    let required = if write { access.write } else { access.read };
+    if required != SecurityContext::NONE {
+        check_security_context(lua, required, &format!("{}.{}", class_name, member))?;
+    }
+    if let Some(sandbox) = get_current_sandbox(lua) {
+        let member = format!("{}.{}", class_name, member);
+        if !sandbox.capabilities.contains(capability) {
+            return Err(capability_violation(lua, capability, &member));
+        }
+        if write
+            && !sandbox
+                .capabilities
+                .contains(ScriptCapability::AccessOutsideWrite)
+            && !sandbox.contains(instance)
+        {
+            return Err(capability_violation(
+                lua,
+                ScriptCapability::AccessOutsideWrite,
+                &member,
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use r2g_mlua::prelude::*;
+
+    use super::ScriptSandbox;
+    use crate::core::{ManagedInstance, RblxVM, ThreadIdentity, ThreadIdentityType};
+    use crate::userdata::{enums::ScriptCapability, SecurityCapabilities};
+
+    #[test]
+    fn threads_started_by_a_sandboxed_thread_stay_sandboxed() {
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let workspace: ManagedInstance = vm.get_workspace().cast_from_sized().unwrap();
+        let state = vm.get_main_state();
+        let lua = state.get_lua().clone();
+        let func = lua
+            .load(
+                r#"
+                local results = {}
+                results.main = pcall(Instance.new, "Folder")
+                task.spawn(function()
+                    results.spawn = pcall(Instance.new, "Folder")
+                end)
+                coroutine.resume(coroutine.create(function()
+                    results.coroutine = pcall(Instance.new, "Folder")
+                end))
+                coroutine.wrap(function()
+                    results.wrap = pcall(Instance.new, "Folder")
+                end)()
+                workspace.ChildAdded:Connect(function()
+                    results.signal = pcall(Instance.new, "Folder")
+                end)
+                return results
+                "#,
+            )
+            .into_function()
+            .unwrap();
+        let thread = lua.create_thread(func).unwrap();
+        state.set_thread_identity(
+            thread.clone(),
+            ThreadIdentity {
+                security_identity: ThreadIdentityType::Script,
+                script: None,
+                sandbox: Some(ScriptSandbox {
+                    capabilities: SecurityCapabilities::empty().with(ScriptCapability::Basic),
+                    container: workspace.downgrade(),
+                }),
+            },
+        );
+        // Threads made from Rust get their parent pushed first by the thread event callback, see run_module.
+        let returned: LuaMultiValue = thread.resume(()).unwrap();
+        let results = LuaTable::from_lua(returned.into_iter().last().unwrap(), &lua).unwrap();
+        // Fired from outside the sandbox, the callback still runs as the thread that connected it.
+        lua.load(r#"Instance.new("Folder", workspace)"#)
+            .exec()
+            .unwrap();
+        for key in ["main", "spawn", "coroutine", "wrap", "signal"] {
+            assert_eq!(
+                results.get::<Option<bool>>(key).unwrap(),
+                Some(false),
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn sandboxed_scripts_need_capabilities_for_globals_and_loading_code() {
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let workspace: ManagedInstance = vm.get_workspace().cast_from_sized().unwrap();
+        let state = vm.get_main_state();
+        let lua = state.get_lua().clone();
+        let run = |state: &mut crate::core::LuauState, capabilities: SecurityCapabilities| {
+            let func = lua
+                .load(
+                    r#"
+                    local results = {}
+                    results.loadstring = pcall(loadstring, "return 1")
+                    results.globals = pcall(function() return _G.x end)
+                    results.shared = pcall(function() return shared.x end)
+                    results.service = pcall(function() return game:GetService("ScriptContext").Name end)
+                    return results
+                    "#,
+                )
+                .set_environment(state.create_sandboxed_env().unwrap())
+                .into_function()
+                .unwrap();
+            let thread = lua.create_thread(func).unwrap();
+            state.set_thread_identity(
+                thread.clone(),
+                ThreadIdentity {
+                    security_identity: ThreadIdentityType::Script,
+                    script: None,
+                    sandbox: Some(ScriptSandbox {
+                        capabilities,
+                        container: workspace.downgrade(),
+                    }),
+                },
+            );
+            let returned: LuaMultiValue = thread.resume(()).unwrap();
+            LuaTable::from_lua(returned.into_iter().last().unwrap(), &lua).unwrap()
+        };
+        let keys = ["loadstring", "globals", "shared", "service"];
+
+        let basic = SecurityCapabilities::empty().with(ScriptCapability::Basic);
+        let denied = run(state, basic);
+        for key in keys {
+            assert!(!denied.get::<bool>(key).unwrap(), "{}", key);
+        }
+        let history: Vec<String> = lua
+            .load(
+                r#"
+                local messages = {}
+                for _, entry in game:GetService("LogService"):GetLogHistory() do
+                    table.insert(messages, entry[1])
+                end
+                return messages
+                "#,
+            )
+            .eval()
+            .unwrap();
+        for expected in [
+            "'loadstring' (lacking capability LoadString)",
+            "'_G' (lacking capability ScriptGlobals)",
+            "'shared' (lacking capability ScriptGlobals)",
+            "'ScriptContext.Name' (lacking capability CapabilityControl)",
+        ] {
+            assert!(
+                history.iter().any(|x| x.ends_with(expected)),
+                "{} not logged",
+                expected
+            );
+        }
+
+        let allowed = run(
+            state,
+            basic
+                .with(ScriptCapability::LoadString)
+                .with(ScriptCapability::ScriptGlobals)
+                .with(ScriptCapability::CapabilityControl),
+        );
+        for key in keys {
+            assert!(allowed.get::<bool>(key).unwrap(), "{}", key);
+        }
+    }
+}
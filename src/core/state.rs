@@ -1,33 +1,83 @@
 use std::mem::MaybeUninit;
+use std::path::{Path, PathBuf};
 use std::ptr::null_mut;
+use std::sync::Arc;
 use std::{collections::HashMap, ffi::c_void, mem::transmute, ptr::addr_of_mut};
 
+use super::debug::{install_hooks, register_chunk};
 use super::lua_macros::args_to_string;
+use super::native::{is_native_module, native_functions};
 use super::scheduler::ITaskScheduler;
 use super::ParallelDispatch::{Default, Synchronized};
 use super::{
-    borrowck_ignore, FastFlag, FastFlags, Irc, RwLock, RwLockReadGuard, RwLockWriteGuard,
-    TaskScheduler, Trc,
+    borrowck_ignore, Debugger, FastFlag, FastFlags, Irc, MemberAccessCache, RwLock,
+    RwLockReadGuard, RwLockWriteGuard, ScriptMemoryTracker, ScriptProfile, ScriptProfiler,
+    StateDebugger, TaskScheduler, Trc,
+};
+use super::{
+    check_capability, chunk_display_name, get_bytecode_cache, require_file, resolve_require,
+    DynInstance, ManagedInstance, ModuleKey, ModuleResult, RblxVM, RequireTarget, ScriptSandbox,
+    ThreadIdentityType, WeakManagedInstance,
 };
-use super::{DynInstance, ManagedInstance, RblxVM, ThreadIdentityType, WeakManagedInstance};
 use crate::instance::{DataModel, IModuleScript, LogService, WeakManagedActor};
-use crate::userdata::register_userdata_singletons;
-use r2g_mlua::{prelude::*, ChunkMode, Compiler};
+use crate::userdata::{enums::ScriptCapability, register_userdata_singletons};
+use r2g_mlua::{ffi, prelude::*, ChunkMode, Compiler};
 
 pub mod registry_keys {
-    pub const VM_REGISTRYKEY: &'static str = "__vm__";
-    pub const STATE_REGISTRYKEY: &'static str = "__state__";
-    pub const LOG_SERVICE_REGISTRYKEY: &'static str = "__log_service__";
-    pub const DATA_MODEL_REGISTRYKEY: &'static str = "__data_model__";
-    pub(super) const TASK_PUSH_WAIT: &'static str = "__task_push_wait__";
-    pub(super) const TASK_PUSH_SYNC_DESYNC: &'static str = "__task_push_sync_desync__";
-    pub const ACTOR_REGISTRYKEY: &'static str = "__actor__";
+    pub const VM_REGISTRYKEY: &str = "__vm__";
+    pub const STATE_REGISTRYKEY: &str = "__state__";
+    pub const LOG_SERVICE_REGISTRYKEY: &str = "__log_service__";
+    pub const DATA_MODEL_REGISTRYKEY: &str = "__data_model__";
+    pub(super) const TASK_PUSH_WAIT: &str = "__task_push_wait__";
+    pub(super) const TASK_PUSH_SYNC_DESYNC: &str = "__task_push_sync_desync__";
+    pub const ACTOR_REGISTRYKEY: &str = "__actor__";
+    pub(crate) const DEBUG_CHUNKS_REGISTRYKEY: &str = "__debug_chunks__";
+    pub(crate) const DEBUG_SOURCES_REGISTRYKEY: &str = "__debug_sources__";
+    pub(crate) const DEBUG_VALUE_REGISTRYKEY: &str = "__debug_value__";
 }
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct ThreadIdentity {
     pub security_identity: ThreadIdentityType,
     pub script: Option<WeakManagedInstance>,
+    /// Set when the thread runs code from inside a sandboxed container.
+    pub sandbox: Option<ScriptSandbox>,
+}
+/// How a chunk gets compiled, each mode has its own optimization and debug levels in the fast flags.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompileMode {
+    /// Interpreted, with the debug information the debugger needs.
+    Debug,
+    Release,
+    /// Compiled to native code where the platform supports it.
+    Native,
+}
+
+impl CompileMode {
+    const fn level_flags(self) -> (FastFlag, FastFlag) {
+        match self {
+            CompileMode::Debug => (FastFlag::OptimizationLevelDebug, FastFlag::DebugLevelDebug),
+            CompileMode::Release => (
+                FastFlag::OptimizationLevelRelease,
+                FastFlag::DebugLevelRelease,
+            ),
+            CompileMode::Native => (
+                FastFlag::OptimizationLevelNative,
+                FastFlag::DebugLevelNative,
+            ),
+        }
+    }
 }
+
+/// A chunk handed to the native code generator. This is what the chunk asked for: the code generator doesn't report
+/// the functions it couldn't compile, those silently run interpreted.
+#[derive(Clone, Debug)]
+pub struct NativeChunk {
+    pub chunk_name: String,
+    /// The `@native` functions, as their line and name, when only those were requested.
+    /// `None` when the whole chunk was.
+    pub functions: Option<Vec<(usize, String)>>,
+}
+
 #[derive(Debug)]
 pub struct LuauState {
     vm: *mut RwLock<RblxVM>,
@@ -35,6 +85,16 @@ pub struct LuauState {
     threads: HashMap<*const c_void, ThreadIdentity>,
     task: MaybeUninit<Box<dyn ITaskScheduler>>,
     actor: Option<WeakManagedActor>,
+    memory: ScriptMemoryTracker,
+    profiler: Option<ScriptProfiler>,
+    debugger: StateDebugger,
+    /// The modules whose body is running, innermost last.
+    requiring: Vec<ModuleKey>,
+    /// The results of the modules loaded from files, by path.
+    file_modules: HashMap<PathBuf, ModuleResult>,
+    native_chunks: Vec<NativeChunk>,
+    codegen_unsupported_warned: bool,
+    member_access: MemberAccessCache,
 }
 impl LuauState {
     fn get_vm_from_lua(lua: &Lua) -> &RwLock<RblxVM> {
@@ -48,19 +108,19 @@ impl LuauState {
             ptr.as_mut().unwrap_unchecked()
         }
     }
-    fn thread_event_callback(lua: &Lua, event: LuaThreadEventInfo) -> Result<(), LuaError> {
-        let is_running = lua.gc_is_running();
-        if is_running {
-            lua.gc_stop();
-        }
-        let state;
-        unsafe {
-            let reg: LuaLightUserData =
-                lua.named_registry_value(registry_keys::STATE_REGISTRYKEY)?;
-            state = reg.0.cast::<LuauState>().as_mut().unwrap();
-        }
+    /// Destroyed events run on the thread being destroyed, which may have died with an error and can't take
+    /// anything pushed on its stack anymore: they only forget the identity of the thread, without calling into Luau.
+    fn thread_event_callback(
+        state: &mut LuauState,
+        lua: &Lua,
+        event: LuaThreadEventInfo,
+    ) -> Result<(), LuaError> {
         match event {
             LuaThreadEventInfo::Created(parent) => {
+                let is_running = lua.gc_is_running();
+                if is_running {
+                    lua.gc_stop();
+                }
                 state.get_log_service().log_message(
                     lua,
                     format!(
@@ -75,21 +135,14 @@ impl LuauState {
                         .threads
                         .insert(lua.current_thread().to_pointer(), iden.unwrap().clone());
                 }
+                if is_running {
+                    lua.gc_restart();
+                }
             }
             LuaThreadEventInfo::Destroyed(thread_ptr) => {
-                state.get_log_service().log_message(
-                    lua,
-                    format!(
-                        "[thread_events] thread destroyed: thread: 0x{:x}",
-                        thread_ptr as isize
-                    ),
-                );
                 state.threads.remove(&thread_ptr);
             }
         }
-        if is_running {
-            lua.gc_restart();
-        }
         Ok(())
     }
     pub fn get_thread_identity_pointer(&self, thread: *const c_void) -> Option<&ThreadIdentity> {
@@ -107,9 +160,19 @@ impl LuauState {
     pub fn get_thread_identity_mut(&mut self, thread: LuaThread) -> Option<&mut ThreadIdentity> {
         self.get_thread_identity_pointer_pointer_mut(thread.to_pointer().cast())
     }
+    /// Sets the identity of a thread. Its allocations are charged to the script of the identity from then on.
     pub fn set_thread_identity(&mut self, thread: LuaThread, identity: ThreadIdentity) {
+        let category = identity
+            .script
+            .as_ref()
+            .map_or(0, |x| self.memory.category(&self.lua, x));
+        unsafe { ffi::lua_setmemcat(thread.to_pointer() as *mut _, category as i32) };
         self.threads.insert(thread.to_pointer().cast(), identity);
     }
+    /// Iterates over the threads known to this state.
+    pub fn get_thread_pointers(&self) -> impl Iterator<Item = *const c_void> + '_ {
+        self.threads.keys().copied()
+    }
     pub(super) unsafe fn bind_services(&mut self) {
         self.lua
             .set_named_registry_value(
@@ -214,6 +277,55 @@ impl LuauState {
                     .unwrap(),
             )
             .unwrap();
+        // Time functions
+        {
+            let globals = self.lua.globals();
+            globals
+                .raw_set(
+                    "tick",
+                    self.lua
+                        .create_function(|lua, ()| Ok(get_state(lua).get_unix_time()))
+                        .unwrap(),
+                )
+                .unwrap();
+            globals
+                .raw_set(
+                    "time",
+                    self.lua
+                        .create_function(|lua, ()| Ok(get_state(lua).get_game_time()))
+                        .unwrap(),
+                )
+                .unwrap();
+            globals
+                .raw_set(
+                    "elapsedTime",
+                    self.lua
+                        .create_function(|lua, ()| Ok(get_state(lua).get_clock()))
+                        .unwrap(),
+                )
+                .unwrap();
+            let os = globals.raw_get::<LuaTable>("os").unwrap();
+            let os_time = os.raw_get::<LuaFunction>("time").unwrap();
+            os.raw_set(
+                "time",
+                self.lua
+                    .create_function(move |lua, time: Option<LuaTable>| match time {
+                        Some(time) => os_time.call::<LuaValue>(time),
+                        None => Ok(LuaValue::Integer(
+                            get_state(lua).get_unix_time().floor() as LuaInteger
+                        )),
+                    })
+                    .unwrap(),
+            )
+            .unwrap();
+            os.raw_set(
+                "clock",
+                self.lua
+                    .create_function(|lua, ()| Ok(get_state(lua).get_clock()))
+                    .unwrap(),
+            )
+            .unwrap();
+        }
         // Task scheduler registration
         {
             type DynTaskScheduler = dyn ITaskScheduler;
@@ -225,7 +337,7 @@ impl LuauState {
                         match thread_or_func {
                             LuaValue::Thread(thread) => get_state(borrowck_ignore(lua))
                                 .get_task_scheduler()
-                                .spawn_thread(thread),
+                                .spawn_thread(lua, thread),
                             LuaValue::Function(func) => get_state(borrowck_ignore(lua))
                                 .get_task_scheduler()
                                 .spawn_func(lua, func, mv),
@@ -266,7 +378,7 @@ impl LuauState {
                             match thread_or_func {
                                 LuaValue::Thread(thread) => get_state(borrowck_ignore(lua))
                                     .get_task_scheduler_mut()
-                                    .delay_thread(thread, Default, time),
+                                    .delay_thread(lua, thread, Default, time),
                                 LuaValue::Function(func) => get_state(borrowck_ignore(lua))
                                     .get_task_scheduler_mut()
                                     .delay_func(lua, func, mv, Synchronized, time),
@@ -373,20 +485,77 @@ impl LuauState {
             .raw_set(
                 "require",
                 self.lua
-                    .create_function(|lua, script: ManagedInstance| {
-                        let s = script
-                            .cast_from_unsized::<dyn IModuleScript>()
-                            .map_err(|_| {
-                                LuaError::RuntimeError(
-                                    "invalid argument #1 to 'require' (ModuleScript expected)"
-                                        .into(),
-                                )
-                            })?;
+                    .create_function(|lua, target: LuaValue| {
+                        let s = match target {
+                            LuaValue::Integer(_) | LuaValue::Number(_) => {
+                                check_capability(lua, ScriptCapability::AssetRequire, "require")?;
+                                return Err(LuaError::RuntimeError(
+                                    "Requiring assets by id is not supported".into(),
+                                ));
+                            }
+                            LuaValue::String(path) => {
+                                match resolve_require(lua, &path.to_str()?)? {
+                                    RequireTarget::Instance(x) => {
+                                        let name = x.get_full_name()?;
+                                        x.cast_from_unsized::<dyn IModuleScript>().map_err(
+                                            |_| {
+                                                LuaError::RuntimeError(format!(
+                                                    "{} is not a ModuleScript",
+                                                    name
+                                                ))
+                                            },
+                                        )?
+                                    }
+                                    RequireTarget::File(x) => {
+                                        // Files are loaded from outside the DataModel, like assets.
+                                        check_capability(
+                                            lua,
+                                            ScriptCapability::AssetRequire,
+                                            "require",
+                                        )?;
+                                        return require_file(lua, &x);
+                                    }
+                                }
+                            }
+                            x => ManagedInstance::from_lua(x, lua)
+                                .ok()
+                                .and_then(|x| x.cast_from_unsized::<dyn IModuleScript>().ok())
+                                .ok_or_else(|| {
+                                    LuaError::RuntimeError(
+                                        "invalid argument #1 to 'require' (ModuleScript expected)"
+                                            .into(),
+                                    )
+                                })?,
+                        };
                         s.require(lua, &get_state(lua).actor)
                     })
                     .unwrap(),
             )
             .unwrap();
+        self.lua
+            .globals()
+            .raw_set(
+                "loadstring",
+                self.lua
+                    .create_function(|lua, (source, chunk_name): (String, Option<String>)| {
+                        check_capability(lua, ScriptCapability::LoadString, "loadstring")?;
+                        let state = get_state(lua);
+                        let env = if get_current_identity(lua).is_some_and(|x| x.sandbox.is_some())
+                        {
+                            state.create_sandboxed_env()?
+                        } else {
+                            state.create_env_from_global()?
+                        };
+                        let chunk_name = chunk_name.unwrap_or_else(|| "loadstring".into());
+                        match state.compile(&chunk_name, &source, env) {
+                            Ok(func) => Ok((Some(func), None)),
+                            Err(LuaError::SyntaxError { message, .. }) => Ok((None, Some(message))),
+                            Err(x) => Err(x),
+                        }
+                    })
+                    .unwrap(),
+            )
+            .unwrap();
     }
     unsafe fn _init(&mut self) {
         self.lua
@@ -403,13 +572,22 @@ impl LuauState {
         self.register_globals();
 
         self.lua.sandbox(true).unwrap();
+        // Sandboxing freezes the tables already in the globals, scripts have to be able to write to this one.
+        self.lua
+            .globals()
+            .raw_set("shared", self.lua.create_table().unwrap())
+            .unwrap();
         self.lua.enable_jit(false);
+        self.apply_memory_limit();
         register_userdata_singletons(&mut self.lua).unwrap();
         self.lua
             .globals()
             .set_readonly(self.flags().get_bool(FastFlag::GlobalsReadonly));
-        self.lua
-            .set_thread_event_callback(Self::thread_event_callback);
+        // The state doesn't move once initialized, its address is in the registry already.
+        let state = addr_of_mut!(*self) as usize;
+        self.lua.set_thread_event_callback(move |lua, event| {
+            Self::thread_event_callback(unsafe { &mut *(state as *mut LuauState) }, lua, event)
+        });
         self.lua.gc_stop();
     }
     pub(super) unsafe fn init(&mut self, ptr: *mut RwLock<RblxVM>, task: Box<dyn ITaskScheduler>) {
@@ -425,6 +603,14 @@ impl LuauState {
             threads: HashMap::default(),
             task: MaybeUninit::new(Box::new(TaskScheduler::new())),
             actor: None,
+            memory: ScriptMemoryTracker::default(),
+            profiler: None,
+            debugger: StateDebugger::default(),
+            requiring: Vec::new(),
+            file_modules: HashMap::new(),
+            native_chunks: Vec::new(),
+            codegen_unsupported_warned: false,
+            member_access: MemberAccessCache::default(),
         };
         unsafe {
             state._init();
@@ -438,6 +624,14 @@ impl LuauState {
             threads: HashMap::default(),
             task: MaybeUninit::uninit(),
             actor: None,
+            memory: ScriptMemoryTracker::default(),
+            profiler: None,
+            debugger: StateDebugger::default(),
+            requiring: Vec::new(),
+            file_modules: HashMap::new(),
+            native_chunks: Vec::new(),
+            codegen_unsupported_warned: false,
+            member_access: MemberAccessCache::default(),
         }
     }
     pub fn get_vm(&self) -> RwLockReadGuard<RblxVM> {
@@ -447,6 +641,40 @@ impl LuauState {
         unsafe { self.vm.as_ref().unwrap_unchecked().write().unwrap() }
     }
 
+    pub fn get_clock(&self) -> f64 {
+        unsafe {
+            self.vm
+                .as_ref()
+                .unwrap_unchecked()
+                .access()
+                .as_ref()
+                .unwrap_unchecked()
+                .get_clock()
+        }
+    }
+    pub fn get_unix_time(&self) -> f64 {
+        unsafe {
+            self.vm
+                .as_ref()
+                .unwrap_unchecked()
+                .access()
+                .as_ref()
+                .unwrap_unchecked()
+                .get_unix_time()
+        }
+    }
+    pub fn get_game_time(&self) -> f64 {
+        unsafe {
+            self.vm
+                .as_ref()
+                .unwrap_unchecked()
+                .access()
+                .as_ref()
+                .unwrap_unchecked()
+                .get_game_time()
+        }
+    }
+
     pub(super) unsafe fn watchdog_check(&self) -> bool {
         self.vm
             .as_ref()
@@ -472,46 +700,136 @@ impl LuauState {
         // sadly doesnt work
         &["Instance"] // good enough
     }
-    fn get_debug_compiler() -> Compiler {
-        Compiler::new()
-            .set_debug_level(2)
-            .set_optimization_level(1)
+    fn get_compiler(&self, mode: CompileMode) -> Compiler {
+        let (optimization, debug) = mode.level_flags();
+        let compiler = Compiler::new()
+            .set_optimization_level(self.flags().get_int(optimization).clamp(0, 2) as u8)
+            .set_debug_level(self.flags().get_int(debug).clamp(0, 2) as u8)
             .set_userdata_types(
                 Self::get_userdata_types()
                     .into_iter()
                     .map(|x| String::from(*x))
                     .collect(),
-            )
+            );
+        match mode {
+            CompileMode::Debug => compiler,
+            CompileMode::Release | CompileMode::Native => compiler.set_type_info_level(1),
+        }
     }
-    fn get_release_compiler() -> Compiler {
-        Compiler::new()
-            .set_debug_level(1)
-            .set_optimization_level(2)
-            .set_userdata_types(
-                Self::get_userdata_types()
-                    .into_iter()
-                    .map(|x| String::from(*x))
-                    .collect(),
-            )
-            .set_type_info_level(1)
+    /// Compiles `chunk`, going through the bytecode cache and the directory of the `BytecodeCacheDirectory` flag.
+    fn get_bytecode(&self, mode: CompileMode, chunk: &str) -> LuaResult<Arc<[u8]>> {
+        let directory = self.flags().get_string(FastFlag::BytecodeCacheDirectory);
+        get_bytecode_cache().get_or_compile(
+            &self.get_compiler(mode),
+            chunk,
+            (!directory.is_empty()).then(|| Path::new(&directory)),
+        )
+    }
+    /// Loads the bytecode of `chunk`. Cached bytecode that doesn't load, say a cache file damaged in a way its
+    /// checksum can't tell, is dropped from the cache and compiled again.
+    fn load_bytecode(
+        &self,
+        mode: CompileMode,
+        chunk_name: &str,
+        chunk: &str,
+        env: LuaTable,
+    ) -> LuaResult<LuaFunction> {
+        let load = |bytecode: &[u8]| {
+            self.lua
+                .load(bytecode)
+                .set_name(chunk_name)
+                .set_mode(ChunkMode::Binary)
+                .set_environment(env.clone())
+                .into_function()
+        };
+        load(&self.get_bytecode(mode, chunk)?).or_else(|_| {
+            let directory = self.flags().get_string(FastFlag::BytecodeCacheDirectory);
+            get_bytecode_cache().invalidate(
+                &self.get_compiler(mode),
+                chunk,
+                (!directory.is_empty()).then(|| Path::new(&directory)),
+            );
+            load(&self.get_bytecode(mode, chunk)?)
+        })
+    }
+    /// Picks how `chunk` gets compiled. Debug mode keeps everything interpreted for the debugger, otherwise
+    /// the `NativeCodegen` flag decides, by default only chunks marked `--!native` or holding `@native`
+    /// functions go through the native code generator.
+    pub fn get_compile_mode(&self, chunk: &str) -> CompileMode {
+        if self.flags().get_bool(FastFlag::DebugMode) {
+            return CompileMode::Debug;
+        }
+        match self.flags().get_int(FastFlag::NativeCodegen) {
+            1 => CompileMode::Native,
+            2 => CompileMode::Release,
+            _ if is_native_module(chunk) || !native_functions(chunk).is_empty() => {
+                CompileMode::Native
+            }
+            _ => CompileMode::Release,
+        }
+    }
+    /// Compiles `chunk` the way [`LuauState::get_compile_mode`] picks.
+    pub fn compile(
+        &mut self,
+        chunk_name: &str,
+        chunk: &str,
+        env: LuaTable,
+    ) -> LuaResult<LuaFunction> {
+        match self.get_compile_mode(chunk) {
+            CompileMode::Debug => self.compile_debug(chunk_name, chunk, env),
+            CompileMode::Release => self.compile_release(chunk_name, chunk, env),
+            CompileMode::Native => self.compile_jit(chunk_name, chunk, env),
+        }
     }
+    /// Compiles `chunk` to native code where the platform supports it. Chunks holding `@native` functions
+    /// only get those compiled, unless they are marked `--!native`.
+    /// On platforms without native code generation, the chunk runs interpreted and a warning is written to the
+    /// output. Functions the code generator gives up on can't be told apart: `luau_codegen_compile` doesn't return
+    /// anything, they just run interpreted.
     pub fn compile_jit(
         &mut self,
         chunk_name: &str,
         chunk: &str,
         env: LuaTable,
     ) -> LuaResult<LuaFunction> {
-        let v = Self::get_release_compiler().compile(chunk)?;
+        if unsafe { ffi::luau_codegen_supported() } == 0 {
+            if !self.codegen_unsupported_warned {
+                self.codegen_unsupported_warned = true;
+                self.get_log_service().log_warn(
+                    &self.lua,
+                    String::from(
+                        "Native code generation isn't supported on this platform, scripts run interpreted",
+                    ),
+                );
+            }
+            return self.compile_release(chunk_name, chunk, env);
+        }
         self.lua.enable_jit(true);
-        let f = self
-            .lua
-            .load(v)
-            .set_name(chunk_name)
-            .set_mode(ChunkMode::Binary)
-            .set_environment(env)
-            .into_function();
+        let f = self.load_bytecode(CompileMode::Native, chunk_name, chunk, env.clone());
         self.lua.enable_jit(false);
-        f
+        match f {
+            Ok(f) => {
+                let functions = native_functions(chunk);
+                self.native_chunks.push(NativeChunk {
+                    chunk_name: chunk_name.into(),
+                    functions: (!is_native_module(chunk) && !functions.is_empty())
+                        .then_some(functions),
+                });
+                Ok(f)
+            }
+            // Only loading the bytecode can fail here, not generating the native code.
+            Err(err) => {
+                self.get_log_service().log_warn(
+                    &self.lua,
+                    format!(
+                        "Failed to load {} for native code generation, it runs interpreted: {}",
+                        chunk_display_name(chunk_name),
+                        err
+                    ),
+                );
+                self.compile_release(chunk_name, chunk, env)
+            }
+        }
     }
     pub fn compile_release(
         &mut self,
@@ -519,13 +837,7 @@ impl LuauState {
         chunk: &str,
         env: LuaTable,
     ) -> LuaResult<LuaFunction> {
-        let v = Self::get_release_compiler().compile(chunk)?;
-        self.lua
-            .load(v)
-            .set_name(chunk_name)
-            .set_mode(ChunkMode::Binary)
-            .set_environment(env)
-            .into_function()
+        self.load_bytecode(CompileMode::Release, chunk_name, chunk, env)
     }
     pub fn compile_debug(
         &mut self,
@@ -533,13 +845,13 @@ impl LuauState {
         chunk: &str,
         env: LuaTable,
     ) -> LuaResult<LuaFunction> {
-        let v = Self::get_debug_compiler().compile(chunk)?;
-        self.lua
-            .load(v)
-            .set_name(chunk_name)
-            .set_mode(ChunkMode::Binary)
-            .set_environment(env)
-            .into_function()
+        let f = self.load_bytecode(CompileMode::Debug, chunk_name, chunk, env)?;
+        register_chunk(self, chunk_name, chunk, &f)?;
+        Ok(f)
+    }
+    /// The chunks of this state that went through the native code generator.
+    pub fn get_native_chunks(&self) -> &[NativeChunk] {
+        &self.native_chunks
     }
     pub fn create_env_from_global(&mut self) -> LuaResult<LuaTable> {
         let lua = self.get_lua();
@@ -552,6 +864,30 @@ impl LuauState {
 
         Ok(table)
     }
+    /// Like [`LuauState::create_env_from_global`], for code running in a sandbox: reading `_G` and `shared` needs
+    /// the `ScriptGlobals` capability.
+    pub fn create_sandboxed_env(&mut self) -> LuaResult<LuaTable> {
+        let lua = self.get_lua();
+        let metatable = lua.create_table()?;
+        metatable.raw_set("__metatable", "env")?;
+        metatable.raw_set(
+            "__index",
+            lua.create_function(|lua, (_, key): (LuaValue, LuaValue)| {
+                if let LuaValue::String(name) = &key {
+                    let name = name.to_str()?;
+                    if *name == *"_G" || *name == *"shared" {
+                        check_capability(lua, ScriptCapability::ScriptGlobals, &name)?;
+                    }
+                }
+                lua.globals().get::<LuaValue>(key)
+            })?,
+        )?;
+
+        let table = lua.create_table()?;
+        table.set_metatable(Some(metatable));
+
+        Ok(table)
+    }
     #[inline]
     pub fn get_task_scheduler_mut(&mut self) -> &mut dyn ITaskScheduler {
         // SAFETY: The state must be initialized
@@ -566,21 +902,117 @@ impl LuauState {
     pub const fn flags(&self) -> &'static FastFlags {
         FastFlags::from_vm(self.vm)
     }
-    pub fn gc(&self) {
+    pub fn gc(&mut self) {
         self.lua.gc_restart();
         self.lua.gc_collect().unwrap();
         self.lua.gc_stop();
+        self.memory.collected();
     }
-    pub fn gc_step(&self, kb: i32) {
+    pub fn gc_step(&mut self, kb: i32) {
         self.lua.gc_restart();
         self.lua.gc_step_kbytes(kb).unwrap();
         self.lua.gc_stop();
+        self.memory.collected();
+    }
+    /// Applies the `MemoryLimitMb` fast flag to the allocator of this state.
+    /// Allocations going over the limit fail with a "not enough memory" error instead of aborting.
+    pub fn apply_memory_limit(&self) {
+        self.lua.set_memory_limit(self.get_memory_limit()).unwrap();
+    }
+    /// Returns the amount of memory (in bytes) used by this state.
+    pub fn get_memory_usage(&self) -> usize {
+        self.lua.used_memory()
+    }
+    /// Returns the memory limit (in bytes) of this state, zero means no limit.
+    pub fn get_memory_limit(&self) -> usize {
+        self.flags().get_int(FastFlag::MemoryLimitMb).max(0) as usize * 1024 * 1024
+    }
+    /// Returns the amount of memory (in bytes) attributed to a script.
+    pub fn get_script_memory_usage(&self, script: &WeakManagedInstance) -> usize {
+        self.memory.get(&self.lua, script)
+    }
+    /// Iterates over the scripts of this state, along with the memory (in bytes) attributed to them.
+    pub fn iter_script_memory_usage(&self) -> impl Iterator<Item = (&WeakManagedInstance, usize)> {
+        self.memory.iter(&self.lua)
+    }
+    #[inline(always)]
+    pub fn get_member_access_cache(&mut self) -> &mut MemberAccessCache {
+        &mut self.member_access
+    }
+    fn interrupt(lua: &Lua) -> LuaResult<LuaVmState> {
+        let state = get_state(lua);
+        if state.debugger.is_attached() {
+            if let Some(debugger) = state.get_debugger() {
+                debugger.check_pause_request(state, lua.current_thread().to_pointer() as *mut _);
+            }
+        }
+        if let Some(profiler) = state.profiler.as_mut() {
+            if profiler.should_sample() {
+                let script = state
+                    .threads
+                    .get(&lua.current_thread().to_pointer())
+                    .and_then(|x| x.script.as_ref()?.upgrade())
+                    .and_then(|x| x.get_full_name().ok())
+                    .unwrap_or_else(|| String::from("<no script>"));
+                profiler.sample(lua, script);
+            }
+        }
+        Ok(LuaVmState::Continue)
+    }
+    /// Installs the interrupt this state needs while the watchdog isn't tripped.
+    pub(super) fn restore_interrupt(&self) {
+        if self.profiler.is_some() || self.debugger.is_attached() {
+            self.lua.set_interrupt(Self::interrupt);
+        } else {
+            self.lua.remove_interrupt();
+        }
+    }
+    /// Starts sampling the call stacks of this state, `frequency` times per second.
+    /// Restarts the profiler if it was already running.
+    pub fn start_profiler(&mut self, frequency: f64) {
+        self.profiler = Some(ScriptProfiler::new(frequency));
+        self.restore_interrupt();
+    }
+    pub fn stop_profiler(&mut self) -> Option<ScriptProfile> {
+        let profiler = self.profiler.take();
+        self.restore_interrupt();
+        profiler.map(ScriptProfiler::finish)
+    }
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+    /// Returns the debugger of the VM, if one is running.
+    pub fn get_debugger(&self) -> Option<&'static Debugger> {
+        unsafe {
+            self.vm
+                .as_ref()
+                .unwrap_unchecked()
+                .access()
+                .as_ref()
+                .unwrap_unchecked()
+                .get_debugger()
+        }
+    }
+    pub(crate) fn get_debugger_state_mut(&mut self) -> &mut StateDebugger {
+        &mut self.debugger
+    }
+    /// Installs the debug hooks on this state, or removes them.
+    pub(crate) fn set_debugger_attached(&mut self, attached: bool) {
+        self.debugger.set_attached(attached);
+        install_hooks(&self.lua, attached);
+        self.restore_interrupt();
     }
     /// Gets the RblxVM pointer. This is thread-safe even with `.access()`.
     #[inline(always)]
     pub(super) const fn get_vm_ptr(&self) -> *mut RwLock<RblxVM> {
         self.vm
     }
+    pub(crate) fn get_requiring(&mut self) -> &mut Vec<ModuleKey> {
+        &mut self.requiring
+    }
+    pub(crate) fn get_file_modules(&mut self) -> &mut HashMap<PathBuf, ModuleResult> {
+        &mut self.file_modules
+    }
     pub(crate) fn set_actor(&mut self, actor: WeakManagedActor) {
         self.actor = Some(actor);
     }
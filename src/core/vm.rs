@@ -2,21 +2,39 @@ use std::borrow::BorrowMut;
 use std::collections::HashMap;
 use std::marker::PhantomPinned;
 use std::mem::MaybeUninit;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use r2g_mlua::prelude::*;
 
 use crate::core::scheduler::GlobalTaskScheduler;
-use crate::instance::{DataModel, IDataModel, LogService, RunService, WeakManagedActor, Workspace};
+use crate::instance::{
+    DataModel, GodotBridge, IBaseScript, IDataModel, IGodotBridge, IRunService, LogService,
+    RunService, ScriptContext, WeakManagedActor, Workspace,
+};
+use crate::project::SourceWatcher;
 
 use super::state::LuauState;
 use super::{
-    FastFlag, FastFlagValue, FastFlags, InstanceReplicationTable, InstanceTagCollectionTable, Irc,
-    RwLock, Trc, Watchdog, Weak, GLOBAL_LOCKS_OF_THREAD,
+    get_task_scheduler_from_lua, ClockMode, Debugger, FastFlag, FastFlagValue, FastFlags,
+    FrameStats, InstanceReplicationTable, InstanceTagCollectionTable, Irc, ManagedInstance,
+    NativeChunk, RequireAlias, RwLock, SchedulerClock, ScriptProfile, Trc, Watchdog, Weak,
+    WeakManagedInstance, GLOBAL_LOCKS_OF_THREAD,
 };
 
+/// Whether the simulation of a VM runs, see [`RblxVM::set_run_state`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunState {
+    /// The simulation is stopped, no frame runs.
+    Edit,
+    Running,
+    /// Frames only run when stepped, the scheduler clock stands still meanwhile.
+    Paused,
+}
+
 pub struct RblxVM {
     main_state: Trc<LuauState>,
     states: Vec<Weak<LuauState>>,
@@ -31,9 +49,32 @@ pub struct RblxVM {
     hard_wd: Watchdog,
     soft_wd: Watchdog,
 
+    game_time: f64,
+    clock: SchedulerClock,
+    run_state: RunState,
+    pending_steps: u32,
+    frame_stats: FrameStats,
+    debugger: Option<Debugger>,
+    source_watchers: Vec<SourceWatcher>,
+    last_source_poll: Instant,
+    require_aliases: HashMap<String, RequireAlias>,
+    module_root: Option<PathBuf>,
+
     _pin: PhantomPinned,
 }
 
+const SOURCE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The memory Godot itself uses. Unit tests run without the engine, there is none to count.
+#[cfg(not(test))]
+fn engine_memory_usage() -> usize {
+    godot::classes::Os::singleton().get_static_memory_usage() as usize
+}
+#[cfg(test)]
+fn engine_memory_usage() -> usize {
+    0
+}
+
 impl RblxVM {
     pub fn new(flags_table: Option<Vec<(FastFlag, FastFlagValue)>>) -> Box<RwLock<RblxVM>> {
         unsafe {
@@ -47,10 +88,22 @@ impl RblxVM {
                 data_model: MaybeUninit::uninit(),
                 hard_wd: Watchdog::new_timeout(10.0),
                 soft_wd: Watchdog::new_timeout(1.0 / 60.0),
+                game_time: 0.0,
+                clock: SchedulerClock::new(),
+                run_state: RunState::Running,
+                pending_steps: 0,
+                frame_stats: FrameStats::default(),
+                debugger: None,
+                source_watchers: Vec::new(),
+                last_source_poll: Instant::now(),
+                require_aliases: HashMap::new(),
+                module_root: None,
                 _pin: PhantomPinned::default(),
                 flags: MaybeUninit::uninit(),
             }));
             vm.set_global_lock(vm.access().as_ref().unwrap().global_lock.as_ref());
+            // Instances pick up the global lock on top of the thread's stack, which may belong to another VM.
+            vm.get_mut().push_global_lock_atomic();
             let vm_ptr = &raw mut *vm;
             let flags = FastFlags::new(vm_ptr);
             vm.get_mut().flags.write(flags);
@@ -86,12 +139,26 @@ impl RblxVM {
                 .assume_init_ref()
                 .init_services(vm.access().as_mut().unwrap().get_main_state().get_lua())
                 .unwrap();
+            let game = vm.get_mut().get_game_instance();
+            vm.get_mut()
+                .register_instance_tree(&game.cast_from_sized().unwrap());
             vm.get_mut()
                 .main_state
                 .access()
                 .as_mut()
                 .unwrap_unchecked()
                 .bind_services();
+            let port = vm.get_mut().flags().get_int(FastFlag::DebuggerPort);
+            if vm.get_mut().flags().get_bool(FastFlag::DebugMode) && port > 0 {
+                if let Err(err) = vm.get_mut().start_debugger(port as u16) {
+                    let lua = vm.get_mut().get_main_state().get_lua().clone();
+                    vm.get_mut().get_log_service().log_err(
+                        &lua,
+                        format!("Failed to start the debugger on port {}: {}", port, err),
+                    );
+                }
+            }
+            vm.get_mut().pop_global_lock_atomic();
             vm
         }
     }
@@ -116,7 +183,7 @@ impl RblxVM {
             });
     }
     fn watchdog_reset_state(state: &mut LuauState) {
-        state.get_lua().remove_interrupt();
+        state.restore_interrupt();
     }
     pub fn watchdog_trip(&self) {
         self.hard_wd.trip();
@@ -152,12 +219,154 @@ impl RblxVM {
         self.hard_wd.reset();
         self.soft_wd.reset();
     }
+    /// Restarts the watchdog timers without restoring tripped states, used when resuming from the debugger.
+    pub(crate) fn watchdog_resume(&mut self) {
+        if !self.hard_wd.is_tripped() {
+            self.hard_wd.reset();
+            self.soft_wd.reset();
+        }
+    }
     pub(crate) fn watchdog_check(&self) -> bool {
         if self.hard_wd.check() {
             self.watchdog_trip();
         }
         self.soft_wd.check()
     }
+    /// Time since the game started running, advanced once per frame.
+    #[inline(always)]
+    pub fn get_game_time(&self) -> f64 {
+        self.game_time
+    }
+    pub(crate) fn advance_game_time(&mut self, delta: f64) {
+        self.game_time += delta;
+    }
+    /// Time of the clock the threads are scheduled on, it stands still unless the simulation is running.
+    #[inline(always)]
+    pub fn get_clock(&self) -> f64 {
+        self.clock.now()
+    }
+    /// Seconds since the Unix epoch, as seen by scripts. See [`SchedulerClock::unix_time`].
+    #[inline(always)]
+    pub fn get_unix_time(&self) -> f64 {
+        self.clock.unix_time()
+    }
+    #[inline(always)]
+    pub fn get_clock_mode(&self) -> ClockMode {
+        self.clock.get_mode()
+    }
+    /// Switches the scheduler clock to real time, or to manual mode where it only moves by the delta of each
+    /// frame step. `time` sets where the clock starts from, it carries on from its current time otherwise.
+    pub fn set_clock_mode(&mut self, mode: ClockMode, time: Option<f64>) {
+        self.clock.set_mode(mode, time);
+    }
+    /// Moves the clock forward by `delta` seconds, unless it follows the real one.
+    pub(crate) fn advance_clock(&mut self, delta: f64) {
+        self.clock.advance_frame(delta);
+    }
+    #[inline(always)]
+    pub fn get_run_state(&self) -> RunState {
+        self.run_state
+    }
+    /// Starts, pauses or stops the simulation, starting with the next frame. Pending steps get dropped.
+    pub fn set_run_state(&mut self, run_state: RunState) {
+        self.run_state = run_state;
+        self.pending_steps = 0;
+        self.clock.set_frozen(run_state != RunState::Running);
+    }
+    /// Runs `frames` more frames while the simulation is paused. Returns false if it isn't paused.
+    pub fn step(&mut self, frames: u32) -> bool {
+        if self.run_state != RunState::Paused {
+            return false;
+        }
+        self.pending_steps = self.pending_steps.saturating_add(frames);
+        true
+    }
+    #[inline(always)]
+    pub fn get_time_scale(&self) -> f64 {
+        self.clock.get_time_scale()
+    }
+    /// Speeds the simulation up or slows it down, 1 being real time. It has to be finite and not negative.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.clock.set_time_scale(time_scale);
+    }
+    /// Returns how much time the next frame simulates given the real `delta`, or `None` if it doesn't run.
+    pub(crate) fn next_frame_delta(&mut self, delta: f64) -> Option<f64> {
+        let delta = delta * self.clock.get_time_scale();
+        match self.run_state {
+            RunState::Running => {
+                self.clock.advance_frame(delta);
+                Some(delta)
+            }
+            RunState::Paused if self.pending_steps > 0 => {
+                self.pending_steps -= 1;
+                self.clock.advance_frame(delta);
+                Some(delta)
+            }
+            _ => None,
+        }
+    }
+    /// Adds an instance and its descendants to the instance replication table.
+    pub(crate) fn register_instance_tree(&self, instance: &ManagedInstance) {
+        self.instances.add_instance(instance.clone());
+        for i in instance.get_descendants().unwrap_or_default() {
+            self.instances.add_instance(i);
+        }
+    }
+    /// Lets go of everything keeping the Luau states alive besides the VM: threads get cancelled, connections
+    /// get disconnected and scripts forget the state they ran in. Nothing runs in the VM after this.
+    pub(crate) fn release_all(&mut self) {
+        for state in self.get_all_states() {
+            let mut st = state.write();
+            get_task_scheduler_from_lua(st.get_lua()).cancel_all();
+        }
+        <dyn IRunService>::clear_render_steps(&*self.get_run_service());
+        <dyn IGodotBridge>::detach(&*self.get_godot_bridge());
+        self.source_watchers.clear();
+        let game: ManagedInstance = self.get_game_instance().cast_from_sized().unwrap();
+        let mut instances = self.instances.get_instances();
+        instances.extend(game.get_descendants().unwrap_or_default());
+        instances.push(game);
+        for instance in instances {
+            instance.disconnect_all_signals();
+            if let Ok(script) = instance.cast_from_unsized::<dyn IBaseScript>() {
+                script.release_state();
+            }
+        }
+    }
+    /// Counters collected on the last frame step.
+    #[inline(always)]
+    pub fn get_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+    pub(crate) fn update_frame_stats(
+        &mut self,
+        heartbeat_time: f64,
+        scheduler_time: f64,
+        gc_time: f64,
+    ) {
+        self.instances.garbage_collect();
+        let (main, secondary, capacity) = self.instances.get_stats();
+        let states = self.get_all_states();
+        self.frame_stats = FrameStats {
+            heartbeat_time,
+            physics_step_time: 0.0,
+            scheduler_time,
+            gc_time,
+            instance_count: main + secondary,
+            lua_heap: states.iter().map(|x| x.read().get_memory_usage()).sum(),
+            script_memory: states
+                .iter()
+                .map(|x| {
+                    x.read()
+                        .iter_script_memory_usage()
+                        .map(|(_, bytes)| bytes)
+                        .sum::<usize>()
+                })
+                .sum(),
+            instance_table_memory: capacity * std::mem::size_of::<(usize, WeakManagedInstance)>(),
+            engine_memory: engine_memory_usage(),
+        };
+    }
     #[inline(always)]
     pub(crate) fn get_instance_tag_table(&self) -> &InstanceTagCollectionTable {
         &self.instances_tag_collection
@@ -198,6 +407,9 @@ impl RblxVM {
         };
         let mut state = LuauState::new(self_rwlock);
         state.set_actor(actor.clone());
+        if self.debugger.is_some() {
+            state.set_debugger_attached(true);
+        }
         let rc = Trc::new(state);
         self.states.push(rc.downgrade());
         rc
@@ -214,20 +426,173 @@ impl RblxVM {
             .chain(std::iter::once(self.main_state.clone()))
             .collect()
     }
+    /// Runs `f` on every Luau state of this VM, using `running` as is instead of locking it again.
+    fn for_each_state(
+        &self,
+        mut running: Option<&mut LuauState>,
+        mut f: impl FnMut(&mut LuauState),
+    ) {
+        let running_ptr = running.as_deref_mut().map(|x| &raw mut *x);
+        for state in self.get_all_states() {
+            match running.as_deref_mut() {
+                Some(x) if running_ptr == Some(unsafe { state.access() }) => f(x),
+                _ => f(&mut state.write()),
+            }
+        }
+    }
+    /// Starts the script profiler on every Luau state of this VM. `running` is the state calling in from Luau,
+    /// if any, it stays borrowed for as long as it runs.
+    pub fn start_profiler(&self, running: Option<&mut LuauState>, frequency: f64) {
+        self.for_each_state(running, |x| x.start_profiler(frequency));
+    }
+    /// Stops the script profiler, returning the samples of every Luau state.
+    pub fn stop_profiler(&self, running: Option<&mut LuauState>) -> ScriptProfile {
+        let mut profile = ScriptProfile::default();
+        self.for_each_state(running, |x| {
+            if let Some(x) = x.stop_profiler() {
+                profile.merge(x);
+            }
+        });
+        profile
+    }
+    pub fn is_profiling(&self) -> bool {
+        self.main_state.read().is_profiling()
+    }
+    /// Starts the debug adapter server on a localhost port, zero picks any free port.
+    /// Returns the port it listens on, which is the current one if it's already running.
+    pub fn start_debugger(&mut self, port: u16) -> std::io::Result<u16> {
+        if let Some(debugger) = self.debugger.as_ref() {
+            return Ok(debugger.port());
+        }
+        let debugger = Debugger::new(port)?;
+        let port = debugger.port();
+        self.debugger = Some(debugger);
+        for state in self.get_all_states() {
+            state.write().set_debugger_attached(true);
+        }
+        Ok(port)
+    }
+    /// Stops the debug adapter server, clearing every breakpoint.
+    pub fn stop_debugger(&mut self) {
+        if let Some(debugger) = self.debugger.as_ref() {
+            debugger.detach(&self.get_all_states());
+        }
+        self.debugger = None;
+    }
+    #[inline(always)]
+    pub fn get_debugger(&self) -> Option<&Debugger> {
+        self.debugger.as_ref()
+    }
+    /// Lets the debugger handle the requests it received since the last frame.
+    pub(crate) fn poll_debugger(&self) {
+        if let Some(debugger) = self.debugger.as_ref() {
+            debugger.poll(&self.main_state, &self.get_all_states());
+        }
+    }
+    /// Keeps the scripts under `target` in sync with the files in `root`, see [`SourceWatcher`].
+    /// The directory gets synced on the next frame, then polled a few times per second.
+    pub fn watch_sources(&mut self, root: PathBuf, target: &ManagedInstance) {
+        self.source_watchers.retain(|x| x.root() != root);
+        self.source_watchers.push(SourceWatcher::new(root, target));
+        self.last_source_poll = Instant::now() - SOURCE_POLL_INTERVAL;
+    }
+    pub fn stop_watching_sources(&mut self) {
+        self.source_watchers.clear();
+    }
+    /// Applies the file changes picked up by the source watchers, errors are written to the output.
+    pub(crate) fn poll_source_watchers(&mut self) {
+        if self.source_watchers.is_empty() || self.last_source_poll.elapsed() < SOURCE_POLL_INTERVAL
+        {
+            return;
+        }
+        self.last_source_poll = Instant::now();
+        let lua = self.get_main_state().get_lua().clone();
+        let log_service = self.get_log_service();
+        for watcher in self.source_watchers.iter_mut() {
+            if let Err(err) = watcher.poll(&lua) {
+                log_service.log_err(&lua, format!("Failed to reload scripts: {}", err));
+            }
+        }
+    }
+    /// Makes `require("@name/...")` start from `alias`, alias names are case insensitive.
+    pub fn set_require_alias(&mut self, name: &str, alias: RequireAlias) {
+        self.require_aliases.insert(name.to_lowercase(), alias);
+    }
+    pub fn get_require_alias(&self, name: &str) -> Option<RequireAlias> {
+        self.require_aliases.get(&name.to_lowercase()).cloned()
+    }
+    pub fn clear_require_aliases(&mut self) {
+        self.require_aliases.clear();
+    }
+    /// Enables the filesystem module loader, letting require paths reach the Luau files under `root`.
+    /// `None` disables it again, only ModuleScripts can be required then.
+    pub fn set_module_root(&mut self, root: Option<PathBuf>) -> std::io::Result<()> {
+        self.module_root = root.map(std::fs::canonicalize).transpose()?;
+        Ok(())
+    }
+    pub fn get_module_root(&self) -> Option<&Path> {
+        self.module_root.as_deref()
+    }
+    /// Returns the chunks that went through the native code generator, across all Luau states.
+    pub fn get_native_chunks(&self) -> Vec<NativeChunk> {
+        self.get_all_states()
+            .iter()
+            .flat_map(|x| x.read().get_native_chunks().to_vec())
+            .collect()
+    }
+    /// Returns the memory (in bytes) used by every Luau state of this VM.
+    pub fn get_total_memory_usage(&self) -> usize {
+        self.get_all_states()
+            .iter()
+            .map(|x| x.read().get_memory_usage())
+            .sum()
+    }
+    /// Returns the memory (in bytes) attributed to every script, across all Luau states.
+    pub fn get_script_memory_usage(&self) -> HashMap<WeakManagedInstance, usize> {
+        let mut usage: HashMap<WeakManagedInstance, usize> = HashMap::new();
+        for state in self.get_all_states() {
+            for (script, bytes) in state.read().iter_script_memory_usage() {
+                *usage.entry(script.clone()).or_default() += bytes;
+            }
+        }
+        usage
+    }
     pub fn get_log_service(&self) -> Irc<LogService> {
         <dyn IDataModel>::get_log_service(&*self.get_game_instance())
     }
+    pub fn get_script_context(&self) -> Irc<ScriptContext> {
+        <dyn IDataModel>::get_script_context(&*self.get_game_instance())
+    }
     pub fn get_run_service(&self) -> Irc<RunService> {
         <dyn IDataModel>::get_run_service(&*self.get_game_instance())
     }
     pub fn get_workspace(&self) -> Irc<Workspace> {
         <dyn IDataModel>::get_workspace(&*self.get_game_instance())
     }
+    pub fn get_godot_bridge(&self) -> Irc<GodotBridge> {
+        <dyn IDataModel>::get_godot_bridge(&*self.get_game_instance())
+    }
 }
 
 impl Drop for RblxVM {
     fn drop(&mut self) {
+        // The DataModel goes before the states, so the instances only Luau still references are dropped
+        // when the states close.
+        unsafe { self.data_model.assume_init_drop() };
         self.states.clear();
         unsafe { self.flags.assume_init_drop() };
     }
 }
+
+#[cfg(test)]
+impl RblxVM {
+    /// Creates a VM for unit tests. Nothing gets printed to Godot, and the VM holds the global lock of the
+    /// test's thread the way it does during a frame.
+    pub(crate) fn new_for_test() -> Box<RwLock<RblxVM>> {
+        let mut vm = RblxVM::new(None);
+        vm.get_mut().get_log_service().clear_hooks();
+        unsafe { vm.get_mut().set_global_lock_state(false) };
+        vm.get_mut().push_global_lock_atomic();
+        vm
+    }
+}
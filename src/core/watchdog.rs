@@ -1,6 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 
-use r2g_mlua::ffi::lua_clock;
+use super::real_clock;
 
 #[derive(Debug)]
 pub struct Watchdog {
@@ -28,20 +28,20 @@ impl Watchdog {
     pub fn set_timeout(&mut self, timeout: f64) {
         self.timeout = timeout;
     }
-    #[inline(always)]
-    fn clock() -> f64 {
-        unsafe { lua_clock() }
-    }
     #[inline]
     pub fn trip(&self) {
         self.flag.store(true, Relaxed);
     }
     #[inline]
+    pub fn is_tripped(&self) -> bool {
+        self.flag.load(Relaxed)
+    }
+    #[inline]
     pub fn check(&self) -> bool {
         if self.flag.load(Relaxed) {
             true
         } else {
-            let flag = self.timeout + self.watchdog < Self::clock() && self.timeout != 0f64;
+            let flag = self.timeout + self.watchdog < real_clock() && self.timeout != 0f64;
             if flag {
                 self.trip()
             }
@@ -50,7 +50,7 @@ impl Watchdog {
     }
     #[inline]
     pub fn reset(&mut self) {
-        self.watchdog = Self::clock();
+        self.watchdog = real_clock();
         self.flag.store(false, Relaxed);
     }
     #[inline]
@@ -0,0 +1,316 @@
+use godot::classes::code_edit::CodeCompletionKind;
+use r2g_mlua::prelude::*;
+use r2g_mlua::Compiler;
+
+use crate::core::lua_macros::args_to_string;
+use crate::core::{get_state, LuauState, ManagedInstance, MemberKind, ThreadIdentityType};
+use crate::instance::escape_bbcode_and_format;
+use crate::userdata::enums::MessageType;
+
+pub(super) const COMMAND_BAR_CHUNK: &str = "<command bar>";
+
+/// Calls the compiled entry and prints what it returned, from Luau so the entry can still yield.
+const DISPLAY_WRAPPER: &str = "local f, display = ...\nreturn function() display(f()) end";
+
+const MESSAGE_TYPES: [MessageType; 4] = [
+    MessageType::MessageOutput,
+    MessageType::MessageInfo,
+    MessageType::MessageWarning,
+    MessageType::MessageError,
+];
+
+/// The output and command history of the console, kept to rebuild the output whenever the filter changes.
+#[derive(Default)]
+pub(super) struct ConsoleState {
+    /// The Godot log and the startup message, only shown while nothing is filtered out.
+    header: String,
+    messages: Vec<(String, MessageType)>,
+    hidden: [bool; 4],
+    search: String,
+    history: Vec<String>,
+    history_index: usize,
+    /// The entry being typed before going through the history.
+    draft: String,
+}
+
+impl ConsoleState {
+    pub(super) fn set_header(&mut self, header: String) {
+        self.header = header;
+    }
+    fn is_filtered(&self) -> bool {
+        self.hidden.iter().any(|x| *x) || !self.search.is_empty()
+    }
+    fn is_shown(&self, message: &str, message_type: MessageType) -> bool {
+        !self.hidden[message_type as usize]
+            && (self.search.is_empty() || message.to_lowercase().contains(&self.search))
+    }
+    /// Records a message of the LogService, returning the text to append to the output if the filter shows it.
+    pub(super) fn push_message(
+        &mut self,
+        message: String,
+        message_type: MessageType,
+    ) -> Option<String> {
+        let shown = self
+            .is_shown(&message, message_type)
+            .then(|| escape_bbcode_and_format(message.clone(), message_type) + "\n");
+        self.messages.push((message, message_type));
+        shown
+    }
+    pub(super) fn clear_messages(&mut self) {
+        self.messages.clear();
+    }
+    /// Shows or hides the messages of each type, in the order of the `MessageType` enum.
+    pub(super) fn set_filter(&mut self, shown: [bool; 4], search: &str) {
+        self.hidden = shown.map(|x| !x);
+        self.search = search.to_lowercase();
+    }
+    /// Builds the whole output as the filter shows it.
+    pub(super) fn render(&self) -> String {
+        let mut output = if self.is_filtered() {
+            String::new()
+        } else {
+            self.header.clone()
+        };
+        for (message, message_type) in self.messages.iter() {
+            if self.is_shown(message, *message_type) {
+                output += &escape_bbcode_and_format(message.clone(), *message_type);
+                output.push('\n');
+            }
+        }
+        output
+    }
+    pub(super) fn push_history(&mut self, entry: String) {
+        if self.history.last() != Some(&entry) {
+            self.history.push(entry);
+        }
+        self.history_index = self.history.len();
+        self.draft.clear();
+    }
+    /// Steps back through the history, `current` being what the command bar holds now.
+    pub(super) fn history_previous(&mut self, current: String) -> Option<&str> {
+        if self.history_index == 0 {
+            return None;
+        }
+        if self.history_index == self.history.len() {
+            self.draft = current;
+        }
+        self.history_index -= 1;
+        Some(&self.history[self.history_index])
+    }
+    pub(super) fn history_next(&mut self) -> Option<&str> {
+        if self.history_index >= self.history.len() {
+            return None;
+        }
+        self.history_index += 1;
+        Some(self.history.get(self.history_index).unwrap_or(&self.draft))
+    }
+}
+
+pub(super) fn message_type_from_index(index: i64) -> Option<MessageType> {
+    MESSAGE_TYPES.get(usize::try_from(index).ok()?).copied()
+}
+
+/// Returns true when `source` only fails to compile because it ends early, like an unclosed `do` block.
+/// Pressing enter continues such an entry on a new line instead of running it.
+pub(super) fn is_incomplete(source: &str) -> bool {
+    match Compiler::new().compile(source) {
+        Err(LuaError::SyntaxError { message, .. }) => {
+            message.ends_with("<eof>") || message.ends_with("unfinished comment")
+        }
+        _ => false,
+    }
+}
+
+/// Compiles an entry of the command bar. Entries that are an expression get their values printed, the way a
+/// REPL does, anything else runs as is.
+pub(super) fn compile_command(state: &mut LuauState, source: &str) -> LuaResult<LuaFunction> {
+    let env = state.create_env_from_global()?;
+    let func = match state.compile(
+        COMMAND_BAR_CHUNK,
+        &format!("return {}", source),
+        env.clone(),
+    ) {
+        Ok(x) => x,
+        Err(_) => state.compile(COMMAND_BAR_CHUNK, source, env)?,
+    };
+    let lua = state.get_lua();
+    let display = lua.create_function(|lua, values: LuaMultiValue| {
+        if !values.is_empty() {
+            get_state(lua)
+                .get_log_service()
+                .log_message(lua, args_to_string(values, "\t"));
+        }
+        Ok(())
+    })?;
+    lua.load(DISPLAY_WRAPPER)
+        .set_name(COMMAND_BAR_CHUNK)
+        .call((func, display))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_')
+}
+
+/// Splits the expression typed at the end of `line` into the names leading to the word being completed, and
+/// whether that word follows a `:`. `game.Workspace:Fi` gives `(["game", "Workspace"], true)`.
+pub(super) fn completion_path(line: &str) -> Option<(Vec<&str>, bool)> {
+    let start = line
+        .char_indices()
+        .rev()
+        .take_while(|(_, x)| x.is_ascii_alphanumeric() || matches!(x, '_' | '.' | ':'))
+        .last()
+        .map_or(line.len(), |(i, _)| i);
+    let expression = &line[start..];
+    let separator = expression.rfind(['.', ':']);
+    // Only the last separator may be a `:`, `a:b.c` isn't an expression.
+    if expression[..separator.unwrap_or(0)].contains(':') {
+        return None;
+    }
+    let mut names: Vec<&str> = expression.split(['.', ':']).collect();
+    names.pop();
+    if !names.iter().all(|x| is_identifier(x)) {
+        return None;
+    }
+    Some((names, separator.is_some_and(|x| &expression[x..=x] == ":")))
+}
+
+fn value_kind(value: &LuaValue) -> CodeCompletionKind {
+    match value {
+        LuaValue::Function(_) => CodeCompletionKind::FUNCTION,
+        LuaValue::Table(_) => CodeCompletionKind::CLASS,
+        _ => CodeCompletionKind::VARIABLE,
+    }
+}
+
+fn table_completions(table: &LuaTable, method: bool) -> Vec<(String, CodeCompletionKind)> {
+    table
+        .pairs::<LuaValue, LuaValue>()
+        .filter_map(|x| x.ok())
+        .filter_map(|(key, value)| {
+            let key = key.as_str()?.to_string();
+            (is_identifier(&key) && (!method || value.is_function()))
+                .then(|| (key, value_kind(&value)))
+        })
+        .collect()
+}
+
+fn instance_completions(
+    instance: &ManagedInstance,
+    method: bool,
+) -> Vec<(String, CodeCompletionKind)> {
+    let contexts = ThreadIdentityType::StudioCommandBar.get_security_contexts();
+    let mut completions: Vec<(String, CodeCompletionKind)> = instance
+        .get_members()
+        .into_iter()
+        .filter(|x| contexts.has(x.security))
+        .filter_map(|x| {
+            let kind = match (x.kind, method) {
+                (MemberKind::Function, _) => CodeCompletionKind::FUNCTION,
+                (_, true) => return None,
+                (MemberKind::Property, _) => CodeCompletionKind::MEMBER,
+                (MemberKind::Event | MemberKind::Callback, _) => CodeCompletionKind::SIGNAL,
+            };
+            Some((x.name, kind))
+        })
+        .collect();
+    if !method {
+        completions.extend(
+            instance
+                .get_children()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|x| x.get_name())
+                .filter(|x| is_identifier(x))
+                .map(|x| (x, CodeCompletionKind::NODE_PATH)),
+        );
+    }
+    completions
+}
+
+/// Lists what can follow the expression typed at the end of `line`: globals, the fields of a table, or the
+/// members and children of an Instance. Only tables and Instances are walked, so completing never runs code.
+pub(super) fn complete(lua: &Lua, line: &str) -> Vec<(String, CodeCompletionKind)> {
+    let Some((names, method)) = completion_path(line) else {
+        return Vec::new();
+    };
+    let globals = lua.globals();
+    let Some((first, rest)) = names.split_first() else {
+        return table_completions(&globals, false);
+    };
+    let mut value: LuaValue = globals.raw_get(*first).unwrap_or(LuaNil);
+    for name in rest {
+        value = match &value {
+            LuaValue::Table(x) => x.raw_get(*name).unwrap_or(LuaNil),
+            LuaValue::UserData(_) => match ManagedInstance::from_lua(value.clone(), lua) {
+                Ok(x) => x.lua_get(lua, name.to_string()).unwrap_or(LuaNil),
+                Err(_) => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        };
+    }
+    match &value {
+        LuaValue::Table(x) => table_completions(x, method),
+        LuaValue::String(_) if method => globals
+            .raw_get::<LuaTable>("string")
+            .map(|x| table_completions(&x, true))
+            .unwrap_or_default(),
+        LuaValue::UserData(_) => ManagedInstance::from_lua(value.clone(), lua)
+            .map(|x| instance_completions(&x, method))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{completion_path, is_incomplete, ConsoleState};
+
+    #[test]
+    fn splits_the_expression_being_completed() {
+        assert_eq!(
+            completion_path("game.Workspace:Fi"),
+            Some((vec!["game", "Workspace"], true))
+        );
+        assert_eq!(
+            completion_path("print(game.Work"),
+            Some((vec!["game"], false))
+        );
+        assert_eq!(completion_path("local x = pri"), Some((vec![], false)));
+        assert_eq!(completion_path("a:b.c"), None);
+        assert_eq!(completion_path("a.1b.c"), None);
+    }
+
+    #[test]
+    fn continues_entries_that_end_early() {
+        assert!(is_incomplete("do"));
+        assert!(is_incomplete("for i = 1, 10 do\n\tprint(i)"));
+        assert!(is_incomplete("local t = {"));
+        assert!(is_incomplete("--[[ a comment"));
+        assert!(!is_incomplete("print(1)"));
+        assert!(!is_incomplete("local x = = 1"));
+        assert!(!is_incomplete("end"));
+    }
+
+    #[test]
+    fn history_brings_back_the_draft() {
+        let mut console = ConsoleState::default();
+        console.push_history("first".into());
+        console.push_history("second".into());
+        console.push_history("second".into());
+        assert_eq!(console.history_next(), None);
+        assert_eq!(console.history_previous("typing".into()), Some("second"));
+        assert_eq!(console.history_previous("second".into()), Some("first"));
+        assert_eq!(console.history_previous("first".into()), None);
+        assert_eq!(console.history_next(), Some("second"));
+        assert_eq!(console.history_next(), Some("typing"));
+        assert_eq!(console.history_next(), None);
+        // Running an entry drops the draft.
+        console.push_history("third".into());
+        assert_eq!(console.history_previous(String::new()), Some("third"));
+        assert_eq!(console.history_next(), Some(""));
+    }
+}
@@ -0,0 +1,118 @@
+use godot::prelude::*;
+use r2g_mlua::prelude::*;
+
+use super::lua_to_variant;
+
+/// An error raised while compiling or running code pushed from Godot, returned in place of the result.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct RblxError {
+    message: GString,
+
+    base: Base<RefCounted>,
+}
+
+impl RblxError {
+    pub fn new_gd(message: impl Into<GString>) -> Gd<RblxError> {
+        let message = message.into();
+        Gd::from_init_fn(|base| RblxError { message, base })
+    }
+}
+
+#[godot_api]
+impl RblxError {
+    #[func]
+    fn get_message(&self) -> GString {
+        self.message.clone()
+    }
+}
+
+/// The result of [method RblxVMNode.run_async], completing once the thread running the code finishes.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct RblxAsyncResult {
+    result: Option<Variant>,
+
+    base: Base<RefCounted>,
+}
+
+impl RblxAsyncResult {
+    pub fn new_gd() -> Gd<RblxAsyncResult> {
+        Gd::from_init_fn(|base| RblxAsyncResult { result: None, base })
+    }
+    /// Stores the result and emits [signal completed] once Godot is idle, so handlers never run while the VM is
+    /// running threads.
+    pub fn complete(this: &Gd<RblxAsyncResult>, result: Variant) {
+        this.clone().bind_mut().result = Some(result.clone());
+        // The callable keeps the object alive until the signal got emitted.
+        let mut object = this.clone().upcast::<Object>();
+        Callable::from_local_fn("RblxAsyncResult::complete", move |_| {
+            object.emit_signal("completed", std::slice::from_ref(&result));
+            Ok(Variant::nil())
+        })
+        .call_deferred(&[]);
+    }
+}
+
+#[godot_api]
+impl RblxAsyncResult {
+    /// Emitted with the values the code returned, or a [RblxError] if it failed.
+    #[signal]
+    fn completed(result: Variant);
+
+    #[func]
+    fn is_completed(&self) -> bool {
+        self.result.is_some()
+    }
+    /// Returns the values the code returned, or [code]null[/code] while it is still running.
+    #[func]
+    fn get_result(&self) -> Variant {
+        self.result.clone().unwrap_or_default()
+    }
+}
+
+/// Converts what a chunk of the VM of the RblxVMNode `vm` returned: nothing becomes null, a single value its
+/// Variant, several an Array.
+pub(super) fn results_to_variant(values: &LuaMultiValue, vm: InstanceId) -> Variant {
+    match values.len() {
+        0 => Variant::nil(),
+        1 => lua_to_variant(&values[0], vm),
+        _ => values
+            .iter()
+            .map(|x| lua_to_variant(x, vm))
+            .collect::<VariantArray>()
+            .to_variant(),
+    }
+}
+
+/// Calls the chunk in protected mode and hands the outcome to `done`, from Luau so the chunk can still yield.
+const COMPLETION_WRAPPER: &str = "local f, done = ...\nreturn function() done(pcall(f)) end";
+
+/// Wraps `func` so it completes `result` with what it returns, or with the error it raises.
+pub(super) fn complete_with_result(
+    lua: &Lua,
+    chunk_name: &str,
+    func: LuaFunction,
+    result: &Gd<RblxAsyncResult>,
+    vm: InstanceId,
+) -> LuaResult<LuaFunction> {
+    let result = result.clone();
+    let done = lua.create_function(move |_, mut values: LuaMultiValue| {
+        let variant = match values.pop_front() {
+            Some(LuaValue::Boolean(true)) => results_to_variant(&values, vm),
+            _ => {
+                let message = match values.pop_front() {
+                    Some(LuaValue::String(x)) => x.to_string_lossy(),
+                    Some(x) => x.to_string().unwrap_or_default(),
+                    None => String::new(),
+                };
+                RblxError::new_gd(message).to_variant()
+            }
+        };
+        RblxAsyncResult::complete(&result, variant);
+        Ok(())
+    })?;
+    lua.load(COMPLETION_WRAPPER)
+        .set_name(chunk_name)
+        .call((func, done))
+}
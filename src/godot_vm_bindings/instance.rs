@@ -0,0 +1,248 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use godot::{global::Error, prelude::*};
+use r2g_mlua::prelude::*;
+
+use super::eval::results_to_variant;
+use super::{lua_to_variant, variant_to_lua, RblxError, RblxVMNode};
+use crate::core::{ManagedInstance, WeakManagedInstance};
+use crate::userdata::RBXScriptConnection;
+
+const INSTANCE_CHUNK: &str = "<godot instance>";
+const GET_PROPERTY: &str = "local instance, name = ...\nreturn instance[name]";
+const SET_PROPERTY: &str = "local instance, name, value = ...\ninstance[name] = value";
+const CALL_METHOD: &str =
+    "local instance, name = ...\nreturn instance[name](instance, select(3, ...))";
+const CONNECT_SIGNAL: &str =
+    "local instance, name, callback = ...\nreturn instance[name]:Connect(callback)";
+const CONNECT_PROPERTY_CHANGED: &str = "local instance, name, callback = ...\n\
+    return instance:GetPropertyChangedSignal(name):Connect(callback)";
+
+/// Binds the node running the VM. Binding fails when the node is already running code, such as when Godot code
+/// called from the VM uses an instance. This version of godot has no `try_bind_mut`, so the panic is caught.
+fn try_bind_mut(node: &mut Gd<RblxVMNode>) -> LuaResult<GdMut<'_, RblxVMNode>> {
+    panic::catch_unwind(AssertUnwindSafe(move || {
+        // Moved in, so the closure is FnOnce and can hand the guard out.
+        let node = node;
+        node.bind_mut()
+    }))
+    .map_err(|_| {
+        LuaError::RuntimeError(
+            "the RblxVMNode is busy, instances can't be used from Godot code the VM calls".into(),
+        )
+    })
+}
+
+/// An Instance of the DataModel, as seen from Godot.
+/// It holds the instance weakly, so it doesn't keep destroyed instances alive.
+/// Properties and methods are accessed with the UserInit identity, like [method RblxVMNode.eval] does.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct RblxInstance {
+    instance: WeakManagedInstance,
+    /// The RblxVMNode running the VM the instance belongs to.
+    vm: InstanceId,
+
+    base: Base<RefCounted>,
+}
+
+impl RblxInstance {
+    pub fn new_gd(instance: &ManagedInstance, vm: InstanceId) -> Gd<RblxInstance> {
+        Gd::from_init_fn(|base| RblxInstance {
+            instance: instance.downgrade(),
+            vm,
+            base,
+        })
+    }
+    /// Gets the wrapped instance, if it still exists.
+    pub fn get_instance(&self) -> Option<ManagedInstance> {
+        self.instance.upgrade()
+    }
+    /// Runs `chunk` on the VM with the instance, then the values `args` gives.
+    fn call_chunk(
+        &self,
+        chunk: &str,
+        args: impl FnOnce(&Lua) -> LuaResult<Vec<LuaValue>>,
+    ) -> LuaResult<LuaMultiValue> {
+        let instance = self
+            .get_instance()
+            .ok_or_else(|| LuaError::RuntimeError("the instance no longer exists".into()))?;
+        let mut node = Gd::<RblxVMNode>::try_from_instance_id(self.vm).map_err(|_| {
+            LuaError::RuntimeError("the RblxVMNode of the instance no longer exists".into())
+        })?;
+        let mut node = try_bind_mut(&mut node)?;
+        node.call_now(|state| {
+            let lua = state.get_lua();
+            let func = lua.load(chunk).set_name(INSTANCE_CHUNK).into_function()?;
+            let mut values = LuaMultiValue::new();
+            values.push_back(instance.into_lua(lua)?);
+            values.extend(args(lua)?);
+            Ok((func, values))
+        })
+    }
+    fn result_to_variant(&self, result: LuaResult<LuaMultiValue>) -> Variant {
+        match result {
+            Ok(values) => results_to_variant(&values, self.vm),
+            Err(err) => RblxError::new_gd(err.to_string()).to_variant(),
+        }
+    }
+    /// Connects `callable` to the signal `chunk` returns, the callable gets called once Godot is idle with the
+    /// arguments of the signal.
+    fn connect(
+        &self,
+        chunk: &str,
+        name: GString,
+        callable: Callable,
+    ) -> Option<Gd<RblxConnection>> {
+        let vm = self.vm;
+        self.call_chunk(chunk, |lua| {
+            let callback = lua.create_function(move |_, args: LuaMultiValue| {
+                let args: Vec<Variant> = args.iter().map(|x| lua_to_variant(x, vm)).collect();
+                callable.call_deferred(&args);
+                Ok(())
+            })?;
+            Ok(vec![
+                name.to_string().into_lua(lua)?,
+                LuaValue::Function(callback),
+            ])
+        })
+        .and_then(|values| match values.front() {
+            Some(LuaValue::UserData(x)) => Ok(x.borrow::<RBXScriptConnection>()?.clone()),
+            _ => Err(LuaError::RuntimeError(
+                "Connect didn't return an RBXScriptConnection".into(),
+            )),
+        })
+        .inspect_err(|err| godot_error!("RblxInstance: {}", err))
+        .ok()
+        .map(RblxConnection::new_gd)
+    }
+}
+
+#[godot_api]
+impl RblxInstance {
+    /// Returns [code]false[/code] once the instance no longer exists.
+    #[func]
+    fn is_valid(&self) -> bool {
+        self.get_instance().is_some()
+    }
+    /// Returns the ClassName of the instance, or an empty string if it no longer exists.
+    #[func]
+    fn get_class_name(&self) -> GString {
+        self.get_instance()
+            .map(|x| GString::from(x.get_class_name()))
+            .unwrap_or_default()
+    }
+    /// Returns the full name of the instance, such as [code]Workspace.Model.Part[/code], or an empty string if it no longer exists.
+    #[func]
+    fn get_full_name(&self) -> GString {
+        self.get_instance()
+            .and_then(|x| x.get_full_name().ok())
+            .map(GString::from)
+            .unwrap_or_default()
+    }
+    /// Returns the value of a property or a child with this name, converted like [method RblxVMNode.eval] does.
+    /// Returns a [RblxError] if it can't be read.
+    #[func]
+    fn get_property(&self, name: GString) -> Variant {
+        self.result_to_variant(self.call_chunk(GET_PROPERTY, |lua| {
+            Ok(vec![name.to_string().into_lua(lua)?])
+        }))
+    }
+    /// Sets a property, the value gets converted to its Luau counterpart.
+    #[func]
+    fn set_property(&self, name: GString, value: Variant) -> Error {
+        match self.call_chunk(SET_PROPERTY, |lua| {
+            Ok(vec![
+                name.to_string().into_lua(lua)?,
+                variant_to_lua(lua, &value)?,
+            ])
+        }) {
+            Ok(_) => Error::OK,
+            Err(err) => {
+                godot_error!("RblxInstance: {}", err);
+                Error::FAILED
+            }
+        }
+    }
+    /// Calls a method with the given arguments and returns what it returned, like [method RblxVMNode.eval] does.
+    /// Methods that yield can't be called. Returns a [RblxError] if the call fails.
+    #[func]
+    fn call_method(&self, name: GString, args: VariantArray) -> Variant {
+        self.result_to_variant(self.call_chunk(CALL_METHOD, |lua| {
+            let mut values = vec![name.to_string().into_lua(lua)?];
+            for x in args.iter_shared() {
+                values.push(variant_to_lua(lua, &x)?);
+            }
+            Ok(values)
+        }))
+    }
+    /// Returns the children of the instance, or an empty array if it no longer exists.
+    #[func]
+    fn get_children(&self) -> Array<Gd<RblxInstance>> {
+        self.get_instance()
+            .and_then(|x| x.get_children().ok())
+            .unwrap_or_default()
+            .iter()
+            .map(|x| RblxInstance::new_gd(x, self.vm))
+            .collect()
+    }
+    /// Returns the first child with this name, or the first descendant if [param recursive] is set.
+    #[func]
+    fn find_first_child(&self, name: GString, recursive: bool) -> Option<Gd<RblxInstance>> {
+        let instance = self.get_instance()?;
+        let name = name.to_string();
+        let candidates = if recursive {
+            instance.get_descendants()
+        } else {
+            instance.get_children()
+        };
+        candidates
+            .ok()?
+            .iter()
+            .find(|x| x.get_name() == name)
+            .map(|x| RblxInstance::new_gd(x, self.vm))
+    }
+    /// Connects a [Callable] to an RBXScriptSignal of the instance, such as [code]ChildAdded[/code] or [code]Changed[/code].
+    /// The callable gets called once Godot is idle, with the arguments of the signal converted like [method RblxVMNode.eval] does.
+    /// Returns [code]null[/code] if the instance has no such signal.
+    #[func]
+    fn connect_signal(&self, signal: GString, callable: Callable) -> Option<Gd<RblxConnection>> {
+        self.connect(CONNECT_SIGNAL, signal, callable)
+    }
+    /// Connects a [Callable] to the changes of a property, like [method connect_signal] does with [code]GetPropertyChangedSignal[/code].
+    #[func]
+    fn connect_property_changed(
+        &self,
+        property: GString,
+        callable: Callable,
+    ) -> Option<Gd<RblxConnection>> {
+        self.connect(CONNECT_PROPERTY_CHANGED, property, callable)
+    }
+}
+
+/// A connection made by [method RblxInstance.connect_signal].
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct RblxConnection {
+    connection: RBXScriptConnection,
+
+    base: Base<RefCounted>,
+}
+
+impl RblxConnection {
+    pub fn new_gd(connection: RBXScriptConnection) -> Gd<RblxConnection> {
+        Gd::from_init_fn(|base| RblxConnection { connection, base })
+    }
+}
+
+#[godot_api]
+impl RblxConnection {
+    #[func]
+    fn is_connected(&self) -> bool {
+        self.connection.is_connected()
+    }
+    #[func]
+    fn disconnect(&self) {
+        self.connection.disconnect();
+    }
+}
@@ -1,2 +1,9 @@
+mod console;
+mod eval;
+mod instance;
+mod variant;
 mod vm_node;
+pub use eval::RblxError;
+pub use instance::RblxInstance;
+pub(crate) use variant::{lua_to_variant, variant_to_lua};
 pub use vm_node::RblxVMNode;
@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use std::ffi::c_void;
+
+use godot::builtin::real;
+use godot::prelude::*;
+use r2g_mlua::prelude::*;
+
+use super::RblxInstance;
+use crate::core::ManagedInstance;
+use crate::userdata::{CFrame, Vector2int16, Vector3int16};
+
+type RbxVector2 = crate::userdata::Vector2;
+type RbxVector3 = crate::userdata::Vector3;
+
+/// Converts a Luau value of the VM of the RblxVMNode `vm` into a Variant.
+///
+/// Sequences become an Array and other tables a Dictionary, Vector3, Vector2 and CFrame become their Godot
+/// counterparts, EnumItems their value and Instances a [`RblxInstance`]. Functions, threads and tables
+/// nested into themselves become null, other userdata the string `tostring` gives.
+pub(crate) fn lua_to_variant(value: &LuaValue, vm: InstanceId) -> Variant {
+    to_variant(value, vm, &mut HashSet::new())
+}
+
+fn to_variant(value: &LuaValue, vm: InstanceId, visited: &mut HashSet<*const c_void>) -> Variant {
+    match value {
+        LuaValue::Boolean(x) => x.to_variant(),
+        LuaValue::Integer(x) => (*x as i64).to_variant(),
+        LuaValue::Number(x) => x.to_variant(),
+        LuaValue::Vector(x) => {
+            Vector3::new(x.x() as real, x.y() as real, x.z() as real).to_variant()
+        }
+        LuaValue::String(x) => GString::from(&*x.to_string_lossy()).to_variant(),
+        LuaValue::Table(x) => {
+            if !visited.insert(x.to_pointer()) {
+                return Variant::nil();
+            }
+            let variant = table_to_variant(x, vm, visited);
+            visited.remove(&x.to_pointer());
+            variant
+        }
+        LuaValue::UserData(x) => userdata_to_variant(x, vm),
+        _ => Variant::nil(),
+    }
+}
+
+fn table_to_variant(
+    table: &LuaTable,
+    vm: InstanceId,
+    visited: &mut HashSet<*const c_void>,
+) -> Variant {
+    let pairs: Vec<(LuaValue, LuaValue)> = table.pairs().filter_map(|x| x.ok()).collect();
+    if pairs.len() == table.raw_len() {
+        // Sequences keep their order, pairs doesn't promise one.
+        let mut array = VariantArray::new();
+        for x in table.sequence_values::<LuaValue>().filter_map(|x| x.ok()) {
+            array.push(&to_variant(&x, vm, visited));
+        }
+        array.to_variant()
+    } else {
+        let mut dict = Dictionary::new();
+        for (key, value) in pairs.iter() {
+            let _ = dict.insert(to_variant(key, vm, visited), to_variant(value, vm, visited));
+        }
+        dict.to_variant()
+    }
+}
+
+fn vector3(x: &RbxVector3) -> Vector3 {
+    Vector3::new(x.x as real, x.y as real, x.z as real)
+}
+
+fn userdata_to_variant(userdata: &LuaAnyUserData, vm: InstanceId) -> Variant {
+    if let Ok(x) = userdata.borrow::<ManagedInstance>() {
+        RblxInstance::new_gd(&x, vm).to_variant()
+    } else if let Ok(x) = userdata.borrow::<RbxVector3>() {
+        vector3(&x).to_variant()
+    } else if let Ok(x) = userdata.borrow::<RbxVector2>() {
+        Vector2::new(x.x as real, x.y as real).to_variant()
+    } else if let Ok(x) = userdata.borrow::<CFrame>() {
+        let [r0, r1, r2] = x
+            .rot_matrix
+            .map(|[x, y, z]| vector3(&RbxVector3 { x, y, z }));
+        let [x, y, z] = x.pos;
+        Transform3D::new(
+            Basis::from_rows(r0, r1, r2),
+            vector3(&RbxVector3 { x, y, z }),
+        )
+        .to_variant()
+    } else if let Ok(x) = userdata.borrow::<Vector3int16>() {
+        Vector3i::new(x.x as i32, x.y as i32, x.z as i32).to_variant()
+    } else if let Ok(x) = userdata.borrow::<Vector2int16>() {
+        Vector2i::new(x.x as i32, x.y as i32).to_variant()
+    } else if userdata
+        .metatable()
+        .and_then(|x| x.get::<String>("__type"))
+        .is_ok_and(|x| x == "EnumItem")
+    {
+        LuaObjectLike::get::<i64>(userdata, "Value")
+            .unwrap_or_default()
+            .to_variant()
+    } else {
+        LuaObjectLike::to_string(userdata)
+            .map(|x| x.to_variant())
+            .unwrap_or_default()
+    }
+}
+
+fn unsupported(variant: &Variant) -> LuaError {
+    LuaError::RuntimeError(format!(
+        "cannot convert a {:?} to a Luau value",
+        variant.get_type()
+    ))
+}
+
+macro_rules! packed_to_table {
+    ($lua: expr, $variant: expr, $type: ty) => {
+        $lua.create_sequence_from(
+            $variant
+                .to::<$type>()
+                .as_slice()
+                .iter()
+                .map(|x| variant_to_lua($lua, &x.to_variant()))
+                .collect::<LuaResult<Vec<LuaValue>>>()?,
+        )
+        .map(LuaValue::Table)
+    };
+}
+
+/// Converts a Variant into a Luau value, the opposite of [`lua_to_variant`].
+/// Arrays, packed arrays and Dictionaries become tables, Vector3, Vector2 and Transform3D become their Luau
+/// counterparts and [`RblxInstance`]s the Instance they wrap. Anything else is an error.
+pub(crate) fn variant_to_lua(lua: &Lua, variant: &Variant) -> LuaResult<LuaValue> {
+    match variant.get_type() {
+        VariantType::NIL => Ok(LuaNil),
+        VariantType::BOOL => Ok(LuaValue::Boolean(variant.to())),
+        VariantType::INT => variant.to::<i64>().into_lua(lua),
+        VariantType::FLOAT => Ok(LuaValue::Number(variant.to())),
+        VariantType::STRING | VariantType::STRING_NAME | VariantType::NODE_PATH => {
+            lua.create_string(variant.to_string()).map(LuaValue::String)
+        }
+        VariantType::VECTOR2 => {
+            let x = variant.to::<Vector2>();
+            RbxVector2::new(x.x as f64, x.y as f64).into_lua(lua)
+        }
+        VariantType::VECTOR2I => {
+            let x = variant.to::<Vector2i>();
+            RbxVector2::new(x.x as f64, x.y as f64).into_lua(lua)
+        }
+        VariantType::VECTOR3 => {
+            let x = variant.to::<Vector3>();
+            RbxVector3::new(x.x as f64, x.y as f64, x.z as f64).into_lua(lua)
+        }
+        VariantType::VECTOR3I => {
+            let x = variant.to::<Vector3i>();
+            RbxVector3::new(x.x as f64, x.y as f64, x.z as f64).into_lua(lua)
+        }
+        VariantType::TRANSFORM3D => {
+            let x = variant.to::<Transform3D>();
+            let [r0, r1, r2] = x
+                .basis
+                .rows
+                .map(|x| RbxVector3::new(x.x as f64, x.y as f64, x.z as f64));
+            let pos = RbxVector3::new(x.origin.x as f64, x.origin.y as f64, x.origin.z as f64);
+            CFrame::from_matrix(pos, r0, r1, r2).into_lua(lua)
+        }
+        VariantType::ARRAY => lua
+            .create_sequence_from(
+                variant
+                    .to::<VariantArray>()
+                    .iter_shared()
+                    .map(|x| variant_to_lua(lua, &x))
+                    .collect::<LuaResult<Vec<LuaValue>>>()?,
+            )
+            .map(LuaValue::Table),
+        VariantType::PACKED_BYTE_ARRAY => packed_to_table!(lua, variant, PackedByteArray),
+        VariantType::PACKED_INT32_ARRAY => packed_to_table!(lua, variant, PackedInt32Array),
+        VariantType::PACKED_INT64_ARRAY => packed_to_table!(lua, variant, PackedInt64Array),
+        VariantType::PACKED_FLOAT32_ARRAY => packed_to_table!(lua, variant, PackedFloat32Array),
+        VariantType::PACKED_FLOAT64_ARRAY => packed_to_table!(lua, variant, PackedFloat64Array),
+        VariantType::PACKED_STRING_ARRAY => packed_to_table!(lua, variant, PackedStringArray),
+        VariantType::PACKED_VECTOR2_ARRAY => packed_to_table!(lua, variant, PackedVector2Array),
+        VariantType::PACKED_VECTOR3_ARRAY => packed_to_table!(lua, variant, PackedVector3Array),
+        VariantType::DICTIONARY => {
+            let table = lua.create_table()?;
+            for (key, value) in variant.to::<Dictionary>().iter_shared() {
+                table.raw_set(variant_to_lua(lua, &key)?, variant_to_lua(lua, &value)?)?;
+            }
+            Ok(LuaValue::Table(table))
+        }
+        VariantType::OBJECT => match variant.try_to::<Option<Gd<RblxInstance>>>() {
+            Ok(Some(x)) => x.bind().get_instance().into_lua(lua),
+            Ok(None) => Ok(LuaNil),
+            Err(_) => Err(unsupported(variant)),
+        },
+        _ => Err(unsupported(variant)),
+    }
+}
@@ -1,21 +1,45 @@
-use std::{collections::HashMap, mem::transmute};
+use std::{
+    collections::HashMap,
+    mem::transmute,
+    path::{Path, PathBuf},
+};
 
 use bevy_reflect::Typed;
 use godot::{
-    classes::{node::InternalMode, Engine, FileAccess, RichTextLabel},
-    global::Error,
+    classes::{
+        file_access::ModeFlags, node::InternalMode, BaseButton, CodeEdit, Engine, FileAccess,
+        InputEvent, InputEventKey, LineEdit, Performance, ProjectSettings, RichTextLabel,
+    },
+    global::{Error, Key},
     prelude::*,
 };
+use r2g_mlua::prelude::{LuaError, LuaFunction, LuaMultiValue, LuaResult, LuaThreadStatus};
 
+use super::console::{
+    compile_command, complete, is_incomplete, message_type_from_index, ConsoleState,
+};
+use super::eval::{complete_with_result, results_to_variant, RblxAsyncResult, RblxError};
+use super::RblxInstance;
 use crate::{
     core::{
-        borrowck_ignore_mut, get_state, FastFlag, FastFlagValue, GlobalTaskScheduler,
-        ParallelDispatch::Synchronized, RblxVM, RwLock, ThreadIdentity, ThreadIdentityType,
+        borrowck_ignore_mut, chunk_display_name, get_state, get_task_scheduler_from_lua, ClockMode,
+        FastFlag, FastFlagValue, FrameStats, GlobalTaskScheduler, LuauState, ManagedInstance,
+        ParallelDispatch::Synchronized, RblxVM, RunState, RwLock, ThreadIdentity,
+        ThreadIdentityType,
     },
-    instance::escape_bbcode_and_format,
+    instance::IGodotBridge,
+    project::{self, find_path},
 };
 
+const LOG_WINDOW: &str = "ConsoleInterface/ConsoleInterface/PanelContainer/RichTextLabel";
+const COMMAND_BAR: &str = "ConsoleInterface/ConsoleInterface/PanelContainer2/CommandBar";
+const GODOT_CHUNK: &str = "<godot>";
+const FILTERS: &str = "ConsoleInterface/ConsoleInterface/Filters";
+/// The filter buttons, in the order of the `MessageType` enum.
+const FILTER_BUTTONS: [&str; 4] = ["Output", "Info", "Warnings", "Errors"];
+
 /// The RblxVM node, holding either a client or a server state, depending on the startup flags.
+/// Every node runs its own VM, so a server and its clients can live in the same scene.
 ///
 /// [b]Note:[/b] This object is not thread-safe. It should from only a single thread.
 #[derive(GodotClass)]
@@ -26,6 +50,12 @@ pub struct RblxVMNode {
     /// [b]Note:[/b] This is only loaded on startup! At runtime, you have to use the [method set_fast_flag_async] and [method get_fast_flag] methods.
     #[export]
     startup_flags: Dictionary,
+    /// Starts the VM once the node is ready, and again whenever it re-enters the tree. Otherwise it waits for [method start].
+    #[export]
+    auto_start: bool,
+    console: ConsoleState,
+    /// The ids of the [Performance] custom monitors of the running VM.
+    monitors: Vec<StringName>,
 
     base: Base<Node>,
 }
@@ -54,6 +84,9 @@ impl INode for RblxVMNode {
         RblxVMNode {
             vm: None,
             startup_flags: dict,
+            auto_start: true,
+            console: ConsoleState::default(),
+            monitors: Vec::new(),
             base: owner,
         }
     }
@@ -62,6 +95,96 @@ impl INode for RblxVMNode {
         if Engine::singleton().is_editor_hint() {
             return;
         }
+        self.setup_console();
+        if self.auto_start {
+            self.start();
+        }
+    }
+
+    fn enter_tree(&mut self) {
+        // Ready only runs once, the VM gets started again when the node comes back to the tree.
+        if self.base().is_node_ready()
+            && self.auto_start
+            && self.vm.is_none()
+            && !Engine::singleton().is_editor_hint()
+        {
+            self.start();
+        }
+    }
+
+    fn process(&mut self, delta: f64) {
+        if let Some(vm) = self.vm.as_mut() {
+            let write = vm
+                .write()
+                .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+                .unwrap();
+            GlobalTaskScheduler::frame_step(write, delta).unwrap();
+        }
+    }
+
+    fn exit_tree(&mut self) {
+        if self.vm.is_some() {
+            self.stop();
+        }
+    }
+}
+
+impl RblxVMNode {
+    fn setup_console(&mut self) {
+        let scene = load::<PackedScene>("res://addons/rblx-godot/ConsoleInterface.tscn");
+        let loaded_scene = scene.instantiate().unwrap();
+
+        self.base_mut()
+            .add_child_ex(&loaded_scene)
+            .internal(InternalMode::FRONT)
+            .done();
+
+        let mut log_window = self.base().get_node_as::<RichTextLabel>(LOG_WINDOW);
+        let meta_clicked_callable = self.base().callable("on_console_meta_clicked");
+        log_window.connect("meta_clicked", &meta_clicked_callable);
+
+        // Read current log file from godot.
+        let header = FileAccess::get_file_as_string("user://logs/godot.log").to_string()
+            + include_str!("startup_message.rtf");
+        log_window.append_text(&header);
+        self.console.set_header(header);
+
+        let filters = self.base().get_node_as::<Node>(FILTERS);
+        let toggled_callable = self.base().callable("on_console_filter_toggled");
+        for name in FILTER_BUTTONS {
+            filters
+                .get_node_as::<BaseButton>(name)
+                .connect("toggled", &toggled_callable);
+        }
+        let search_callable = self.base().callable("on_console_search_changed");
+        filters
+            .get_node_as::<LineEdit>("Search")
+            .connect("text_changed", &search_callable);
+
+        let mut command_bar = self.base().get_node_as::<CodeEdit>(COMMAND_BAR);
+        let input_callable = self.base().callable("on_command_bar_input");
+        command_bar.connect("gui_input", &input_callable);
+        let completion_callable = self.base().callable("on_command_bar_completion_requested");
+        command_bar.connect("code_completion_requested", &completion_callable);
+    }
+    fn set_clock_mode(&self, mode: ClockMode, time: Option<f64>) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        match vm.write() {
+            Ok(mut write) => {
+                write.set_clock_mode(mode, time);
+                Error::OK
+            }
+            Err(_) => {
+                godot_error!("RblxVMNode: failed to acquire write lock on RblxVM");
+                Error::ERR_CANT_ACQUIRE_RESOURCE
+            }
+        }
+    }
+    /// Converts [member startup_flags] to the flags table of the VM, skipping the entries that aren't valid.
+    fn startup_flags_table(&self) -> Vec<(FastFlag, FastFlagValue)> {
         let variants: HashMap<&str, usize> = FastFlag::type_info()
             .as_enum()
             .unwrap()
@@ -106,35 +229,27 @@ impl INode for RblxVMNode {
                 godot_error!("RblxVMNode: {}", e);
             }
         }
-
-        self.vm = Some(RblxVM::new(Some(flags_table)));
-        self.post_init();
+        flags_table
     }
-
-    fn process(&mut self, delta: f64) {
-        if let Some(vm) = self.vm.as_mut() {
-            let write = vm
-                .write()
-                .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
-                .unwrap();
-            GlobalTaskScheduler::frame_step(write, delta).unwrap();
-        }
-    }
-}
-
-impl RblxVMNode {
-    fn post_init(&mut self) {
-        let scene = load::<PackedScene>("res://addons/rblx-godot/ConsoleInterface.tscn");
-        let loaded_scene = scene.instantiate().unwrap();
-
-        self.base_mut()
-            .add_child_ex(&loaded_scene)
-            .internal(InternalMode::FRONT)
-            .done();
-
-        let mut log_window = self.base_mut().get_node_as::<RichTextLabel>(
-            "ConsoleInterface/ConsoleInterface/PanelContainer/RichTextLabel",
+    /// Hooks a freshly created VM up to the node: the console, the Performance monitors and the GodotBridge.
+    fn attach_vm(&mut self) {
+        // Mirror the frame stats into the editor debugger. Every node gets its own category, named after the node
+        // and its instance id, so several VMs don't replace each other's monitors.
+        let mut performance = Performance::singleton();
+        let category = format!(
+            "RblxVM {} {}",
+            self.base().get_name(),
+            self.base().instance_id()
         );
+        let monitor_callable = self.base().callable("get_performance_monitor");
+        for (index, (id, _)) in FrameStats::MONITORS.iter().enumerate() {
+            let id = StringName::from(monitor_id(&category, id));
+            performance
+                .add_custom_monitor_ex(&id, &monitor_callable)
+                .arguments(&varray![index as i64])
+                .done();
+            self.monitors.push(id);
+        }
 
         let vm = self.vm.as_ref().unwrap();
         let read = vm
@@ -143,23 +258,16 @@ impl RblxVMNode {
             .map_err(|_| Error::ERR_CANT_ACQUIRE_RESOURCE)
             .unwrap();
 
-        let add_callable = log_window.callable("append_text");
-
-        let clear_callable = log_window.callable("clear");
+        let message_callable = self.base().callable("on_log_message");
+        let clear_callable = self.base().callable("on_log_cleared");
         read.get_log_service().add_hook(move |str| {
             if let Some((msg, msg_type, _)) = str {
-                add_callable.call_deferred(&[escape_bbcode_and_format(msg, msg_type).to_variant()]);
-                add_callable.call_deferred(&["\n".to_variant()]);
+                message_callable.call_deferred(&[msg.to_variant(), (msg_type as i64).to_variant()]);
             } else {
                 clear_callable.call_deferred(&[]);
             }
         });
-
-        // Read current log file from godot.
-        let log_content = FileAccess::get_file_as_string("user://logs/godot.log");
-
-        log_window.append_text(&log_content);
-        log_window.append_text(include_str!("startup_message.rtf"));
+        <dyn IGodotBridge>::set_root(&*read.get_godot_bridge(), self.base().instance_id());
 
         drop(read);
         #[cfg(debug_assertions)]
@@ -173,10 +281,434 @@ impl RblxVMNode {
             }
         }
     }
+    fn remove_performance_monitors(&mut self) {
+        let mut performance = Performance::singleton();
+        for id in self.monitors.drain(..) {
+            if performance.has_custom_monitor(&id) {
+                performance.remove_custom_monitor(&id);
+            }
+        }
+    }
+    /// Rebuilds the output after the message types or the search text of the console changed.
+    fn refresh_console_output(&mut self) {
+        let filters = self.base().get_node_as::<Node>(FILTERS);
+        let shown = FILTER_BUTTONS.map(|x| filters.get_node_as::<BaseButton>(x).is_pressed());
+        let search = filters.get_node_as::<LineEdit>("Search").get_text();
+        self.console.set_filter(shown, &search.to_string());
+        let mut log_window = self.base().get_node_as::<RichTextLabel>(LOG_WINDOW);
+        log_window.clear();
+        log_window.append_text(&self.console.render());
+    }
+    /// Runs an entry of the command bar with the command bar's identity, printing the values it returns.
+    fn run_command(&mut self, source: String) {
+        if source.trim().is_empty() {
+            return;
+        }
+        self.console.push_history(source.clone());
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return;
+        };
+        let log_service = write.get_log_service();
+        let state = write.get_main_state();
+        let lua = state.get_lua().clone();
+        log_service.log_message(&lua, format!("> {}", source.replace('\n', "\n> ")));
+        if let Err(err) = compile_command(state, &source)
+            .and_then(|func| defer_with_identity(state, func, ThreadIdentityType::StudioCommandBar))
+        {
+            log_service.log_err(&lua, err.to_string());
+        }
+    }
+    /// Resumes the function `build` returns right away, with its arguments, in a thread with the UserInit identity.
+    /// Threads that yield get cancelled, nothing would resume them.
+    pub(super) fn call_now(
+        &mut self,
+        build: impl FnOnce(&mut LuauState) -> LuaResult<(LuaFunction, LuaMultiValue)>,
+    ) -> LuaResult<LuaMultiValue> {
+        let vm = self
+            .vm
+            .as_ref()
+            .ok_or_else(|| LuaError::RuntimeError("RblxVM not initialized".into()))?;
+        let mut write = vm
+            .write()
+            .map_err(|_| LuaError::RuntimeError("failed to acquire write lock on RblxVM".into()))?;
+        let state = write.get_main_state();
+        let lua = state.get_lua().clone();
+        let (func, args) = build(state)?;
+        let thread = lua.create_thread(func)?;
+        state.set_thread_identity(
+            thread.clone(),
+            ThreadIdentity {
+                security_identity: ThreadIdentityType::UserInit,
+                script: None,
+                sandbox: None,
+            },
+        );
+        // Same as a frame of the task scheduler, the instances get locked one by one meanwhile.
+        unsafe { write.set_global_lock_state(false) };
+        write.push_global_lock_atomic();
+        let mut result = thread.resume::<LuaMultiValue>(args);
+        if result.is_ok() && thread.status() == LuaThreadStatus::Resumable {
+            let _ = get_task_scheduler_from_lua(&lua).cancel(&lua, &thread);
+            result = Err(LuaError::RuntimeError(
+                "attempt to yield from Godot, use run_async to run code that yields".into(),
+            ));
+        }
+        write.pop_global_lock_atomic();
+        unsafe { write.set_global_lock_state(true) };
+        result
+    }
+}
+
+/// Moves a monitor id of [`FrameStats::MONITORS`] to `category`.
+fn monitor_id(category: &str, id: &str) -> String {
+    format!("{}/{}", category, id.split_once('/').map_or(id, |x| x.1))
+}
+
+fn set_command_bar_text(command_bar: &mut Gd<CodeEdit>, text: &str) {
+    command_bar.set_text(text);
+    let last_line = command_bar.get_line_count() - 1;
+    command_bar.set_caret_line(last_line);
+    let column = command_bar.get_line(last_line).len() as i32;
+    command_bar.set_caret_column(column);
+}
+
+fn compile_godot_chunk(state: &mut LuauState, chunk: &str) -> LuaResult<LuaFunction> {
+    let env = state.create_env_from_global()?;
+    state.compile(GODOT_CHUNK, chunk, env)
+}
+
+/// Runs `func` on the next deferred cycle of `state`, in a thread with the given identity.
+fn defer_with_identity(
+    state: &mut LuauState,
+    func: LuaFunction,
+    identity: ThreadIdentityType,
+) -> LuaResult<()> {
+    let lua = unsafe { (&raw const *state.get_lua()).as_ref().unwrap_unchecked() };
+    let thr = unsafe { borrowck_ignore_mut(state) }
+        .get_task_scheduler_mut()
+        .defer_func(lua, func, (), Synchronized)?;
+    state.set_thread_identity(
+        thr,
+        ThreadIdentity {
+            security_identity: identity,
+            script: None,
+            sandbox: None,
+        },
+    );
+    Ok(())
 }
 
 #[godot_api]
 impl RblxVMNode {
+    /// Emitted when a script location gets clicked in the console, like the ones of an error's traceback.
+    #[signal]
+    fn script_location_clicked(script: GString, line: i64);
+
+    #[func]
+    fn on_console_meta_clicked(&mut self, meta: Variant) {
+        let meta = meta.to_string();
+        let Some((script, line)) = meta.rsplit_once(':') else {
+            return;
+        };
+        if let Ok(line) = line.parse::<i64>() {
+            self.base_mut().emit_signal(
+                "script_location_clicked",
+                &[GString::from(script).to_variant(), line.to_variant()],
+            );
+        }
+    }
+    #[func]
+    fn on_log_message(&mut self, message: GString, message_type: i64) {
+        let Some(message_type) = message_type_from_index(message_type) else {
+            return;
+        };
+        if let Some(text) = self.console.push_message(message.to_string(), message_type) {
+            self.base()
+                .get_node_as::<RichTextLabel>(LOG_WINDOW)
+                .append_text(&text);
+        }
+    }
+    #[func]
+    fn on_log_cleared(&mut self) {
+        self.console.clear_messages();
+        self.refresh_console_output();
+    }
+    #[func]
+    fn on_console_filter_toggled(&mut self, _pressed: bool) {
+        self.refresh_console_output();
+    }
+    #[func]
+    fn on_console_search_changed(&mut self, _text: GString) {
+        self.refresh_console_output();
+    }
+    #[func]
+    fn on_command_bar_input(&mut self, event: Gd<InputEvent>) {
+        let Ok(key) = event.try_cast::<InputEventKey>() else {
+            return;
+        };
+        let mut command_bar = self.base().get_node_as::<CodeEdit>(COMMAND_BAR);
+        // While the completion popup is open, the keys go to it.
+        if !key.is_pressed()
+            || key.is_command_or_control_pressed()
+            || command_bar.get_code_completion_selected_index() >= 0
+        {
+            return;
+        }
+        match key.get_keycode() {
+            Key::ENTER | Key::KP_ENTER => {
+                let source = command_bar.get_text().to_string();
+                if key.is_shift_pressed() || is_incomplete(&source) {
+                    command_bar.insert_text_at_caret("\n");
+                } else {
+                    command_bar.clear();
+                    self.run_command(source);
+                }
+            }
+            Key::UP if command_bar.get_caret_line() == 0 => {
+                let current = command_bar.get_text().to_string();
+                if let Some(entry) = self.console.history_previous(current) {
+                    set_command_bar_text(&mut command_bar, entry);
+                }
+            }
+            Key::DOWN if command_bar.get_caret_line() == command_bar.get_line_count() - 1 => {
+                if let Some(entry) = self.console.history_next() {
+                    set_command_bar_text(&mut command_bar, entry);
+                }
+            }
+            _ => return,
+        }
+        command_bar.accept_event();
+    }
+    #[func]
+    fn on_command_bar_completion_requested(&mut self) {
+        let Some(vm) = self.vm.as_ref() else {
+            return;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return;
+        };
+        let mut command_bar = self.base().get_node_as::<CodeEdit>(COMMAND_BAR);
+        let line: String = command_bar
+            .get_line(command_bar.get_caret_line())
+            .to_string()
+            .chars()
+            .take(command_bar.get_caret_column() as usize)
+            .collect();
+        let lua = write.get_main_state().get_lua().clone();
+        // Indexing instances goes through the VM, which can't stay locked meanwhile.
+        drop(write);
+        for (name, kind) in complete(&lua, &line) {
+            command_bar.add_code_completion_option(kind, name.as_str(), name.as_str());
+        }
+        command_bar.update_code_completion_options(false);
+    }
+    /// Creates the VM with the [member startup_flags]. Returns [constant ERR_ALREADY_IN_USE] if it's already running.
+    #[func]
+    fn start(&mut self) -> Error {
+        if self.vm.is_some() {
+            godot_error!("RblxVMNode: RblxVM already running");
+            return Error::ERR_ALREADY_IN_USE;
+        }
+        self.vm = Some(RblxVM::new(Some(self.startup_flags_table())));
+        self.attach_vm();
+        Error::OK
+    }
+    /// Shuts the VM down. The functions bound with [code]game:BindToClose[/code] get up to [code]CloseTimeout[/code] seconds to return, then [code]game.Close[/code] fires, every thread gets cancelled and the VM is dropped along with its Luau states.
+    /// [b]Note:[/b] This blocks until the shutdown is done, and can't be called while the VM is running code, use [method Object.call_deferred] from there.
+    #[func]
+    fn stop(&mut self) -> Error {
+        let Some(vm) = self.vm.take() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        self.remove_performance_monitors();
+        let result = match vm.write() {
+            Ok(write) => {
+                let timeout = write.flags().get_float(FastFlag::CloseTimeout);
+                match GlobalTaskScheduler::close(write, timeout) {
+                    Ok(()) => Error::OK,
+                    Err(err) => {
+                        godot_error!("RblxVMNode: error while shutting down: {}", err);
+                        Error::FAILED
+                    }
+                }
+            }
+            Err(_) => {
+                godot_error!("RblxVMNode: failed to acquire write lock on RblxVM");
+                Error::ERR_CANT_ACQUIRE_RESOURCE
+            }
+        };
+        drop(vm);
+        result
+    }
+    /// Stops the VM if it's running, then starts a new one from the [member startup_flags].
+    #[func]
+    fn reset(&mut self) -> Error {
+        if self.vm.is_some() {
+            // Errors are already reported, the VM is gone either way.
+            let _ = self.stop();
+        }
+        self.start()
+    }
+    /// Returns [code]true[/code] while the VM exists, between [method start] and [method stop].
+    #[func]
+    fn is_started(&self) -> bool {
+        self.vm.is_some()
+    }
+    #[constant]
+    const RUN_STATE_EDIT: i64 = 0;
+    #[constant]
+    const RUN_STATE_RUNNING: i64 = 1;
+    #[constant]
+    const RUN_STATE_PAUSED: i64 = 2;
+
+    /// Starts, pauses or stops the simulation with one of the [code]RUN_STATE_*[/code] constants, starting with the next frame.
+    /// Unless it's running, frames are skipped and the scheduler clock stands still. Luau plugins can do the same through [code]RunService:Run()[/code], [code]Pause()[/code] and [code]Stop()[/code].
+    #[func]
+    fn set_run_state(&self, state: i64) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let run_state = match state {
+            Self::RUN_STATE_EDIT => RunState::Edit,
+            Self::RUN_STATE_RUNNING => RunState::Running,
+            Self::RUN_STATE_PAUSED => RunState::Paused,
+            _ => {
+                godot_error!("RblxVMNode: invalid run state {}", state);
+                return Error::ERR_INVALID_PARAMETER;
+            }
+        };
+        match vm.write() {
+            Ok(mut write) => {
+                write.set_run_state(run_state);
+                Error::OK
+            }
+            Err(_) => {
+                godot_error!("RblxVMNode: failed to acquire write lock on RblxVM");
+                Error::ERR_CANT_ACQUIRE_RESOURCE
+            }
+        }
+    }
+    /// Returns one of the [code]RUN_STATE_*[/code] constants, or [code]-1[/code] if the VM isn't started.
+    #[func]
+    fn get_run_state(&self) -> i64 {
+        let Some(vm) = self.vm.as_ref() else {
+            return -1;
+        };
+        let Ok(read) = vm
+            .read()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire read lock on RblxVM"))
+        else {
+            return -1;
+        };
+        match read.get_run_state() {
+            RunState::Edit => Self::RUN_STATE_EDIT,
+            RunState::Running => Self::RUN_STATE_RUNNING,
+            RunState::Paused => Self::RUN_STATE_PAUSED,
+        }
+    }
+    /// Runs the next [param frames] frames of a paused simulation, the scheduler clock moving by the delta of each.
+    /// Returns [constant ERR_UNAVAILABLE] if the simulation isn't paused.
+    #[func]
+    fn step(&self, frames: i64) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return Error::ERR_CANT_ACQUIRE_RESOURCE;
+        };
+        if write.step(frames.clamp(0, u32::MAX as i64) as u32) {
+            Error::OK
+        } else {
+            godot_error!("RblxVMNode: the simulation isn't paused");
+            Error::ERR_UNAVAILABLE
+        }
+    }
+    /// Speeds the simulation up or slows it down, [code]1.0[/code] being real time. Frame deltas and the scheduler clock are both scaled.
+    #[func]
+    fn set_time_scale(&self, time_scale: f64) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        if !time_scale.is_finite() || time_scale < 0.0 {
+            godot_error!("RblxVMNode: invalid time scale {}", time_scale);
+            return Error::ERR_INVALID_PARAMETER;
+        }
+        match vm.write() {
+            Ok(mut write) => {
+                write.set_time_scale(time_scale);
+                Error::OK
+            }
+            Err(_) => {
+                godot_error!("RblxVMNode: failed to acquire write lock on RblxVM");
+                Error::ERR_CANT_ACQUIRE_RESOURCE
+            }
+        }
+    }
+    #[func]
+    fn get_time_scale(&self) -> f64 {
+        let Some(vm) = self.vm.as_ref() else {
+            return 1.0;
+        };
+        vm.read().map(|x| x.get_time_scale()).unwrap_or(1.0)
+    }
+    /// Makes the scheduler clock only move by the delta of each frame, starting at [param time] seconds. Dates seen by scripts start at [param time] since the Unix epoch as well.
+    /// Waiting for 5 seconds then takes frames adding up to 5 seconds, however long they really take. Along with [method frame_step], this makes tests reproducible.
+    #[func]
+    fn use_manual_clock(&self, time: f64) -> Error {
+        self.set_clock_mode(ClockMode::Manual, Some(time))
+    }
+    /// Makes the scheduler clock follow the real one again, carrying on from its current time.
+    #[func]
+    fn use_real_time_clock(&self) -> Error {
+        self.set_clock_mode(ClockMode::RealTime, None)
+    }
+    /// Returns the time of the scheduler clock, which is what [code]os.clock()[/code] returns to scripts.
+    #[func]
+    fn get_clock(&self) -> f64 {
+        let Some(vm) = self.vm.as_ref() else {
+            return 0.0;
+        };
+        vm.read().map(|x| x.get_clock()).unwrap_or(0.0)
+    }
+    /// Runs a frame of [param delta] seconds right away, on top of the ones run while processing.
+    /// With processing disabled and the manual clock, the VM only advances through this, in lockstep with the caller.
+    #[func]
+    fn frame_step(&mut self, delta: f64) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let Ok(write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return Error::ERR_CANT_ACQUIRE_RESOURCE;
+        };
+        match GlobalTaskScheduler::frame_step(write, delta) {
+            Ok(()) => Error::OK,
+            Err(err) => {
+                godot_error!("RblxVMNode: frame step failed: {}", err);
+                Error::FAILED
+            }
+        }
+    }
     /// Sets a fast flag to a new value asynchronously, returns an error if the flag name is invalid.
     /// [b]Note:[/b] If provided an invalid type, it will panic inside the task scheduler.
     #[func]
@@ -285,34 +817,395 @@ impl RblxVMNode {
         })()
         .unwrap_or_else(|e| e)
     }
+    /// Returns the memory used by every Luau state of the VM, in bytes.
+    #[func]
+    fn get_memory_usage(&self) -> i64 {
+        self.vm
+            .as_ref()
+            .map(|vm| {
+                vm.read()
+                    .inspect_err(|_| {
+                        godot_error!("RblxVMNode: failed to acquire read lock on RblxVM")
+                    })
+                    .map(|read| read.get_total_memory_usage() as i64)
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    }
+    /// Returns a dictionary mapping the full name of every script to the memory attributed to it, in bytes.
+    #[func]
+    fn get_script_memory_usage(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        if let Some(vm) = self.vm.as_ref() {
+            let Ok(read) = vm
+                .read()
+                .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire read lock on RblxVM"))
+            else {
+                return dict;
+            };
+            for (script, bytes) in read.get_script_memory_usage() {
+                if let Some(name) = script.upgrade().and_then(|x| x.get_full_name().ok()) {
+                    let _ = dict.insert(name, bytes as i64);
+                }
+            }
+        }
+        dict
+    }
+    /// Returns the current value of one of the VM's [Performance] custom monitors, by its index.
+    #[func]
+    fn get_performance_monitor(&self, index: i64) -> f64 {
+        let Some(vm) = self.vm.as_ref() else {
+            return 0.0;
+        };
+        let Ok(read) = vm
+            .read()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire read lock on RblxVM"))
+        else {
+            return 0.0;
+        };
+        let stats = read.get_frame_stats();
+        usize::try_from(index)
+            .ok()
+            .and_then(|x| FrameStats::MONITORS.get(x))
+            .map(|(_, get)| get(&stats))
+            .unwrap_or(0.0)
+    }
+    /// Starts sampling the call stacks of every Luau state, [param frequency] times per second.
+    #[func]
+    fn start_profiler(&self, frequency: f64) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        match vm.read() {
+            Ok(read) => {
+                read.start_profiler(None, frequency);
+                Error::OK
+            }
+            Err(_) => {
+                godot_error!("RblxVMNode: failed to acquire read lock on RblxVM");
+                Error::ERR_CANT_ACQUIRE_RESOURCE
+            }
+        }
+    }
+    /// Stops the profiler. Returns a dictionary holding the samples as collapsed stacks under [code]collapsed[/code], and as a Chrome trace under [code]chrome_trace[/code].
+    #[func]
+    fn stop_profiler(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return dict;
+        };
+        let Ok(read) = vm
+            .read()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire read lock on RblxVM"))
+        else {
+            return dict;
+        };
+        let profile = read.stop_profiler(None);
+        let _ = dict.insert("collapsed", profile.to_collapsed_stacks());
+        let _ = dict.insert("chrome_trace", profile.to_chrome_trace());
+        dict
+    }
+    /// Returns the scripts that requested native code, keyed by their full name. Each holds [code]"*"[/code] when the whole script did, otherwise the [code]@native[/code] functions as [code]name:line[/code].
+    /// This lists what was requested: functions the code generator couldn't compile aren't reported by Luau and still show up here, running interpreted.
+    #[func]
+    fn get_native_report(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return dict;
+        };
+        let Ok(read) = vm
+            .read()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire read lock on RblxVM"))
+        else {
+            return dict;
+        };
+        for chunk in read.get_native_chunks() {
+            let functions: PackedStringArray = match chunk.functions {
+                Some(x) => x
+                    .iter()
+                    .map(|(line, name)| GString::from(format!("{}:{}", name, line)))
+                    .collect(),
+                None => [GString::from("*")].into_iter().collect(),
+            };
+            let _ = dict.insert(chunk_display_name(&chunk.chunk_name), functions);
+        }
+        dict
+    }
+    /// Starts the debug adapter server on [param port], [code]0[/code] picks any free port. Returns the port it listens on, or [code]-1[/code] if it couldn't start.
+    /// Editors such as VS Code can then attach to [code]127.0.0.1[/code] on that port. Breakpoints only work in scripts compiled with the [code]DebugMode[/code] fast flag set.
+    #[func]
+    fn start_debugger(&self, port: i64) -> i64 {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return -1;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return -1;
+        };
+        match write.start_debugger(port.clamp(0, u16::MAX as i64) as u16) {
+            Ok(port) => port as i64,
+            Err(err) => {
+                godot_error!("RblxVMNode: failed to start the debugger: {}", err);
+                -1
+            }
+        }
+    }
+    /// Stops the debug adapter server, disconnecting the client and clearing every breakpoint.
+    #[func]
+    fn stop_debugger(&self) {
+        if let Some(vm) = self.vm.as_ref() {
+            match vm.write() {
+                Ok(mut write) => write.stop_debugger(),
+                Err(_) => godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"),
+            }
+        }
+    }
+    /// Loads the Rojo project at [param path] into the DataModel, either a [code].project.json[/code] file or a directory holding [code]default.project.json[/code].
+    /// The scripts it holds start on the next frame. Problems with single files or properties are written to the output as warnings.
+    #[func]
+    fn load_project(&self, path: GString) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return Error::ERR_CANT_ACQUIRE_RESOURCE;
+        };
+        let lua = write.get_main_state().get_lua().clone();
+        let game: ManagedInstance = write.get_game_instance().cast_from_sized().unwrap();
+        // Building instances goes through the VM, which can't stay locked meanwhile.
+        // The global lock still has to be pushed, so the new instances pick this VM's.
+        write.push_global_lock_atomic();
+        drop(write);
+        let path = ProjectSettings::singleton()
+            .globalize_path(&path)
+            .to_string();
+        let result = project::load_project(&lua, Path::new(&path), &game);
+        unsafe { vm.access().as_ref().unwrap().pop_global_lock_atomic() };
+        match result {
+            Ok(()) => Error::OK,
+            Err(err) => {
+                godot_error!("RblxVMNode: failed to load {}: {}", path, err);
+                Error::ERR_PARSE_ERROR
+            }
+        }
+    }
+    /// Checks the source of every script in the DataModel without running it, writing the problems to the output as warnings. Only syntax errors are found, scripts are not type checked nor linted.
+    /// Returns how many were found, or [code]-1[/code] if the scripts couldn't be walked, so CI can fail on anything but [code]0[/code].
+    #[func]
+    fn analyze_scripts(&self) -> i64 {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return -1;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return -1;
+        };
+        let lua = write.get_main_state().get_lua().clone();
+        let game: ManagedInstance = write.get_game_instance().cast_from_sized().unwrap();
+        match project::analyze_scripts(&game) {
+            Ok(diagnostics) => {
+                let log_service = write.get_log_service();
+                for diagnostic in diagnostics.iter() {
+                    log_service.log_warn(&lua, diagnostic.to_output_line());
+                }
+                diagnostics.len() as i64
+            }
+            Err(err) => {
+                godot_error!("RblxVMNode: failed to analyze scripts: {}", err);
+                -1
+            }
+        }
+    }
+    /// Writes Luau type definitions for the classes the VM implements to [param path], in the format luau-lsp reads, so editors can type check scripts. Members are declared without their signatures, taking and returning [code]any[/code].
+    #[func]
+    fn write_type_definitions(&self, path: GString) -> Error {
+        let Some(mut file) = FileAccess::open(&path, ModeFlags::WRITE) else {
+            godot_error!(
+                "RblxVMNode: failed to open {}: {:?}",
+                path,
+                FileAccess::get_open_error()
+            );
+            return FileAccess::get_open_error();
+        };
+        file.store_string(&project::generate_type_definitions());
+        Error::OK
+    }
+    /// Keeps the scripts under [param target] in sync with the files in [param directory], laid out the way Rojo does it.
+    /// [param target] is the full name of an instance, such as [code]Workspace.Scripts[/code]. Scripts restart when their file changes, and so do the scripts that required a changed ModuleScript.
+    #[func]
+    fn watch_sources(&self, directory: GString, target: GString) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return Error::ERR_CANT_ACQUIRE_RESOURCE;
+        };
+        let root = ProjectSettings::singleton()
+            .globalize_path(&directory)
+            .to_string();
+        if !Path::new(&root).is_dir() {
+            godot_error!("RblxVMNode: {} is not a directory", root);
+            return Error::ERR_FILE_NOT_FOUND;
+        }
+        let game: ManagedInstance = write.get_game_instance().cast_from_sized().unwrap();
+        let path: Vec<String> = target.to_string().split('.').map(String::from).collect();
+        match find_path(&game, &path) {
+            Ok(Some(target)) => {
+                write.watch_sources(PathBuf::from(root), &target);
+                Error::OK
+            }
+            _ => {
+                godot_error!("RblxVMNode: no instance named {}", target);
+                Error::ERR_DOES_NOT_EXIST
+            }
+        }
+    }
+    /// Stops every source watcher started with [method watch_sources].
+    #[func]
+    fn stop_watching_sources(&self) {
+        if let Some(vm) = self.vm.as_ref() {
+            match vm.write() {
+                Ok(mut write) => write.stop_watching_sources(),
+                Err(_) => godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"),
+            }
+        }
+    }
+    /// Lets [code]require[/code] load the Luau files under [param directory], through [code].luaurc[/code] aliases and relative paths between files.
+    /// An empty [param directory] turns the filesystem module loader off again.
+    #[func]
+    fn set_module_root(&self, directory: GString) -> Error {
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return Error::ERR_CANT_ACQUIRE_RESOURCE;
+        };
+        let root = (!directory.is_empty()).then(|| {
+            PathBuf::from(
+                ProjectSettings::singleton()
+                    .globalize_path(&directory)
+                    .to_string(),
+            )
+        });
+        match write.set_module_root(root) {
+            Ok(()) => Error::OK,
+            Err(err) => {
+                godot_error!(
+                    "RblxVMNode: cannot use {} as the module root: {}",
+                    directory,
+                    err
+                );
+                Error::ERR_FILE_NOT_FOUND
+            }
+        }
+    }
     /// Pushes Lua code to the task scheduler and runs it on the next deferred cycle.
+    /// Returns [constant ERR_PARSE_ERROR] if the code doesn't compile, the error is written to the output.
     #[func]
     fn push_code(&mut self, chunk: GString) -> Error {
-        self.vm.as_mut().map(|vm| {
-            let mut write = vm
-                .write()
-                .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
-                .map_err(|_| Error::ERR_CANT_ACQUIRE_RESOURCE)
-                .unwrap();
-            let state = write.get_main_state();
-            let env = state.create_env_from_global().unwrap();
-            let func = state
-                .compile_jit("<godot>", chunk.to_string().as_str(), env)
-                .unwrap();
-            let lua = unsafe { (&raw const *state.get_lua()).as_ref().unwrap_unchecked() };
-            let thr = unsafe { borrowck_ignore_mut(state) }
-                .get_task_scheduler_mut()
-                .defer_func(lua, func, (), Synchronized)
-                .inspect_err(|_| godot_error!("RblxVMNode: failed to defer on task scheduler"))
-                .unwrap();
-            state.set_thread_identity(
-                thr,
-                ThreadIdentity {
-                    security_identity: ThreadIdentityType::UserInit,
-                    script: None,
-                },
+        let Some(vm) = self.vm.as_ref() else {
+            godot_error!("RblxVMNode: RblxVM not initialized");
+            return Error::ERR_UNCONFIGURED;
+        };
+        let Ok(mut write) = vm
+            .write()
+            .inspect_err(|_| godot_error!("RblxVMNode: failed to acquire write lock on RblxVM"))
+        else {
+            return Error::ERR_CANT_ACQUIRE_RESOURCE;
+        };
+        let state = write.get_main_state();
+        let func = match compile_godot_chunk(state, &chunk.to_string()) {
+            Ok(x) => x,
+            Err(err) => {
+                let lua = state.get_lua().clone();
+                write.get_log_service().log_err(&lua, err.to_string());
+                return Error::ERR_PARSE_ERROR;
+            }
+        };
+        match defer_with_identity(state, func, ThreadIdentityType::UserInit) {
+            Ok(()) => Error::OK,
+            Err(_) => {
+                godot_error!("RblxVMNode: failed to defer on task scheduler");
+                Error::FAILED
+            }
+        }
+    }
+    /// Returns the DataModel of the VM, the entry point to inspect it from Godot.
+    /// Returns [code]null[/code] if the VM isn't initialized.
+    #[func]
+    fn get_game(&self) -> Option<Gd<RblxInstance>> {
+        let read = self.vm.as_ref()?.read().ok()?;
+        let game: ManagedInstance = read.get_game_instance().cast_from_sized().unwrap();
+        Some(RblxInstance::new_gd(&game, self.base().instance_id()))
+    }
+    /// Runs Lua code right away and returns what it returned: [code]null[/code] for nothing, the value for a single one and an [Array] for several.
+    /// Tables become an [Array] or a [Dictionary], Vector3 and CFrame become [Vector3] and [Transform3D], and Instances a [RblxInstance].
+    /// The code can't yield, use [method run_async] for that. If it fails to compile, errors or yields, a [RblxError] is returned instead.
+    #[func]
+    fn eval(&mut self, code: GString) -> Variant {
+        let vm_id = self.base().instance_id();
+        match self.call_now(|state| {
+            Ok((
+                compile_godot_chunk(state, &code.to_string())?,
+                LuaMultiValue::new(),
+            ))
+        }) {
+            Ok(values) => results_to_variant(&values, vm_id),
+            Err(err) => RblxError::new_gd(err.to_string()).to_variant(),
+        }
+    }
+    /// Pushes Lua code to the task scheduler like [method push_code]. The returned [RblxAsyncResult] completes once the code finishes,
+    /// with the values it returned converted like [method eval] does, or with a [RblxError] if it failed:
+    /// [codeblock]
+    /// var result = await vm.run_async("task.wait(1) return workspace.Name").completed
+    /// [/codeblock]
+    #[func]
+    fn run_async(&mut self, code: GString) -> Gd<RblxAsyncResult> {
+        let result = RblxAsyncResult::new_gd();
+        let vm_id = self.base().instance_id();
+        let Some(vm) = self.vm.as_ref() else {
+            RblxAsyncResult::complete(
+                &result,
+                RblxError::new_gd("RblxVM not initialized").to_variant(),
             );
-        });
-        Error::OK
+            return result;
+        };
+        let Ok(mut write) = vm.write() else {
+            RblxAsyncResult::complete(
+                &result,
+                RblxError::new_gd("failed to acquire write lock on RblxVM").to_variant(),
+            );
+            return result;
+        };
+        let state = write.get_main_state();
+        let lua = state.get_lua().clone();
+        if let Err(err) = compile_godot_chunk(state, &code.to_string())
+            .and_then(|func| complete_with_result(&lua, GODOT_CHUNK, func, &result, vm_id))
+            .and_then(|func| defer_with_identity(state, func, ThreadIdentityType::UserInit))
+        {
+            RblxAsyncResult::complete(&result, RblxError::new_gd(err.to_string()).to_variant());
+        }
+        result
     }
 }
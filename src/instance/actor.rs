@@ -93,13 +93,6 @@ impl IObject for Actor {
             .unwrap()
     }
 
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "Object" | "Instance" | "PVInstance" | "Model" | "Actor" => true,
-            _ => false,
-        }
-    }
-
     fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
         self.get_instance_component().changed.clone()
     }
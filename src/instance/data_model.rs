@@ -9,7 +9,10 @@ use crate::core::{
 use crate::core::{FastFlags, InstanceCreationMetadata};
 use crate::userdata::{ManagedRBXScriptSignal, RBXScriptSignal};
 
-use super::{IServiceProvider, LogService, RunService, ServiceProviderComponent, Workspace};
+use super::{
+    GodotBridge, IServiceProvider, LogService, RunService, ScriptContext, ScriptProfilerService,
+    ServiceProviderComponent, Stats, Workspace,
+};
 
 #[derive(Debug)]
 pub struct DataModelComponent {
@@ -18,6 +21,9 @@ pub struct DataModelComponent {
 
     pub(crate) run_service: Option<Irc<RunService>>,
     pub(crate) log_service: Option<Irc<LogService>>,
+    pub(crate) stats: Option<Irc<Stats>>,
+    pub(crate) script_context: Option<Irc<ScriptContext>>,
+    pub(crate) godot_bridge: Option<Irc<GodotBridge>>,
 
     pub graphics_quality_change_request: ManagedRBXScriptSignal,
     pub loaded: ManagedRBXScriptSignal,
@@ -72,16 +78,6 @@ impl IObject for DataModel {
             .unwrap()
     }
 
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "DataModel" => true,
-            "ServiceProvider" => true,
-            "Instance" => true,
-            "Object" => true,
-            _ => false,
-        }
-    }
-
     fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
         self.get_instance_component().changed.clone()
     }
@@ -152,6 +148,11 @@ impl DataModel {
         DynInstance::lock_parent(&*game);
         game
     }
+    /// Fires ServiceProvider.Close, the last thing scripts see before the VM shuts down.
+    pub(crate) fn fire_close(&self, lua: &Lua) -> LuaResult<()> {
+        let signal = self.get_service_provider_component().close.clone();
+        signal.write().fire(lua, ())
+    }
     fn add_service(&self, lua: &Lua, service: ManagedInstance) -> LuaResult<()> {
         let self_ptr = self.get_instance_component().get_instance_pointer();
         service.set_parent(lua, Some(self_ptr))?;
@@ -167,6 +168,17 @@ impl DataModel {
         let serv = RunService::new();
         self.add_service(lua, serv.clone().cast_from_sized::<DynInstance>().unwrap())?;
         self.data_model.write().unwrap().run_service = Some(serv);
+        let serv = Stats::new();
+        self.add_service(lua, serv.clone().cast_from_sized::<DynInstance>().unwrap())?;
+        self.data_model.write().unwrap().stats = Some(serv);
+        let serv = ScriptContext::new();
+        self.add_service(lua, serv.clone().cast_from_sized::<DynInstance>().unwrap())?;
+        self.data_model.write().unwrap().script_context = Some(serv);
+        let serv = GodotBridge::new();
+        self.add_service(lua, serv.clone().cast_from_sized::<DynInstance>().unwrap())?;
+        self.data_model.write().unwrap().godot_bridge = Some(serv);
+        let serv = ScriptProfilerService::new();
+        self.add_service(lua, serv.cast_from_sized::<DynInstance>().unwrap())?;
         let serv = Workspace::new();
         self.add_service(lua, serv.clone().cast_from_sized::<DynInstance>().unwrap())?;
         self.data_model.write().unwrap().workspace = Some(serv);
@@ -283,6 +295,9 @@ impl IInstanceComponent for DataModelComponent {
             workspace: None,
             run_service: None,
             log_service: None,
+            stats: None,
+            script_context: None,
+            godot_bridge: None,
             bind_close: RBXScriptSignal::new(metadata),
             graphics_quality_change_request: RBXScriptSignal::new(metadata),
             loaded: RBXScriptSignal::new(metadata),
@@ -306,6 +321,11 @@ impl dyn IDataModel {
         read.bind_close.write().once(lua, func, Synchronized)?;
         Ok(())
     }
+    /// Runs the functions bound with BindToClose, returning their threads so shutdown can wait for them.
+    pub(crate) fn fire_bind_to_close(&self, lua: &Lua) -> LuaResult<Vec<LuaThread>> {
+        let signal = self.get_data_model_component().bind_close.clone();
+        signal.write().spawn_all(lua, ())
+    }
     pub fn is_loaded(&self) -> bool {
         self.get_data_model_component().is_loaded
     }
@@ -323,4 +343,19 @@ impl dyn IDataModel {
     pub fn get_workspace(&self) -> Irc<Workspace> {
         self.get_data_model_component().workspace.clone().unwrap()
     }
+    pub fn get_stats(&self) -> Irc<Stats> {
+        self.get_data_model_component().stats.clone().unwrap()
+    }
+    pub fn get_script_context(&self) -> Irc<ScriptContext> {
+        self.get_data_model_component()
+            .script_context
+            .clone()
+            .unwrap()
+    }
+    pub fn get_godot_bridge(&self) -> Irc<GodotBridge> {
+        self.get_data_model_component()
+            .godot_bridge
+            .clone()
+            .unwrap()
+    }
 }
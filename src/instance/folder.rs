@@ -0,0 +1,5 @@
+use rblx_godot_derive::instance;
+
+#[instance]
+#[derive(Debug)]
+pub struct Folder {}
@@ -0,0 +1,219 @@
+use std::{cell::RefCell, collections::HashMap, mem::take, rc::Rc};
+
+use godot::{global::Error, prelude::*};
+use r2g_mlua::{prelude::*, Variadic};
+use rblx_godot_derive::{instance, methods};
+
+use crate::core::{DynInstance, Irc, ParallelDispatch::Synchronized};
+use crate::godot_vm_bindings::{lua_to_variant, variant_to_lua};
+use crate::userdata::{ManagedRBXScriptSignal, RBXScriptConnection, RBXScriptSignal};
+
+type PendingEmissions = Rc<RefCell<Vec<(ManagedRBXScriptSignal, Vec<Variant>)>>>;
+
+/// Gives plugins access to the Godot scene tree. Paths are relative to the RblxVMNode running the VM, absolute
+/// paths aren't accepted. Values get converted the same way they are for RblxVMNode.eval, and Nodes become their
+/// path relative to the RblxVMNode.
+#[instance(no_clone, custom_new)]
+#[method(
+    name = "HasNode",
+    func = fn has_node(&self, path: String) -> bool,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "GetProperty",
+    func = fn get_property(&self, lua: &Lua, path: String, property: String) -> LuaResult<LuaValue>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "SetProperty",
+    func = fn set_property(&self, path: String, property: String, value: LuaValue) -> LuaResult<()>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "CallMethod",
+    func = fn call_method(&self, lua: &Lua, path: String, method: String, args: Variadic<LuaValue>) -> LuaResult<LuaValue>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "Connect",
+    func = fn connect(&self, lua: &Lua, path: String, signal: String, func: LuaFunction) -> LuaResult<RBXScriptConnection>,
+    security_context = PluginSecurity
+)]
+#[derive(Debug)]
+pub struct GodotBridge {
+    /// The RblxVMNode running the VM.
+    root: Option<InstanceId>,
+    /// One signal per connected Godot signal, keyed by the node and the signal name, along with the callable
+    /// connected to it on the Godot side.
+    signals: HashMap<(InstanceId, String), (ManagedRBXScriptSignal, Callable)>,
+    /// Emissions of the connected Godot signals, fired on the next frame.
+    pending: PendingEmissions,
+}
+
+impl GodotBridge {
+    pub fn new() -> Irc<GodotBridge> {
+        let inst = Self::construct();
+        DynInstance::set_name(&*inst, "GodotBridge".into()).unwrap();
+        inst
+    }
+}
+
+#[methods]
+impl GodotBridge {
+    fn has_node(&self, path: String) -> bool {
+        self.get_node(&path).is_ok()
+    }
+    fn get_property(&self, lua: &Lua, path: String, property: String) -> LuaResult<LuaValue> {
+        let node = self.get_other_node(&path)?;
+        let root = self.get_root_node()?;
+        variant_to_lua(lua, &node_to_path(&root, node.get(property.as_str())))
+    }
+    fn set_property(&self, path: String, property: String, value: LuaValue) -> LuaResult<()> {
+        let mut node = self.get_other_node(&path)?;
+        node.set(property.as_str(), &lua_to_variant(&value, self.get_root()?));
+        Ok(())
+    }
+    fn call_method(
+        &self,
+        lua: &Lua,
+        path: String,
+        method: String,
+        args: Variadic<LuaValue>,
+    ) -> LuaResult<LuaValue> {
+        let mut node = self.get_other_node(&path)?;
+        if !node.has_method(method.as_str()) {
+            return Err(LuaError::RuntimeError(format!(
+                "{} is not a method of {}",
+                method, path
+            )));
+        }
+        let root = self.get_root()?;
+        let args: VariantArray = args.iter().map(|x| lua_to_variant(x, root)).collect();
+        let result = node.callv(method.as_str(), &args);
+        variant_to_lua(lua, &node_to_path(&self.get_root_node()?, result))
+    }
+    fn connect(
+        &self,
+        lua: &Lua,
+        path: String,
+        signal: String,
+        func: LuaFunction,
+    ) -> LuaResult<RBXScriptConnection> {
+        let mut node = self.get_node(&path)?;
+        if !node.has_signal(signal.as_str()) {
+            return Err(LuaError::RuntimeError(format!(
+                "{} is not a signal of {}",
+                signal, path
+            )));
+        }
+        let key = (node.instance_id(), signal.clone());
+        let existing = self
+            .get_godot_bridge_component()
+            .signals
+            .get(&key)
+            .map(|x| x.0.clone());
+        if let Some(sig) = existing {
+            return sig.write().connect(lua, func, Synchronized);
+        }
+        let sig =
+            RBXScriptSignal::new_internal(self.get_instance_component_mut().get_signal_list());
+        let pending = self.get_godot_bridge_component().pending.clone();
+        let emitted = sig.clone();
+        // Godot emits signals whenever it likes, even while the VM runs, so emissions only get queued here.
+        let callable = Callable::from_local_fn("GodotBridge::Connect", move |args| {
+            pending
+                .borrow_mut()
+                .push((emitted.clone(), args.iter().map(|x| (*x).clone()).collect()));
+            Ok(Variant::nil())
+        });
+        if node.connect(signal.as_str(), &callable) != Error::OK {
+            return Err(LuaError::RuntimeError(format!(
+                "failed to connect to {} of {}",
+                signal, path
+            )));
+        }
+        self.get_godot_bridge_component_mut()
+            .signals
+            .insert(key, (sig.clone(), callable));
+        sig.write().connect(lua, func, Synchronized)
+    }
+}
+
+impl dyn IGodotBridge {
+    /// Sets the RblxVMNode the paths are relative to.
+    pub fn set_root(&self, root: InstanceId) {
+        self.get_godot_bridge_component_mut().root = Some(root);
+    }
+    /// Disconnects the Godot signals connected through `Connect` and forgets the root, the bridge doesn't reach
+    /// the scene tree anymore.
+    pub fn detach(&self) {
+        let mut write = self.get_godot_bridge_component_mut();
+        write.root = None;
+        for ((node, signal), (_, callable)) in write.signals.drain() {
+            if let Ok(mut node) = Gd::<Object>::try_from_instance_id(node) {
+                if node.is_connected(signal.as_str(), &callable) {
+                    node.disconnect(signal.as_str(), &callable);
+                }
+            }
+        }
+        write.pending.borrow_mut().clear();
+    }
+    fn get_root(&self) -> LuaResult<InstanceId> {
+        self.get_godot_bridge_component().root.ok_or_else(|| {
+            LuaError::RuntimeError("GodotBridge is not attached to a RblxVMNode".into())
+        })
+    }
+    fn get_root_node(&self) -> LuaResult<Gd<Node>> {
+        Gd::<Node>::try_from_instance_id(self.get_root()?).map_err(|_| {
+            LuaError::RuntimeError("the RblxVMNode of GodotBridge no longer exists".into())
+        })
+    }
+    fn get_node(&self, path: &str) -> LuaResult<Gd<Node>> {
+        if path.starts_with('/') {
+            return Err(LuaError::RuntimeError(format!(
+                "{} is absolute, paths are relative to the RblxVMNode",
+                path
+            )));
+        }
+        self.get_root_node()?
+            .get_node_or_null(path)
+            .ok_or_else(|| LuaError::RuntimeError(format!("node {} not found", path)))
+    }
+    /// Like `get_node`, but refuses the RblxVMNode itself. It's busy running the VM while the bridge is used, so
+    /// its properties and methods can't be reached.
+    fn get_other_node(&self, path: &str) -> LuaResult<Gd<Node>> {
+        let node = self.get_node(path)?;
+        if node.instance_id() == self.get_root()? {
+            return Err(LuaError::RuntimeError(format!(
+                "{} is the RblxVMNode running the VM, it can't be used through GodotBridge",
+                path
+            )));
+        }
+        Ok(node)
+    }
+    /// Fires the signals connected through `Connect` with what Godot emitted since the last frame.
+    /// Arguments that can't be converted become nil.
+    pub fn fire_pending_signals(&self, lua: &Lua) -> LuaResult<()> {
+        let pending = take(&mut *self.get_godot_bridge_component().pending.borrow_mut());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let root = self.get_root_node()?;
+        for (signal, args) in pending {
+            let args: LuaMultiValue = args
+                .iter()
+                .map(|x| variant_to_lua(lua, &node_to_path(&root, x.clone())).unwrap_or(LuaNil))
+                .collect();
+            signal.write().fire(lua, args)?;
+        }
+        Ok(())
+    }
+}
+
+/// Nodes can't cross over to Luau, their path from `root` does so it can be handed back to the bridge.
+fn node_to_path(root: &Gd<Node>, variant: Variant) -> Variant {
+    match variant.try_to::<Gd<Node>>() {
+        Ok(node) if node.is_inside_tree() => root.get_path_to(&node).to_variant(),
+        _ => variant,
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use r2g_mlua::prelude::*;
+use rblx_godot_derive::{instance, methods};
+
+/// A row of a LocalizationTable. `values` holds the translations, keyed by locale id.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct LocalizationEntry {
+    pub key: String,
+    pub source: String,
+    pub context: String,
+    pub example: String,
+    pub values: BTreeMap<String, String>,
+}
+
+impl LocalizationEntry {
+    fn into_lua(self, lua: &Lua) -> LuaResult<LuaTable> {
+        let table = lua.create_table()?;
+        table.raw_set("Key", self.key)?;
+        table.raw_set("Source", self.source)?;
+        table.raw_set("Context", self.context)?;
+        table.raw_set("Example", self.example)?;
+        table.raw_set("Values", lua.create_table_from(self.values)?)?;
+        Ok(table)
+    }
+    fn from_lua(table: LuaTable) -> LuaResult<LocalizationEntry> {
+        let field = |name: &str| -> LuaResult<String> {
+            Ok(table.get::<Option<String>>(name)?.unwrap_or_default())
+        };
+        let mut values = BTreeMap::new();
+        if let Some(x) = table.get::<Option<LuaTable>>("Values")? {
+            for pair in x.pairs::<String, String>() {
+                let (locale, text) = pair?;
+                values.insert(locale, text);
+            }
+        }
+        Ok(LocalizationEntry {
+            key: field("Key")?,
+            source: field("Source")?,
+            context: field("Context")?,
+            example: field("Example")?,
+            values,
+        })
+    }
+}
+
+#[instance]
+#[method(
+    name = "GetEntries",
+    func = fn get_entries(&self, lua: &Lua) -> LuaResult<LuaTable>
+)]
+#[method(
+    name = "SetEntries",
+    func = fn set_entries(&self, entries: LuaTable) -> LuaResult<()>
+)]
+#[derive(Debug)]
+pub struct LocalizationTable {
+    #[property(name = "SourceLocaleId", default = "en-us".into())]
+    source_locale_id: String,
+    entries: Vec<LocalizationEntry>,
+}
+
+#[methods]
+impl LocalizationTable {
+    fn get_entries(&self, lua: &Lua) -> LuaResult<LuaTable> {
+        let entries = self.get_localization_table_component().entries.clone();
+        lua.create_sequence_from(
+            entries
+                .into_iter()
+                .map(|x| x.into_lua(lua))
+                .collect::<LuaResult<Vec<_>>>()?,
+        )
+    }
+    fn set_entries(&self, entries: LuaTable) -> LuaResult<()> {
+        let entries = entries
+            .sequence_values::<LuaTable>()
+            .map(|x| x.and_then(LocalizationEntry::from_lua))
+            .collect::<LuaResult<Vec<_>>>()?;
+        self.set_localization_entries(entries);
+        Ok(())
+    }
+}
+
+impl dyn ILocalizationTable {
+    pub fn set_localization_entries(&self, entries: Vec<LocalizationEntry>) {
+        self.get_localization_table_component_mut().entries = entries;
+    }
+}
@@ -1,21 +1,24 @@
 use godot::{global::print_rich, meta::ToGodot};
 use r2g_mlua::{ffi::lua_clock, prelude::*};
+use rblx_godot_derive::{instance, methods};
 use std::fmt::Debug;
 
 use crate::{
-    core::{
-        lua_macros::{lua_getter, lua_invalid_argument},
-        DynInstance, IInstance, IInstanceComponent, IObject, InheritanceBase,
-        InheritanceTableBuilder, InstanceComponent, InstanceCreationMetadata, Irc, ManagedInstance,
-        RwLock, RwLockReadGuard, RwLockWriteGuard,
-    },
-    userdata::{enums::MessageType, ManagedRBXScriptSignal, RBXScriptSignal},
+    core::{Irc, ScriptError},
+    userdata::{enums::MessageType, ManagedRBXScriptSignal},
 };
 
-struct LogServiceComponent {
+#[instance(no_clone, custom_new)]
+#[method(name = "ClearOutput", func = fn clear_output(&self))]
+#[method(
+    name = "GetLogHistory",
+    func = fn get_log_history(&self, lua: &Lua) -> LuaResult<LuaTable>
+)]
+pub struct LogService {
     logs: Vec<(String, MessageType, f64)>,
     hooks: Vec<Box<dyn Fn(Option<(String, MessageType, f64)>)>>,
 
+    #[event(name = "MessageOut")]
     pub message_out: ManagedRBXScriptSignal,
 }
 
@@ -27,151 +30,32 @@ impl Debug for LogServiceComponent {
     }
 }
 
-#[derive(Debug)]
-pub struct LogService {
-    instance: RwLock<InstanceComponent>,
-    log_service: RwLock<LogServiceComponent>,
-}
-
-impl IInstanceComponent for LogServiceComponent {
-    fn lua_get(
-        self: &mut RwLockReadGuard<'_, Self>,
-        _ptr: &DynInstance,
-        lua: &Lua,
-        key: &String,
-    ) -> Option<LuaResult<LuaValue>> {
-        match key.as_str() {
-            "ClearOutput" => lua_getter!(function_opt, lua, |_, obj: ManagedInstance| {
-                obj.cast_from_unsized::<LogService>()
-                    .map_err(|_| lua_invalid_argument!("LogService::ClearOutput", 1, self cast Instance to LogService))
-                    .map(|x| x.clear_logs())
-            }),
-            "GetLogHistory" => lua_getter!(function_opt, lua, |lua, obj: ManagedInstance| {
-                obj.cast_from_unsized::<LogService>()
-                    .map_err(|_| lua_invalid_argument!("LogService::GetLogHistory", 1, self cast Instance to LogService))
-                    .map(|x| {
-                        let write = x.log_service.read().unwrap();
-                        let tbl = lua.create_table().unwrap();
-                        for (msg, msg_type, timestamp) in write.logs.iter() {
-                            let entry = lua.create_table().unwrap();
-                            entry.push(msg.clone()).unwrap();
-                            entry.push(*msg_type).unwrap();
-                            entry.push(*timestamp).unwrap();
-                            tbl.push(entry).unwrap();
-                        }
-                        tbl
-                    })
-            }),
-            "MessageOut" => Some(lua_getter!(clone, lua, self.message_out)),
-            _ => None,
-        }
-    }
-
-    fn lua_set(
-        self: &mut RwLockWriteGuard<'_, Self>,
-        _ptr: &DynInstance,
-        _lua: &Lua,
-        _key: &String,
-        _value: &LuaValue,
-    ) -> Option<LuaResult<()>> {
-        None
-    }
-
-    fn clone(
-        self: &RwLockReadGuard<'_, Self>,
-        _: &Lua,
-        _: &InstanceCreationMetadata,
-    ) -> LuaResult<Self> {
-        Err(LuaError::RuntimeError(
-            "Cannot clone LogServiceComponent".into(),
-        ))
-    }
-
-    fn new(metadata: &InstanceCreationMetadata) -> Self {
-        LogServiceComponent {
-            logs: Vec::new(),
-            hooks: Vec::new(),
-            message_out: RBXScriptSignal::new(metadata),
+#[methods]
+impl LogService {
+    fn clear_output(&self) {
+        let mut write = self.get_log_service_component_mut();
+        write.logs.clear();
+        for hook in write.hooks.iter() {
+            hook(None);
         }
     }
-}
-
-impl InheritanceBase for LogService {
-    fn inheritance_table(&self) -> crate::core::InheritanceTable {
-        InheritanceTableBuilder::new()
-            .insert_type::<LogService, dyn IObject>(|x| x, |x| x)
-            .insert_type::<LogService, dyn IInstance>(|x| x, |x| x)
-            .insert_type::<LogService, LogService>(|x| x, |x| x)
-            .output()
-    }
-}
-
-impl IObject for LogService {
-    fn lua_get(&self, lua: &Lua, name: String) -> LuaResult<LuaValue> {
-        self.log_service
-            .read()
-            .unwrap()
-            .lua_get(self, lua, &name)
-            .unwrap_or_else(|| self.instance.read().unwrap().lua_get(lua, &name))
-    }
-
-    fn get_class_name(&self) -> &'static str {
-        "LogService"
-    }
-
-    fn get_property_changed_signal(&self, property: String) -> ManagedRBXScriptSignal {
-        self.instance
-            .read()
-            .unwrap()
-            .get_property_changed_signal(property)
-            .unwrap()
-    }
-
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "Object" | "Instance" | "LogService" => true,
-            _ => false,
+    fn get_log_history(&self, lua: &Lua) -> LuaResult<LuaTable> {
+        let read = self.get_log_service_component();
+        let tbl = lua.create_table()?;
+        for (msg, msg_type, timestamp) in read.logs.iter() {
+            let entry = lua.create_table()?;
+            entry.push(msg.clone())?;
+            entry.push(*msg_type)?;
+            entry.push(*timestamp)?;
+            tbl.push(entry)?;
         }
-    }
-
-    fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
-        self.instance.read().unwrap().changed.clone()
-    }
-}
-
-impl IInstance for LogService {
-    fn get_instance_component(&self) -> RwLockReadGuard<InstanceComponent> {
-        self.instance.read().unwrap()
-    }
-
-    fn get_instance_component_mut(&self) -> RwLockWriteGuard<InstanceComponent> {
-        self.instance.write().unwrap()
-    }
-
-    fn lua_set(&self, lua: &Lua, name: String, val: LuaValue) -> LuaResult<()> {
-        self.log_service
-            .write()
-            .unwrap()
-            .lua_set(self, lua, &name, &val)
-            .unwrap_or_else(|| self.instance.write().unwrap().lua_set(lua, &name, val))
-    }
-
-    fn clone_instance(&self, _lua: &Lua) -> LuaResult<ManagedInstance> {
-        Err(LuaError::RuntimeError("Cannot clone LogService".into()))
+        Ok(tbl)
     }
 }
 
 impl LogService {
     pub fn new() -> Irc<LogService> {
-        let inst = Irc::new_cyclic(|x| {
-            let mut metadata = InstanceCreationMetadata::new("LogService", x.cast_to_instance());
-            let mut l = LogService {
-                instance: RwLock::new_with_flag_auto(InstanceComponent::new(&mut metadata)),
-                log_service: RwLock::new_with_flag_auto(LogServiceComponent::new(&mut metadata)),
-            };
-            DynInstance::submit_metadata(&mut l, metadata);
-            l
-        });
+        let inst = Self::construct();
         inst.add_hook(|x| {
             if let Some((msg, msg_type, _timestamp)) = x {
                 match msg_type {
@@ -191,15 +75,16 @@ impl LogService {
         inst
     }
     pub fn clear_logs(&self) {
-        let mut write = self.log_service.write().unwrap();
-        write.logs.clear();
-        for hook in write.hooks.iter() {
-            hook(None);
-        }
+        (self as &dyn ILogService).clear_output()
     }
     pub fn add_hook(&self, hook: impl Fn(Option<(String, MessageType, f64)>) + 'static) {
         self.log_service.write().unwrap().hooks.push(Box::new(hook));
     }
+    /// Drops every hook, the one printing to the Godot output included. Tests run without an engine to print to.
+    #[cfg(test)]
+    pub(crate) fn clear_hooks(&self) {
+        self.log_service.write().unwrap().hooks.clear();
+    }
     pub fn log_message(&self, lua: &Lua, message: String) {
         let mut write = self.log_service.write().unwrap();
         let timestamp = unsafe { lua_clock() };
@@ -276,10 +161,51 @@ impl LogService {
             .fire(lua, (message, MessageType::MessageError))
             .unwrap();
     }
+    /// Logs an uncaught script error followed by its traceback, like Studio's output.
+    pub fn log_script_error(&self, lua: &Lua, error: &ScriptError) {
+        self.log_err(lua, error.message.clone());
+        self.log_info(lua, String::from("Stack Begin"));
+        for frame in error.traceback.iter() {
+            self.log_info(lua, frame.to_output_line());
+        }
+        self.log_info(lua, String::from("Stack End"));
+    }
+}
+
+/// Finds the script location a message starts with, either `Workspace.Script:3:` from an error message
+/// or `Script 'Workspace.Script', Line 3` from a traceback.
+/// Returns how many bytes it spans, the script's full name and the line.
+fn script_location(msg: &str) -> Option<(usize, &str, u32)> {
+    let (name, rest, separator) = match msg.strip_prefix("Script '") {
+        Some(rest) => {
+            let (name, rest) = rest.split_once("', Line ")?;
+            (name, rest, "")
+        }
+        None => {
+            let (name, rest) = msg.split_once(':')?;
+            (name, rest, ":")
+        }
+    };
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if name.is_empty() || name.contains(['[', ']']) || !rest[digits..].starts_with(separator) {
+        return None;
+    }
+    let line = rest[..digits].parse().ok()?;
+    Some((msg.len() - rest.len() + digits, name, line))
 }
 
 pub(crate) fn escape_bbcode_and_format(msg: String, msg_type: MessageType) -> String {
-    let msg = msg.replace("[", "[lb]");
+    // Script locations become links, the console tells which one got clicked through their meta.
+    let msg = match script_location(&msg) {
+        Some((len, name, line)) => format!(
+            "[url={}:{}]{}[/url]{}",
+            name,
+            line,
+            msg[..len].replace("[", "[lb]"),
+            msg[len..].replace("[", "[lb]")
+        ),
+        None => msg.replace("[", "[lb]"),
+    };
     match msg_type {
         MessageType::MessageOutput => msg,
         MessageType::MessageInfo => format!("[color=blue]{}[/color]", msg),
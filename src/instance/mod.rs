@@ -1,21 +1,141 @@
 mod actor;
 mod data_model;
+mod folder;
+mod godot_bridge;
+mod localization_table;
 mod log_service;
 mod model;
 mod pvinstance;
 mod run_service;
 mod script;
+mod script_context;
+mod script_profiler_service;
 mod service_provider;
+mod stats;
+mod string_value;
 mod workspace;
 
 pub use actor::{Actor, ManagedActor, WeakManagedActor};
 pub use data_model::{DataModel, IDataModel};
-pub use log_service::LogService;
+pub use folder::{Folder, FolderComponent, IFolder};
+pub use godot_bridge::{GodotBridge, GodotBridgeComponent, IGodotBridge};
+pub use localization_table::{
+    ILocalizationTable, LocalizationEntry, LocalizationTable, LocalizationTableComponent,
+};
+pub use log_service::{ILogService, LogService, LogServiceComponent};
 pub use model::{IModel, Model, ModelComponent};
 pub use pvinstance::{IPVInstance, PVInstanceComponent};
-pub use run_service::RunService;
+pub use run_service::{IRunService, RunService, RunServiceComponent};
 pub use script::{IBaseScript, IModuleScript, LocalScript, ModuleScript, Script};
+pub use script_context::{IScriptContext, ScriptContext, ScriptContextComponent};
+pub use script_profiler_service::{
+    IScriptProfilerService, ScriptProfilerService, ScriptProfilerServiceComponent,
+};
 pub use service_provider::{IServiceProvider, ServiceProviderComponent};
-pub use workspace::Workspace;
+pub use stats::{IStats, Stats, StatsComponent};
+pub use string_value::{IStringValue, StringValue, StringValueComponent};
+pub use workspace::{IWorkspace, Workspace, WorkspaceComponent};
 
 pub(crate) use log_service::escape_bbcode_and_format;
+
+use crate::core::{get_state, ClassDescriptor, ClassRegistry, ClassTags, PropertyDescriptor};
+use crate::userdata::enums::ScriptCapability;
+
+pub(crate) fn register_builtin_classes(registry: &mut ClassRegistry) {
+    registry.register(ClassDescriptor::new("Object", None).tags(ClassTags::NOT_CREATABLE));
+    registry.register(
+        ClassDescriptor::new("Instance", Some("Object"))
+            .tags(ClassTags::NOT_CREATABLE)
+            .property(PropertyDescriptor::new("Archivable", "bool"))
+            .property(PropertyDescriptor::new("ClassName", "string").readonly())
+            .property(PropertyDescriptor::new("Name", "string"))
+            .property(PropertyDescriptor::new("Parent", "Instance")),
+    );
+    registry.register(
+        ClassDescriptor::new("PVInstance", Some("Instance")).tags(ClassTags::NOT_CREATABLE),
+    );
+    registry.register(Model::class_descriptor().constructor(|_| Ok(Model::new())));
+    registry.register(Folder::class_descriptor().constructor(|_| Ok(Folder::new())));
+    registry.register(
+        ClassDescriptor::new("ValueBase", Some("Instance")).tags(ClassTags::NOT_CREATABLE),
+    );
+    registry.register(StringValue::class_descriptor().constructor(|_| Ok(StringValue::new())));
+    registry.register(
+        LocalizationTable::class_descriptor().constructor(|_| Ok(LocalizationTable::new())),
+    );
+    registry.register(
+        ClassDescriptor::new("Actor", Some("Model"))
+            .constructor(|lua| Ok(Actor::new(get_state(lua).get_vm_mut()))),
+    );
+    registry
+        .register(ClassDescriptor::new("WorldRoot", Some("Model")).tags(ClassTags::NOT_CREATABLE));
+    registry.register(
+        Workspace::class_descriptor().tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE),
+    );
+    registry.register(
+        ClassDescriptor::new("ServiceProvider", Some("Instance")).tags(ClassTags::NOT_CREATABLE),
+    );
+    registry.register(
+        ClassDescriptor::new("DataModel", Some("ServiceProvider"))
+            .tags(ClassTags::NOT_CREATABLE)
+            .property(PropertyDescriptor::new("CreatorId", "int64").readonly())
+            .property(PropertyDescriptor::new("CreatorType", "CreatorType").readonly())
+            .property(PropertyDescriptor::new("GameId", "int64").readonly())
+            .property(PropertyDescriptor::new("JobId", "string").readonly())
+            .property(PropertyDescriptor::new("PlaceId", "int64").readonly())
+            .property(PropertyDescriptor::new("PlaceVersion", "int").readonly())
+            .property(PropertyDescriptor::new("PrivateServerId", "string").readonly())
+            .property(PropertyDescriptor::new("PrivateServerOwnerId", "int64").readonly())
+            .property(PropertyDescriptor::new("Workspace", "Workspace").readonly()),
+    );
+    registry.register(
+        LogService::class_descriptor()
+            .tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE | ClassTags::NOT_REPLICATED),
+    );
+    registry.register(
+        RunService::class_descriptor()
+            .tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE | ClassTags::NOT_REPLICATED),
+    );
+    registry
+        .register(Stats::class_descriptor().tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE));
+    // These see every script of the VM or reach into the Godot scene, sandboxed scripts don't get them unless
+    // they're also trusted with their own capabilities.
+    registry.register(
+        ScriptProfilerService::class_descriptor()
+            .tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE)
+            .capability(ScriptCapability::CapabilityControl),
+    );
+    registry.register(
+        GodotBridge::class_descriptor()
+            .tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE | ClassTags::NOT_REPLICATED)
+            .capability(ScriptCapability::CapabilityControl),
+    );
+    registry.register(
+        ScriptContext::class_descriptor()
+            .tags(ClassTags::NOT_CREATABLE | ClassTags::SERVICE | ClassTags::NOT_REPLICATED)
+            .capability(ScriptCapability::CapabilityControl),
+    );
+    registry.register(
+        ClassDescriptor::new("LuaSourceContainer", Some("Instance")).tags(ClassTags::NOT_CREATABLE),
+    );
+    registry.register(
+        ClassDescriptor::new("BaseScript", Some("LuaSourceContainer"))
+            .tags(ClassTags::NOT_CREATABLE)
+            .property(PropertyDescriptor::new("Disabled", "bool"))
+            .property(PropertyDescriptor::new("Enabled", "bool"))
+            .property(PropertyDescriptor::new("RunContext", "RunContext")),
+    );
+    registry.register(
+        ClassDescriptor::new("Script", Some("BaseScript"))
+            .constructor(|_| Ok(Script::new()))
+            .property(PropertyDescriptor::new("Source", "ProtectedString")),
+    );
+    registry.register(
+        ClassDescriptor::new("LocalScript", Some("Script")).constructor(|_| Ok(LocalScript::new())),
+    );
+    registry.register(
+        ClassDescriptor::new("ModuleScript", Some("LuaSourceContainer"))
+            .constructor(|_| Ok(ModuleScript::new()))
+            .property(PropertyDescriptor::new("Source", "ProtectedString")),
+    );
+}
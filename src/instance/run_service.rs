@@ -1,158 +1,103 @@
 use r2g_mlua::prelude::*;
+use rblx_godot_derive::{instance, methods};
 
-use crate::core::lua_macros::lua_getter;
 use crate::core::{
-    get_state, get_state_with_rwlock, DynInstance, FastFlag, IInstance, IInstanceComponent,
-    IObject, InheritanceBase, InheritanceTable, InheritanceTableBuilder, InstanceComponent,
-    InstanceCreationMetadata, Irc, LuauState, ManagedInstance, RwLock, RwLockReadGuard,
-    RwLockWriteGuard, Trc,
+    get_state, get_state_with_rwlock, DynInstance, FastFlag, Irc, LuauState, RunState, Trc,
 };
-use crate::userdata::{ManagedRBXScriptSignal, RBXScriptSignal};
+use crate::userdata::ManagedRBXScriptSignal;
 
+#[instance(no_clone, custom_new)]
+#[method(name = "IsClient", func = fn is_client(&self, lua: &Lua) -> bool)]
+#[method(name = "IsServer", func = fn is_server(&self, lua: &Lua) -> bool)]
+#[method(name = "IsStudio", func = fn is_studio(&self, lua: &Lua) -> bool)]
+#[method(name = "IsEdit", func = fn is_edit(&self, lua: &Lua) -> bool)]
+#[method(name = "IsRunning", func = fn is_running(&self, lua: &Lua) -> bool)]
+#[method(name = "IsRunMode", func = fn is_run_mode(&self, lua: &Lua) -> bool)]
+#[method(
+    name = "Run",
+    func = fn run(&self, lua: &Lua) -> LuaResult<()>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "Stop",
+    func = fn stop(&self, lua: &Lua) -> LuaResult<()>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "Pause",
+    func = fn pause(&self, lua: &Lua) -> LuaResult<()>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "BindToRenderStep",
+    func = fn bind_to_render_step(&self, lua: &Lua, name: String, priority: f64, func: LuaFunction) -> LuaResult<()>
+)]
+#[method(
+    name = "UnbindFromRenderStep",
+    func = fn unbind_from_render_step(&self, name: String) -> LuaResult<()>
+)]
 #[derive(Debug)]
 pub struct RunService {
-    instance_component: RwLock<InstanceComponent>,
-
-    render_steps: RwLock<Vec<(String, f64, (Trc<LuauState>, LuaFunction))>>,
+    render_steps: Vec<(String, f64, (Trc<LuauState>, LuaFunction))>,
 
+    #[event(name = "Heartbeat")]
     pub heart_beat: ManagedRBXScriptSignal,
+    #[event(name = "PostSimulation")]
     pub post_simulation: ManagedRBXScriptSignal,
+    #[event(name = "PreAnimation")]
     pub pre_animation: ManagedRBXScriptSignal,
+    #[event(name = "PreRender")]
     pub pre_render: ManagedRBXScriptSignal,
+    #[event(name = "PreSimulation")]
     pub pre_simulation: ManagedRBXScriptSignal,
+    #[event(name = "RenderStepped")]
     pub render_stepped: ManagedRBXScriptSignal,
+    #[event(name = "Stepped")]
     pub stepped: ManagedRBXScriptSignal,
 }
 
-impl InheritanceBase for RunService {
-    fn inheritance_table(&self) -> InheritanceTable {
-        InheritanceTableBuilder::new()
-            .insert_type::<RunService, dyn IObject>(|x| x, |x| x)
-            .insert_type::<RunService, DynInstance>(|x| x, |x| x)
-            .output()
+impl RunService {
+    pub fn new() -> Irc<RunService> {
+        let inst = Self::construct();
+        DynInstance::set_name(&*inst, "Run Service".into()).unwrap();
+        inst
     }
 }
 
-impl IObject for RunService {
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "RunService" | "Instance" | "Object" => true,
-            _ => false,
-        }
-    }
-    fn lua_get(&self, lua: &Lua, name: String) -> LuaResult<LuaValue> {
-        match name.as_str() {
-            "Heartbeat" => lua_getter!(clone, lua, self.heart_beat),
-            "PostSimulation" => lua_getter!(clone, lua, self.post_simulation),
-            "PreAnimation" => lua_getter!(clone, lua, self.pre_animation),
-            "PreRender" => lua_getter!(clone, lua, self.pre_render),
-            "PreSimulation" => lua_getter!(clone, lua, self.pre_simulation),
-            "RenderStepped" => lua_getter!(clone, lua, self.render_stepped),
-            "Stepped" => lua_getter!(clone, lua, self.stepped),
-            "IsClient" => lua_getter!(function, lua, |lua, _: ManagedInstance| Ok(get_state(lua)
-                .flags()
-                .get_bool(FastFlag::IsClient))),
-            "IsServer" => lua_getter!(function, lua, |lua, _: ManagedInstance| Ok(!get_state(lua)
-                .flags()
-                .get_bool(FastFlag::IsClient))),
-            "IsStudio" => lua_getter!(function, lua, |lua, _: ManagedInstance| Ok(get_state(lua)
-                .flags()
-                .get_bool(FastFlag::IsStudio))),
-            "IsEdit" => lua_getter!(function, lua, |_, _: ManagedInstance| Ok(false)),
-            "IsRunning" => lua_getter!(function, lua, |_, _: ManagedInstance| Ok(true)),
-            "IsRunMode" => lua_getter!(function, lua, |_, _: ManagedInstance| Ok(true)),
-            "Run" => lua_getter!(function, lua, |_, _: ManagedInstance| Err::<(), _>(
-                LuaError::RuntimeError(
-                    "expected security level PluginSecurity, but got None".into()
-                )
-            )),
-            "Stop" => lua_getter!(function, lua, |_, _: ManagedInstance| Err::<(), _>(
-                LuaError::RuntimeError(
-                    "expected security level PluginSecurity, but got None".into()
-                )
-            )),
-            "Pause" => lua_getter!(function, lua, |_, _: ManagedInstance| Err::<(), _>(
-                LuaError::RuntimeError(
-                    "expected security level PluginSecurity, but got None".into()
-                )
-            )),
-            "BindToRenderStep" => lua_getter!(function, lua, |lua,
-                                                              (this, name, priority, func): (
-                ManagedInstance,
-                String,
-                f64,
-                LuaFunction
-            )| this
-                .cast_from_unsized::<RunService>()
-                .map_err(|_| LuaError::RuntimeError("expected RunService, got Instance".into()))?
-                .bind_to_render_step(lua, name, priority, func)),
-            "UnbindFromRenderStep" => lua_getter!(function, lua, |_,
-                                                                  (this, name): (
-                ManagedInstance,
-                String
-            )| this
-                .cast_from_unsized::<RunService>()
-                .map_err(|_| LuaError::RuntimeError("expected RunService, got Instance".into()))?
-                .unbind_from_render_step(name)),
-            _ => self.instance_component.read().unwrap().lua_get(lua, &name),
-        }
+#[methods]
+impl RunService {
+    fn is_client(&self, lua: &Lua) -> bool {
+        get_state(lua).flags().get_bool(FastFlag::IsClient)
     }
-    fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
-        self.instance_component.read().unwrap().changed.clone()
+    fn is_server(&self, lua: &Lua) -> bool {
+        !get_state(lua).flags().get_bool(FastFlag::IsClient)
     }
-    fn get_property_changed_signal(&self, property: String) -> ManagedRBXScriptSignal {
-        self.instance_component
-            .read()
-            .unwrap()
-            .get_property_changed_signal(property)
-            .unwrap()
+    fn is_studio(&self, lua: &Lua) -> bool {
+        get_state(lua).flags().get_bool(FastFlag::IsStudio)
     }
-    fn get_class_name(&self) -> &'static str {
-        "RunService"
+    fn is_edit(&self, lua: &Lua) -> bool {
+        get_state(lua).get_vm().get_run_state() == RunState::Edit
     }
-}
-
-impl IInstance for RunService {
-    fn get_instance_component(&self) -> RwLockReadGuard<'_, InstanceComponent> {
-        self.instance_component.read().unwrap()
+    fn is_running(&self, lua: &Lua) -> bool {
+        get_state(lua).get_vm().get_run_state() == RunState::Running
     }
-    fn get_instance_component_mut(&self) -> RwLockWriteGuard<'_, InstanceComponent> {
-        self.instance_component.write().unwrap()
+    fn is_run_mode(&self, lua: &Lua) -> bool {
+        get_state(lua).get_vm().get_run_state() != RunState::Edit
     }
-    fn lua_set(&self, lua: &Lua, name: String, val: LuaValue) -> LuaResult<()> {
-        self.instance_component
-            .write()
-            .unwrap()
-            .lua_set(lua, &name, val)
+    // These take effect on the next frame. Once the simulation is paused or stopped no thread runs, so only
+    // the embedder can start it again.
+    fn run(&self, lua: &Lua) -> LuaResult<()> {
+        get_state(lua).get_vm_mut().set_run_state(RunState::Running);
+        Ok(())
     }
-    fn clone_instance(&self, _: &Lua) -> LuaResult<ManagedInstance> {
-        Err(LuaError::RuntimeError("Cannot clone RunService.".into()))
+    fn stop(&self, lua: &Lua) -> LuaResult<()> {
+        get_state(lua).get_vm_mut().set_run_state(RunState::Edit);
+        Ok(())
     }
-}
-
-impl RunService {
-    pub fn new() -> Irc<RunService> {
-        let inst = Irc::new_cyclic(|x| {
-            let metadata = InstanceCreationMetadata::new("RunService", x.cast_to_instance());
-            let mut r = RunService {
-                instance_component: RwLock::new_with_flag_auto(InstanceComponent::new(&metadata)),
-                render_steps: RwLock::new_with_flag_auto(Vec::new()),
-                heart_beat: RBXScriptSignal::new(&metadata),
-                post_simulation: RBXScriptSignal::new(&metadata),
-                pre_animation: RBXScriptSignal::new(&metadata),
-                pre_render: RBXScriptSignal::new(&metadata),
-                pre_simulation: RBXScriptSignal::new(&metadata),
-                render_stepped: RBXScriptSignal::new(&metadata),
-                stepped: RBXScriptSignal::new(&metadata),
-            };
-            DynInstance::submit_metadata(&mut r, metadata);
-            r
-        });
-        DynInstance::set_name(&*inst, "Run Service".into()).unwrap();
-        inst
+    fn pause(&self, lua: &Lua) -> LuaResult<()> {
+        get_state(lua).get_vm_mut().set_run_state(RunState::Paused);
+        Ok(())
     }
-}
-impl RunService {
     pub fn bind_to_render_step(
         &self,
         lua: &Lua,
@@ -160,14 +105,22 @@ impl RunService {
         priority: f64,
         func: LuaFunction,
     ) -> LuaResult<()> {
-        let mut write = self.render_steps.write().unwrap();
-        write.push((name, priority, (get_state_with_rwlock(lua).clone(), func)));
-        write.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        let mut write = self.get_run_service_component_mut();
+        write
+            .render_steps
+            .push((name, priority, (get_state_with_rwlock(lua).clone(), func)));
+        write
+            .render_steps
+            .sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
         Ok(())
     }
     pub fn unbind_from_render_step(&self, name: String) -> LuaResult<()> {
-        let mut write = self.render_steps.write().unwrap();
-        write.retain(|x| x.0 != name);
+        let mut write = self.get_run_service_component_mut();
+        write.render_steps.retain(|x| x.0 != name);
         Ok(())
     }
+    /// Unbinds every render step, releasing the Luau states they were bound from.
+    pub(crate) fn clear_render_steps(&self) {
+        self.get_run_service_component_mut().render_steps.clear();
+    }
 }
@@ -6,13 +6,15 @@ use r2g_mlua::prelude::*;
 use crate::core::lua_macros::{lua_getter, lua_setter};
 use crate::core::ParallelDispatch::Synchronized;
 use crate::core::{
-    borrowck_ignore, borrowck_ignore_mut, get_current_identity, get_state,
-    get_task_scheduler_from_lua, inheritance_cast_to, DynInstance, FastFlag, IInstance,
-    IInstanceComponent, IObject, InheritanceBase, InheritanceTableBuilder, InstanceComponent,
-    InstanceCreationMetadata, Irc, LuauState, ManagedInstance, RwLock, RwLockReadGuard,
-    RwLockWriteGuard, SecurityContext, Trc, WeakManagedInstance,
+    borrowck_ignore, borrowck_ignore_mut, capability_violation, check_cycle,
+    check_security_context, get_current_identity, get_state, get_task_scheduler_from_lua,
+    inheritance_cast_to, run_module, DynInstance, FastFlag, IInstance, IInstanceComponent, IObject,
+    InheritanceBase, InheritanceTableBuilder, InstanceComponent, InstanceCreationMetadata, Irc,
+    LuauState, ManagedInstance, ModuleKey, ModuleResult, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    ScriptSandbox, SecurityContext, ThreadIdentity, ThreadIdentityType, Trc, WeakManagedInstance,
 };
-use crate::userdata::enums::RunContext;
+use crate::project::check_mode_directive;
+use crate::userdata::enums::{RunContext, ScriptCapability};
 use crate::userdata::{ManagedRBXScriptSignal, RBXScriptConnection};
 
 use super::{ManagedActor, WeakManagedActor};
@@ -42,32 +44,30 @@ impl BaseScriptComponent {
         state: &mut LuauState,
         script: &ManagedInstance,
     ) -> LuaResult<LuaFunction> {
-        let debug = state.flags().get_bool(FastFlag::DebugMode);
-
-        let env = state.create_env_from_global()?;
+        if state.flags().get_bool(FastFlag::AnalyzeOnLoad) {
+            // Syntax errors aren't checked here, compiling reports them.
+            if let Some(diagnostic) = check_mode_directive(&script.get_full_name()?, source) {
+                state
+                    .get_log_service()
+                    .log_warn(lua, diagnostic.to_output_line());
+            }
+        }
+        // Modules required by sandboxed code run in the sandbox of the thread requiring them.
+        let env = if ScriptSandbox::of(script).is_some()
+            || get_current_identity(lua).is_some_and(|x| x.sandbox.is_some())
+        {
+            state.create_sandboxed_env()?
+        } else {
+            state.create_env_from_global()?
+        };
         env.set("script", script.clone())?;
         let func: LuaFunction;
         {
-            let f: LuaResult<LuaFunction>;
-            if debug {
-                f = state.compile_debug(
-                    format!("<script at {}>", script.get_full_name()?).as_str(),
-                    source.as_str(),
-                    env,
-                );
-            } else if source.lines().any(|x| x == "--!native") {
-                f = state.compile_jit(
-                    format!("<script at {}>", script.get_full_name()?).as_str(),
-                    source.as_str(),
-                    env,
-                );
-            } else {
-                f = state.compile_release(
-                    format!("<script at {}>", script.get_full_name()?).as_str(),
-                    source.as_str(),
-                    env,
-                );
-            }
+            let f = state.compile(
+                format!("<script at {}>", script.get_full_name()?).as_str(),
+                source.as_str(),
+                env,
+            );
             func = f.inspect_err(|x| match x {
                 LuaError::RuntimeError(err) => state.get_log_service().log_err(
                     lua,
@@ -102,6 +102,19 @@ impl BaseScriptComponent {
             return Ok(());
         }
         let instance = self.self_instance.upgrade().unwrap();
+        let sandbox = ScriptSandbox::of(&instance);
+        if let Some(sandbox) = sandbox.as_ref() {
+            let capability = if get_state(lua).flags().get_bool(FastFlag::IsClient) {
+                ScriptCapability::RunClientScript
+            } else {
+                ScriptCapability::RunServerScript
+            };
+            if !sandbox.capabilities.contains(capability) {
+                // The script just doesn't run, the violation is reported to LogService.
+                capability_violation(lua, capability, &instance.get_full_name()?);
+                return Ok(());
+            }
+        }
         self.actor = instance
             .get_actor()?
             .map(|x| ActorLuauState::Actor(x.cast_from_unsized().unwrap()))
@@ -112,13 +125,19 @@ impl BaseScriptComponent {
         let env = state.create_env_from_global()?;
         env.set("script", instance.clone())?;
         let func = Self::compile_luau(&self.source, lua, &mut state, &instance)?;
-        self.change_scheduled = Some(unsafe {
-            let thread =
-                borrowck_ignore_mut(borrowck_ignore_mut(&mut *state).get_task_scheduler_mut())
-                    .defer_func(borrowck_ignore(lua), func, (), Synchronized)?;
-
-            thread
-        });
+        let thread = unsafe {
+            borrowck_ignore_mut(borrowck_ignore_mut(&mut *state).get_task_scheduler_mut())
+                .defer_func(borrowck_ignore(lua), func, (), Synchronized)?
+        };
+        state.set_thread_identity(
+            thread.clone(),
+            ThreadIdentity {
+                security_identity: ThreadIdentityType::Script,
+                script: Some(self.self_instance.clone()),
+                sandbox,
+            },
+        );
+        self.change_scheduled = Some(thread);
         Ok(())
     }
     pub fn terminate(self: &mut RwLockWriteGuard<'_, Self>, lua: &Lua) -> LuaResult<()> {
@@ -202,6 +221,22 @@ impl BaseScriptComponent {
             }
         }
     }
+    /// Stops the script if it's running, then starts it over from its current source unless it's disabled.
+    pub(crate) fn restart(
+        self: &mut RwLockWriteGuard<'_, Self>,
+        lua: &Lua,
+        implicit_run_context: RunContext,
+    ) -> LuaResult<()> {
+        if self.change_scheduled.is_some() {
+            self.terminate(lua)?;
+            self.change_scheduled = None;
+        }
+        if self.disabled {
+            return Ok(());
+        }
+        self.disabled = true;
+        self.set_disabled(lua, false, implicit_run_context)
+    }
     fn schedule_start_if_not_started(
         self: &mut RwLockWriteGuard<'_, Self>,
         lua: &Lua,
@@ -341,20 +376,10 @@ impl IInstanceComponent for BaseScriptComponent {
                 Some(d)
             }
             "Source" => {
-                if let Some(iden) = get_current_identity(lua) {
-                    if !iden
-                        .security_identity
-                        .get_security_contexts()
-                        .has(SecurityContext::PLUGIN)
-                    {
-                        return Some(Err(LuaError::RuntimeError(
-                            "Source property is protected (PluginSecurity or higher)".into(),
-                        )));
-                    }
-                } else {
-                    return Some(Err(LuaError::RuntimeError(
-                        "Source property is protected (PluginSecurity or higher)".into(),
-                    )));
+                if let Err(err) =
+                    check_security_context(lua, SecurityContext::PLUGIN, "Script.Source")
+                {
+                    return Some(Err(err));
                 }
                 if let Err(err) = self.set_source(lua_setter!(opt_clone, lua, value)) {
                     return Some(Err(err));
@@ -411,7 +436,7 @@ impl IInstanceComponent for BaseScriptComponent {
 }
 
 impl dyn IBaseScript {
-    fn get_source(&self) -> String {
+    pub(crate) fn get_source(&self) -> String {
         self.get_base_script_component().source.clone()
     }
     fn set_source(&self, lua: &Lua, source: String) -> LuaResult<()> {
@@ -427,7 +452,7 @@ impl dyn IBaseScript {
     fn get_disabled(&self) -> bool {
         self.get_base_script_component().disabled
     }
-    fn set_disabled(&self, lua: &Lua, disabled: bool) -> LuaResult<()> {
+    pub(crate) fn set_disabled(&self, lua: &Lua, disabled: bool) -> LuaResult<()> {
         self.get_base_script_component_mut().set_disabled(
             lua,
             disabled,
@@ -458,6 +483,19 @@ impl dyn IBaseScript {
             &lua_getter!(lua, run_context)?,
         )
     }
+    /// Replaces the source of the script and restarts it with the new one.
+    pub(crate) fn reload(&self, lua: &Lua, source: String) -> LuaResult<()> {
+        self.set_source(lua, source)?;
+        self.get_base_script_component_mut()
+            .restart(lua, self.implicit_run_context())
+    }
+    /// Lets go of the Luau state the script runs in, once the VM is shutting down and its threads are cancelled.
+    pub(crate) fn release_state(&self) {
+        let mut write = self.get_base_script_component_mut();
+        write.connections.clear();
+        write.change_scheduled = None;
+        write.actor = ActorLuauState::None;
+    }
 }
 
 //pub struct ScriptComponent {}
@@ -497,13 +535,6 @@ impl IObject for Script {
             .unwrap()
     }
 
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "Object" | "Instance" | "LuaSourceContainer" | "BaseScript" | "Script" => true,
-            _ => false,
-        }
-    }
-
     fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
         self.instance.read().unwrap().changed.clone()
     }
@@ -616,14 +647,6 @@ impl IObject for LocalScript {
             .unwrap()
     }
 
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "Object" | "Instance" | "LuaSourceContainer" | "BaseScript" | "Script"
-            | "LocalScript" => true,
-            _ => false,
-        }
-    }
-
     fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
         self.instance.read().unwrap().changed.clone()
     }
@@ -702,7 +725,9 @@ impl LocalScript {
 #[derive(Debug)]
 pub struct ModuleScriptComponent {
     source: String,
-    already_imported: HashMap<Option<WeakManagedActor>, LuaValue>,
+    already_imported: HashMap<Option<WeakManagedActor>, ModuleResult>,
+    /// The scripts and modules that required this module, so reloading it can reach them.
+    dependents: Vec<WeakManagedInstance>,
     _self_ptr: WeakManagedInstance,
 }
 
@@ -728,20 +753,10 @@ impl IInstanceComponent for ModuleScriptComponent {
     ) -> Option<LuaResult<()>> {
         match key.as_str() {
             "Source" => {
-                if let Some(iden) = get_current_identity(lua) {
-                    if !iden
-                        .security_identity
-                        .get_security_contexts()
-                        .has(SecurityContext::PLUGIN)
-                    {
-                        return Some(Err(LuaError::RuntimeError(
-                            "Source property is protected (PluginSecurity or higher)".into(),
-                        )));
-                    }
-                } else {
-                    return Some(Err(LuaError::RuntimeError(
-                        "Source property is protected (PluginSecurity or higher)".into(),
-                    )));
+                if let Err(err) =
+                    check_security_context(lua, SecurityContext::PLUGIN, "ModuleScript.Source")
+                {
+                    return Some(Err(err));
                 }
                 self.source = lua_setter!(opt_clone, lua, value);
                 self.already_imported.clear();
@@ -764,6 +779,7 @@ impl IInstanceComponent for ModuleScriptComponent {
         Ok(ModuleScriptComponent {
             source: self.source.clone(),
             already_imported: HashMap::new(),
+            dependents: Vec::new(),
             _self_ptr: metadata.get_ptr(),
         })
     }
@@ -772,6 +788,7 @@ impl IInstanceComponent for ModuleScriptComponent {
         ModuleScriptComponent {
             source: String::new(),
             already_imported: HashMap::new(),
+            dependents: Vec::new(),
             _self_ptr: metadata.get_ptr(),
         }
     }
@@ -818,13 +835,6 @@ impl IObject for ModuleScript {
             .unwrap()
     }
 
-    fn is_a(&self, class_name: &String) -> bool {
-        match class_name.as_str() {
-            "Object" | "Instance" | "LuaSourceContainer" | "ModuleScript" => true,
-            _ => false,
-        }
-    }
-
     fn get_changed_signal(&self) -> ManagedRBXScriptSignal {
         self.instance.read().unwrap().changed.clone()
     }
@@ -888,46 +898,70 @@ impl dyn IModuleScript {
             &lua_getter!(lua, source)?,
         )
     }
+    /// Runs the module once per actor, returning what it returned. A module that raised an error keeps
+    /// failing until its source changes, and requiring a module that is still loading further up is an error.
     pub fn require(&self, lua: &Lua, actor: &Option<WeakManagedActor>) -> LuaResult<LuaValue> {
-        let mut component = self.get_module_script_component_mut();
-        let already_imported = &mut component.already_imported;
-        if let Some(x) = already_imported.get(actor) {
-            return Ok(x.clone());
-        }
-        let func = BaseScriptComponent::compile_luau(
-            &component.source,
+        let self_ptr = self.get_module_script_component()._self_ptr.clone();
+        let key = ModuleKey::Instance(self_ptr.clone());
+        check_cycle(lua, &key)?;
+        let source = {
+            let mut component = self.get_module_script_component_mut();
+            // Modules required while another module's body runs depend on that module, not on the script.
+            let dependent = match get_state(lua).get_requiring().last() {
+                Some(ModuleKey::Instance(x)) => Some(x.clone()),
+                Some(ModuleKey::File(_)) => None,
+                None => get_current_identity(lua).and_then(|x| x.script.clone()),
+            };
+            if let Some(dependent) = dependent {
+                if !component.dependents.contains(&dependent) {
+                    component.dependents.push(dependent);
+                }
+            }
+            if let Some(x) = component.already_imported.get(actor) {
+                return x.get();
+            }
+            component.source.clone()
+        };
+        // The component stays unlocked while the body runs, it may well read its own module.
+        let result = BaseScriptComponent::compile_luau(
+            &source,
             lua,
             get_state(lua),
-            &component._self_ptr.upgrade().unwrap(),
-        )?;
-        let thread = lua.create_thread(func)?;
-        let result = thread.resume::<LuaMultiValue>(());
-        if let Err(err) = result {
-            get_state(lua).get_log_service().log_err(
-                lua,
-                format!(
-                    "Error occured while requiring module script {}: {:?}",
-                    component
-                        ._self_ptr
-                        .upgrade()
-                        .unwrap()
-                        .get_full_name()
-                        .unwrap(),
-                    err
-                ),
-            );
-            return Err(err);
-        }
-        let res;
-        {
-            let mut iter = result.unwrap().into_iter();
-            iter.next();
-            res = iter.next().unwrap_or_else(|| LuaValue::Nil);
-        }
-        component
+            &self_ptr.upgrade().unwrap(),
+        )
+        .and_then(|func| run_module(lua, key, func));
+        let cached = match &result {
+            Ok(x) => ModuleResult::Loaded(x.clone()),
+            Err(_) => ModuleResult::Failed,
+        };
+        self.get_module_script_component_mut()
             .already_imported
-            .insert(actor.clone(), res.clone());
-        Ok(res)
+            .insert(actor.clone(), cached);
+        result
+    }
+    /// Replaces the source of the module, and makes everything that required it pick up the new one.
+    pub(crate) fn reload(&self, lua: &Lua, source: String) -> LuaResult<()> {
+        self.set_source(lua, source)?;
+        self.invalidate(lua)
+    }
+    /// Forgets the results of this module and of the modules that required it, restarting the scripts
+    /// that required any of them.
+    pub(crate) fn invalidate(&self, lua: &Lua) -> LuaResult<()> {
+        let dependents = {
+            let mut write = self.get_module_script_component_mut();
+            write.already_imported.clear();
+            take(&mut write.dependents)
+        };
+        for dependent in dependents.iter().filter_map(|x| x.upgrade()) {
+            if let Ok(module) = dependent.clone().cast_from_unsized::<dyn IModuleScript>() {
+                module.invalidate(lua)?;
+            } else if let Ok(script) = dependent.cast_from_unsized::<dyn IBaseScript>() {
+                script
+                    .get_base_script_component_mut()
+                    .restart(lua, script.implicit_run_context())?;
+            }
+        }
+        Ok(())
     }
 }
 
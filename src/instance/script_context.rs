@@ -0,0 +1,43 @@
+use r2g_mlua::prelude::*;
+use rblx_godot_derive::instance;
+
+use crate::core::{get_state, DynInstance, Irc, ScriptError};
+use crate::userdata::ManagedRBXScriptSignal;
+
+#[instance(no_clone, custom_new)]
+#[derive(Debug)]
+pub struct ScriptContext {
+    /// Set while `Error` handlers run, so an error raised by one of them only gets logged.
+    reporting: bool,
+
+    #[event(name = "Error")]
+    pub error: ManagedRBXScriptSignal,
+}
+
+impl ScriptContext {
+    pub fn new() -> Irc<ScriptContext> {
+        let inst = Self::construct();
+        DynInstance::set_name(&*inst, "ScriptContext".into()).unwrap();
+        inst
+    }
+    /// Writes an uncaught error to the output the way Studio does, then fires `Error` with it.
+    pub fn report_error(&self, lua: &Lua, error: ScriptError) -> LuaResult<()> {
+        get_state(lua)
+            .get_vm()
+            .get_log_service()
+            .log_script_error(lua, &error);
+        let mut write = self.get_script_context_component_mut();
+        if write.reporting {
+            return Ok(());
+        }
+        write.reporting = true;
+        let event = write.error.clone();
+        drop(write);
+        let stack_trace = error.stack_trace();
+        let result = event
+            .write()
+            .fire(lua, (error.message, stack_trace, error.script));
+        self.get_script_context_component_mut().reporting = false;
+        result
+    }
+}
@@ -0,0 +1,110 @@
+use r2g_mlua::prelude::*;
+use rblx_godot_derive::{instance, methods};
+
+use crate::core::{
+    get_state, DynInstance, Irc, ManagedInstance, ScriptProfile, DEFAULT_PROFILER_FREQUENCY,
+};
+use crate::userdata::ManagedRBXScriptSignal;
+
+/// There is a single VM, so the client and server variants of each method do the same thing.
+#[instance(no_clone, custom_new)]
+#[method(
+    name = "ClientStart",
+    func = fn client_start(&self, lua: &Lua, player: Option<ManagedInstance>, frequency: Option<f64>),
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "ClientStop",
+    func = fn client_stop(&self, lua: &Lua, player: Option<ManagedInstance>),
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "ClientRequestData",
+    func = fn client_request_data(&self, lua: &Lua, player: Option<ManagedInstance>) -> LuaResult<()>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "ServerStart",
+    func = fn server_start(&self, lua: &Lua, frequency: Option<f64>),
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "ServerStop",
+    func = fn server_stop(&self, lua: &Lua),
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "ServerRequestData",
+    func = fn server_request_data(&self, lua: &Lua) -> LuaResult<()>,
+    security_context = PluginSecurity
+)]
+#[method(
+    name = "GetCollapsedStacks",
+    func = fn get_collapsed_stacks(&self) -> String,
+    security_context = PluginSecurity
+)]
+#[derive(Debug)]
+pub struct ScriptProfilerService {
+    last_profile: Option<ScriptProfile>,
+
+    #[event(name = "OnNewData")]
+    pub on_new_data: ManagedRBXScriptSignal,
+}
+
+impl ScriptProfilerService {
+    pub fn new() -> Irc<ScriptProfilerService> {
+        let inst = Self::construct();
+        DynInstance::set_name(&*inst, "ScriptProfilerService".into()).unwrap();
+        inst
+    }
+}
+
+#[methods]
+impl ScriptProfilerService {
+    fn client_start(&self, lua: &Lua, _player: Option<ManagedInstance>, frequency: Option<f64>) {
+        self.server_start(lua, frequency)
+    }
+    fn client_stop(&self, lua: &Lua, _player: Option<ManagedInstance>) {
+        self.server_stop(lua)
+    }
+    fn client_request_data(&self, lua: &Lua, player: Option<ManagedInstance>) -> LuaResult<()> {
+        self.fire_new_data(lua, player)
+    }
+    fn server_start(&self, lua: &Lua, frequency: Option<f64>) {
+        get_state(lua).get_vm().start_profiler(
+            Some(get_state(lua)),
+            frequency.unwrap_or(DEFAULT_PROFILER_FREQUENCY),
+        );
+    }
+    fn server_stop(&self, lua: &Lua) {
+        let profile = get_state(lua).get_vm().stop_profiler(Some(get_state(lua)));
+        self.get_script_profiler_service_component_mut()
+            .last_profile = Some(profile);
+    }
+    fn server_request_data(&self, lua: &Lua) -> LuaResult<()> {
+        self.fire_new_data(lua, None)
+    }
+    /// Returns the last profile as collapsed stacks, ready for flame graph tools.
+    fn get_collapsed_stacks(&self) -> String {
+        self.get_script_profiler_service_component()
+            .last_profile
+            .as_ref()
+            .map(ScriptProfile::to_collapsed_stacks)
+            .unwrap_or_default()
+    }
+}
+
+impl dyn IScriptProfilerService {
+    /// Fires `OnNewData` with the last profile, in the Chrome trace event format.
+    fn fire_new_data(&self, lua: &Lua, player: Option<ManagedInstance>) -> LuaResult<()> {
+        let read = self.get_script_profiler_service_component();
+        let json = read
+            .last_profile
+            .as_ref()
+            .map(ScriptProfile::to_chrome_trace)
+            .unwrap_or_default();
+        let signal = read.on_new_data.clone();
+        drop(read);
+        signal.write().fire(lua, (player, json))
+    }
+}
@@ -0,0 +1,85 @@
+use r2g_mlua::prelude::*;
+use rblx_godot_derive::{instance, methods};
+
+use crate::core::{get_state, DynInstance, FrameStats, Irc};
+use crate::userdata::enums::DeveloperMemoryTag;
+
+fn frame_stats(lua: &Lua) -> FrameStats {
+    get_state(lua).get_vm().get_frame_stats()
+}
+
+fn get_heartbeat_time_ms(_: &StatsComponent, _: &DynInstance, lua: &Lua) -> LuaResult<f64> {
+    Ok(frame_stats(lua).heartbeat_time * 1000.0)
+}
+fn get_physics_step_time_ms(_: &StatsComponent, _: &DynInstance, lua: &Lua) -> LuaResult<f64> {
+    Ok(frame_stats(lua).physics_step_time * 1000.0)
+}
+fn get_instance_count(_: &StatsComponent, _: &DynInstance, lua: &Lua) -> LuaResult<i64> {
+    Ok(frame_stats(lua).instance_count as i64)
+}
+fn get_memory_usage_mb(_: &StatsComponent, _: &DynInstance, lua: &Lua) -> LuaResult<f64> {
+    Ok(frame_stats(lua).get_memory_usage_mb_for_tag(DeveloperMemoryTag::LuaHeap))
+}
+
+#[instance(no_clone, custom_new)]
+#[method(
+    name = "GetTotalMemoryUsageMb",
+    func = fn get_total_memory_usage_mb(&self, lua: &Lua) -> f64
+)]
+#[method(
+    name = "GetMemoryUsageMbForTag",
+    func = fn get_memory_usage_mb_for_tag(&self, lua: &Lua, tag: DeveloperMemoryTag) -> f64
+)]
+#[derive(Debug)]
+pub struct Stats {
+    #[property(
+        name = "HeartbeatTimeMs",
+        get = get_heartbeat_time_ms,
+        readonly,
+        not_replicated,
+        transparent
+    )]
+    heartbeat_time_ms: f64,
+    #[property(
+        name = "PhysicsStepTimeMs",
+        get = get_physics_step_time_ms,
+        readonly,
+        not_replicated,
+        transparent
+    )]
+    physics_step_time_ms: f64,
+    #[property(
+        name = "InstanceCount",
+        get = get_instance_count,
+        readonly,
+        not_replicated,
+        transparent
+    )]
+    instance_count: i64,
+    #[property(
+        name = "MemoryUsageMb",
+        get = get_memory_usage_mb,
+        readonly,
+        not_replicated,
+        transparent
+    )]
+    memory_usage_mb: f64,
+}
+
+impl Stats {
+    pub fn new() -> Irc<Stats> {
+        let inst = Self::construct();
+        DynInstance::set_name(&*inst, "Stats".into()).unwrap();
+        inst
+    }
+}
+
+#[methods]
+impl Stats {
+    fn get_total_memory_usage_mb(&self, lua: &Lua) -> f64 {
+        frame_stats(lua).get_total_memory_usage_mb()
+    }
+    fn get_memory_usage_mb_for_tag(&self, lua: &Lua, tag: DeveloperMemoryTag) -> f64 {
+        frame_stats(lua).get_memory_usage_mb_for_tag(tag)
+    }
+}
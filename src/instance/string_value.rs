@@ -0,0 +1,8 @@
+use rblx_godot_derive::instance;
+
+#[instance(superclass = "ValueBase")]
+#[derive(Debug)]
+pub struct StringValue {
+    #[property(name = "Value", default)]
+    value: String,
+}
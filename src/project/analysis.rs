@@ -0,0 +1,102 @@
+use r2g_mlua::prelude::*;
+use r2g_mlua::Compiler;
+
+use crate::core::ManagedInstance;
+use crate::instance::{IBaseScript, IModuleScript};
+
+/// A problem found in the source of a script.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// The full name of the script.
+    pub script: String,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Formats the diagnostic the way the output shows it, `Workspace.Script:3: Expected 'end'`.
+    pub fn to_output_line(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}: {}", self.script, line, self.message),
+            None => format!("{}: {}", self.script, self.message),
+        }
+    }
+}
+
+/// Compiles `source` without running it, returning the syntax error it has if any.
+pub fn check_source(script: &str, source: &str) -> Option<Diagnostic> {
+    let Err(LuaError::SyntaxError { message, .. }) = Compiler::new().compile(source) else {
+        return None;
+    };
+    // The compiler formats errors as `line: message`.
+    let (line, message) = message
+        .split_once(": ")
+        .and_then(|(line, message)| Some((line.parse().ok()?, message)))
+        .map_or((None, message.as_str()), |(line, message)| {
+            (Some(line), message)
+        });
+    Some(Diagnostic {
+        script: script.into(),
+        line,
+        message: message.into(),
+    })
+}
+
+/// Finds a `--!strict` or `--!nonstrict` directive at the top of `source`. Those ask for type checking, which the VM
+/// doesn't do, so they have no effect.
+pub fn check_mode_directive(script: &str, source: &str) -> Option<Diagnostic> {
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // Directives only count in the comments before the first statement.
+        let comment = line.strip_prefix("--")?;
+        let mode = comment.strip_prefix('!').map(str::trim_end);
+        if let Some(mode @ ("strict" | "nonstrict")) = mode {
+            return Some(Diagnostic {
+                script: script.into(),
+                line: Some(index as u32 + 1),
+                message: format!("--!{} has no effect, scripts are not type checked", mode),
+            });
+        }
+    }
+    None
+}
+
+/// Checks every script and ModuleScript under `root` without running them, for CI to catch broken scripts
+/// before the place gets played.
+///
+/// Only syntax errors are reported. Scripts are not type checked nor linted, the Luau build the VM uses doesn't
+/// come with the analysis library, so `--!strict` and `--!nonstrict` have no effect here.
+pub fn analyze_scripts(root: &ManagedInstance) -> LuaResult<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    for instance in std::iter::once(root.clone()).chain(root.get_descendants()?) {
+        let source = if let Ok(x) = instance.clone().cast_from_unsized::<dyn IModuleScript>() {
+            x.get_source()
+        } else if let Ok(x) = instance.clone().cast_from_unsized::<dyn IBaseScript>() {
+            x.get_source()
+        } else {
+            continue;
+        };
+        diagnostics.extend(check_source(&instance.get_full_name()?, &source));
+    }
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_mode_directive;
+
+    #[test]
+    fn finds_mode_directives_before_the_first_statement() {
+        let diagnostic =
+            check_mode_directive("Script", "-- header\n\n--!strict\nlocal x = 1").unwrap();
+        assert_eq!(
+            diagnostic.to_output_line(),
+            "Script:3: --!strict has no effect, scripts are not type checked"
+        );
+        assert!(check_mode_directive("Script", "--!native\nlocal x = 1").is_none());
+        assert!(check_mode_directive("Script", "local x = 1\n--!nonstrict").is_none());
+    }
+}
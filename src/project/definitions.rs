@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::core::{get_class_registry, ClassDescriptor, ClassRegistry, MemberKind};
+
+/// Declarations the generated classes rely on, which aren't classes of the registry.
+const PRELUDE: &str = "\
+declare class RBXScriptConnection
+\tConnected: boolean
+\tfunction Disconnect(self): ()
+end
+
+declare class RBXScriptSignal
+\tfunction Connect(self, callback: (...any) -> ()): RBXScriptConnection
+\tfunction ConnectParallel(self, callback: (...any) -> ()): RBXScriptConnection
+\tfunction Once(self, callback: (...any) -> ()): RBXScriptConnection
+\tfunction Wait(self): ...any
+end
+";
+
+/// Generates a Luau definitions file, in the format luau-lsp reads, declaring the classes the VM implements along
+/// with their superclasses. Members come from the class registry, so the ones the API dump knows about are
+/// declared even if they aren't implemented yet.
+///
+/// The registry doesn't know the signatures of functions, they take and return `any`. Data types and enums are
+/// `any` too.
+pub fn generate_type_definitions() -> String {
+    let registry = get_class_registry();
+    let mut classes: Vec<(&str, usize)> = Vec::new();
+    let mut seen = HashSet::new();
+    for class in registry.classes() {
+        let mut current = Some(class.name.as_str());
+        while let Some(name) = current.filter(|x| seen.insert(*x)) {
+            let superclass = superclass_of(&registry, name);
+            classes.push((name, depth(&registry, name)));
+            current = superclass;
+        }
+    }
+    // Superclasses have to be declared before the classes extending them.
+    classes.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(b.0)));
+
+    let mut output = String::from(PRELUDE);
+    for (name, _) in classes {
+        output.push('\n');
+        write_class(&mut output, &registry, name);
+    }
+    output.push_str(
+        "\ndeclare game: DataModel\ndeclare workspace: Workspace\ndeclare script: LuaSourceContainer\n",
+    );
+    output
+}
+
+/// The superclass the VM gives a class, the API dump has none for Instance.
+fn superclass_of<'a>(registry: &'a ClassRegistry, name: &str) -> Option<&'a str> {
+    registry
+        .get(name)
+        .or_else(|| registry.get_reflected(name))
+        .and_then(|x| x.superclass.as_deref())
+}
+
+fn depth(registry: &ClassRegistry, name: &str) -> usize {
+    let mut depth = 0;
+    let mut current = superclass_of(registry, name);
+    while let Some(name) = current {
+        depth += 1;
+        current = superclass_of(registry, name);
+    }
+    depth
+}
+
+fn write_class(output: &mut String, registry: &ClassRegistry, name: &str) {
+    match superclass_of(registry, name) {
+        Some(superclass) => writeln!(output, "declare class {} extends {}", name, superclass),
+        None => writeln!(output, "declare class {}", name),
+    }
+    .unwrap();
+    // The API dump goes first, its types are the ones Roblox documents.
+    let declaring: Vec<&ClassDescriptor> = registry
+        .get_reflected(name)
+        .into_iter()
+        .chain(registry.get(name))
+        .collect();
+    // Keyed by name, the declaration comes along.
+    let mut members: HashMap<&str, String> = HashMap::new();
+    for class in declaring.iter() {
+        for property in class.properties.iter() {
+            members.entry(&property.name).or_insert_with(|| {
+                format!(
+                    "{}: {}",
+                    property.name,
+                    type_name(registry, &property.value_type)
+                )
+            });
+        }
+        for member in class.members.iter() {
+            members
+                .entry(&member.name)
+                .or_insert_with(|| match member.kind {
+                    MemberKind::Property => format!("{}: any", member.name),
+                    MemberKind::Function => {
+                        format!("function {}(self, ...: any): ...any", member.name)
+                    }
+                    MemberKind::Event => format!("{}: RBXScriptSignal", member.name),
+                    MemberKind::Callback => format!("{}: (...any) -> ...any", member.name),
+                });
+        }
+    }
+    let mut members: Vec<(&str, String)> = members
+        .into_iter()
+        .filter(|(name, _)| is_identifier(name))
+        .collect();
+    members.sort();
+    for (_, declaration) in members {
+        writeln!(output, "\t{}", declaration).unwrap();
+    }
+    output.push_str("end\n");
+}
+
+/// The Luau type of a value type of the API dump.
+fn type_name(registry: &ClassRegistry, value_type: &str) -> String {
+    match value_type {
+        "bool" => "boolean".into(),
+        "int" | "int64" | "float" | "double" => "number".into(),
+        "string" | "Content" | "ProtectedString" => "string".into(),
+        name if registry.get(name).is_some() => format!("{}?", name),
+        name if registry.get_reflected(name).is_some() => "Instance?".into(),
+        _ => "any".into(),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generate_type_definitions;
+
+    #[test]
+    fn declares_implemented_classes_after_their_superclasses() {
+        let definitions = generate_type_definitions();
+        let block = |header: &str| {
+            let start = definitions
+                .find(header)
+                .unwrap_or_else(|| panic!("{} is missing", header));
+            let end = start + definitions[start..].find("end\n").unwrap();
+            (start, &definitions[start..end])
+        };
+        let (instance, members) = block("declare class Instance extends Object\n");
+        let (folder, _) = block("declare class Folder extends Instance\n");
+        assert!(instance < folder);
+        assert!(members.contains("\tName: string\n"));
+        assert!(members.contains("\tParent: Instance?\n"));
+        assert!(members.contains("\tChildAdded: RBXScriptSignal\n"));
+        assert!(members.contains("\tfunction FindFirstChild(self, ...: any): ...any\n"));
+        assert!(definitions.ends_with("declare script: LuaSourceContainer\n"));
+    }
+}
@@ -0,0 +1,694 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use r2g_mlua::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::model::read_model;
+use super::values::{json_to_lua, property_type};
+use super::{reload_script, ScriptKind};
+use crate::core::{
+    create_instance, get_class_registry, get_state, read_luaurc, DynInstance, ManagedInstance,
+    RequireAlias, WeakManagedInstance,
+};
+use crate::instance::{IBaseScript, ILocalizationTable, LocalizationEntry};
+
+#[derive(Deserialize)]
+struct ProjectFile {
+    name: String,
+    tree: ProjectNode,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProjectPath {
+    Required(PathBuf),
+    Optional { optional: PathBuf },
+}
+
+#[derive(Deserialize)]
+struct ProjectNode {
+    #[serde(rename = "$className")]
+    class_name: Option<String>,
+    #[serde(rename = "$path")]
+    path: Option<ProjectPath>,
+    #[serde(rename = "$properties", default)]
+    properties: Map<String, Value>,
+    #[serde(rename = "$ignoreUnknownInstances")]
+    ignore_unknown_instances: Option<bool>,
+    /// The children, along with the `$` keys that aren't supported.
+    #[serde(flatten)]
+    children: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetaFile {
+    class_name: Option<String>,
+    #[serde(default)]
+    properties: Map<String, Value>,
+    ignore_unknown_instances: Option<bool>,
+}
+
+/// A `.model.json` file, Roblox model files are read into the same shape.
+#[derive(Deserialize)]
+pub(super) struct ModelFile {
+    #[serde(rename = "className", alias = "ClassName")]
+    pub(super) class_name: String,
+    #[serde(rename = "name", alias = "Name")]
+    pub(super) name: Option<String>,
+    #[serde(rename = "properties", alias = "Properties", default)]
+    pub(super) properties: Map<String, Value>,
+    #[serde(rename = "children", alias = "Children", default)]
+    pub(super) children: Vec<ModelFile>,
+}
+
+/// An instance to build, as described by the project and the files it points to.
+#[derive(Debug)]
+struct Snapshot {
+    name: String,
+    class_name: String,
+    properties: Map<String, Value>,
+    source: Option<String>,
+    /// The entries of a LocalizationTable read from a CSV file.
+    localization: Option<Vec<LocalizationEntry>>,
+    children: Vec<Snapshot>,
+    ignore_unknown_instances: bool,
+    /// The file or directory the instance was read from, for `.luaurc` aliases to find it.
+    origin: Option<PathBuf>,
+}
+
+impl Snapshot {
+    fn new(name: &str, class_name: &str) -> Snapshot {
+        Snapshot {
+            name: name.into(),
+            class_name: class_name.into(),
+            properties: Map::new(),
+            source: None,
+            localization: None,
+            origin: None,
+            children: Vec::new(),
+            ignore_unknown_instances: true,
+        }
+    }
+    fn from_model(name: &str, model: ModelFile) -> Snapshot {
+        let mut snapshot = Snapshot::new(model.name.as_deref().unwrap_or(name), &model.class_name);
+        snapshot.properties = model.properties;
+        snapshot.children = model
+            .children
+            .into_iter()
+            .map(|x| Snapshot::from_model(&x.class_name.clone(), x))
+            .collect();
+        snapshot
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> LuaResult<T> {
+    let text = fs::read_to_string(path).map_err(|err| {
+        LuaError::RuntimeError(format!("failed to read {}: {}", path.display(), err))
+    })?;
+    serde_json::from_str(&text).map_err(|err| {
+        LuaError::RuntimeError(format!("failed to parse {}: {}", path.display(), err))
+    })
+}
+
+/// Splits CSV text into records, fields may be quoted to hold commas, quotes and line breaks.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Reads the entries of a localization table the way Rojo does: the header names the `Key`, `Source`,
+/// `Context` and `Example` columns, every other column holds the translations to the locale it's named after.
+fn read_localization_csv(text: &str) -> Vec<LocalizationEntry> {
+    let mut records = parse_csv(text).into_iter();
+    let Some(header) = records.next() else {
+        return Vec::new();
+    };
+    records
+        .filter_map(|record| {
+            let mut entry = LocalizationEntry::default();
+            for (column, value) in header.iter().zip(record) {
+                match column.as_str() {
+                    "Key" => entry.key = value,
+                    "Source" => entry.source = value,
+                    "Context" => entry.context = value,
+                    "Example" => entry.example = value,
+                    "" => {}
+                    locale if !value.is_empty() => {
+                        entry.values.insert(locale.into(), value);
+                    }
+                    _ => {}
+                }
+            }
+            (!entry.key.is_empty() || !entry.source.is_empty()).then_some(entry)
+        })
+        .collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Reads a Rojo project, and builds it into instances.
+struct ProjectLoader<'a> {
+    lua: &'a Lua,
+    origins: RefCell<HashMap<PathBuf, WeakManagedInstance>>,
+}
+
+impl ProjectLoader<'_> {
+    fn warn(&self, message: String) {
+        get_state(self.lua)
+            .get_log_service()
+            .log_warn(self.lua, message);
+    }
+    fn read_project(&self, file: &Path) -> LuaResult<Snapshot> {
+        let project: ProjectFile = read_json(file)?;
+        let base = file.parent().unwrap_or(Path::new(""));
+        self.read_node(&project.name, project.tree, base)?
+            .ok_or_else(|| {
+                LuaError::RuntimeError(format!("the tree of {} is empty", file.display()))
+            })
+    }
+    fn read_node(&self, name: &str, node: ProjectNode, base: &Path) -> LuaResult<Option<Snapshot>> {
+        let mut snapshot = match &node.path {
+            Some(ProjectPath::Required(path)) | Some(ProjectPath::Optional { optional: path }) => {
+                let full = base.join(path);
+                if !full.exists() {
+                    if matches!(node.path, Some(ProjectPath::Optional { .. })) {
+                        return Ok(None);
+                    }
+                    return Err(LuaError::RuntimeError(format!(
+                        "{} doesn't exist",
+                        full.display()
+                    )));
+                }
+                let Some(mut snapshot) = self.read_path(&full)? else {
+                    return Ok(None);
+                };
+                snapshot.name = name.into();
+                snapshot
+            }
+            None => {
+                // Services are usually only named, their name is their class.
+                let registry = get_class_registry();
+                let class_name = node.class_name.as_deref().unwrap_or(
+                    if registry
+                        .get(name)
+                        .or(registry.get_reflected(name))
+                        .is_some()
+                    {
+                        name
+                    } else {
+                        "Folder"
+                    },
+                );
+                Snapshot::new(name, class_name)
+            }
+        };
+        if let Some(class_name) = node.class_name {
+            if snapshot.class_name == "Folder" {
+                snapshot.class_name = class_name;
+            } else if snapshot.class_name != class_name {
+                self.warn(format!(
+                    "{} is a {}, its $className {} is ignored",
+                    name, snapshot.class_name, class_name
+                ));
+            }
+        }
+        snapshot.ignore_unknown_instances =
+            node.ignore_unknown_instances.unwrap_or(node.path.is_none());
+        snapshot.properties.extend(node.properties);
+        for (key, value) in node.children {
+            if key.starts_with('$') {
+                continue;
+            }
+            let child: ProjectNode = serde_json::from_value(value).map_err(|err| {
+                LuaError::RuntimeError(format!("invalid project node {}: {}", key, err))
+            })?;
+            if let Some(child) = self.read_node(&key, child, base)? {
+                snapshot.children.push(child);
+            }
+        }
+        Ok(Some(snapshot))
+    }
+    fn read_path(&self, path: &Path) -> LuaResult<Option<Snapshot>> {
+        let mut snapshot = if path.is_dir() {
+            Some(self.read_dir(path)?)
+        } else {
+            self.read_file(path)?
+        };
+        if let Some(x) = snapshot.as_mut() {
+            x.origin = fs::canonicalize(path).ok();
+        }
+        Ok(snapshot)
+    }
+    fn read_dir(&self, dir: &Path) -> LuaResult<Snapshot> {
+        let project = dir.join("default.project.json");
+        if project.is_file() {
+            return self.read_project(&project);
+        }
+        let name = file_name(dir);
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+            .and_then(|x| {
+                x.map(|x| x.map(|x| x.path()))
+                    .collect::<std::io::Result<_>>()
+            })
+            .map_err(|err| {
+                LuaError::RuntimeError(format!("failed to read {}: {}", dir.display(), err))
+            })?;
+        entries.sort();
+        let mut snapshot = None;
+        let mut children = Vec::new();
+        for entry in entries {
+            let entry_name = file_name(&entry);
+            if entry_name.starts_with('.') || entry_name.ends_with(".meta.json") {
+                continue;
+            }
+            match ScriptKind::of_file(&entry_name) {
+                Some(("", kind)) if entry.is_file() => {
+                    snapshot = Some(self.read_script(&entry, &name, kind)?);
+                }
+                _ => children.push(entry),
+            }
+        }
+        let mut snapshot = snapshot.unwrap_or_else(|| Snapshot::new(&name, "Folder"));
+        self.apply_meta(&mut snapshot, &dir.join("init.meta.json"))?;
+        for child in children {
+            if let Some(child) = self.read_path(&child)? {
+                snapshot.children.push(child);
+            }
+        }
+        Ok(snapshot)
+    }
+    fn read_file(&self, file: &Path) -> LuaResult<Option<Snapshot>> {
+        let file_name = file_name(file);
+        let dir = file.parent().unwrap_or(Path::new(""));
+        if let Some((name, kind)) = ScriptKind::of_file(&file_name) {
+            let snapshot = self.read_script(file, name, kind)?;
+            return Ok(Some(snapshot));
+        }
+        if file_name.ends_with(".project.json") {
+            return self.read_project(file).map(Some);
+        }
+        if let Some(name) = file_name.strip_suffix(".model.json") {
+            let model: ModelFile = read_json(file)?;
+            return Ok(Some(Snapshot::from_model(name, model)));
+        }
+        if let Some(name) = file_name.strip_suffix(".txt") {
+            let text = fs::read_to_string(file).map_err(|err| {
+                LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+            })?;
+            let mut snapshot = Snapshot::new(name, "StringValue");
+            snapshot
+                .properties
+                .insert("Value".into(), Value::String(text));
+            self.apply_meta(&mut snapshot, &dir.join(format!("{}.meta.json", name)))?;
+            return Ok(Some(snapshot));
+        }
+        if let Some(name) = file_name.strip_suffix(".csv") {
+            let text = fs::read_to_string(file).map_err(|err| {
+                LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+            })?;
+            let mut snapshot = Snapshot::new(name, "LocalizationTable");
+            snapshot.localization = Some(read_localization_csv(&text));
+            self.apply_meta(&mut snapshot, &dir.join(format!("{}.meta.json", name)))?;
+            return Ok(Some(snapshot));
+        }
+        if let Some(name) = file_name
+            .strip_suffix(".rbxm")
+            .or_else(|| file_name.strip_suffix(".rbxmx"))
+        {
+            return self.read_model(file, name).map(Some);
+        }
+        Ok(None)
+    }
+    /// Reads a binary or XML model file, which has to hold a single instance. Like Rojo, the instance is named
+    /// after the file.
+    fn read_model(&self, file: &Path, name: &str) -> LuaResult<Snapshot> {
+        let data = fs::read(file).map_err(|err| {
+            LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+        })?;
+        let model = read_model(&data).map_err(|err| {
+            LuaError::RuntimeError(format!("failed to parse {}: {}", file.display(), err))
+        })?;
+        if !model.unsupported.is_empty() {
+            self.warn(format!(
+                "{} has properties of types the VM doesn't implement, they are left out: {}",
+                file.display(),
+                model.unsupported.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        let count = model.roots.len();
+        let Ok([root]) = <[ModelFile; 1]>::try_from(model.roots) else {
+            return Err(LuaError::RuntimeError(format!(
+                "{} holds {} instances, model files have to hold exactly one",
+                file.display(),
+                count
+            )));
+        };
+        Ok(Snapshot::from_model(name, ModelFile { name: None, ..root }))
+    }
+    fn read_script(&self, file: &Path, name: &str, kind: ScriptKind) -> LuaResult<Snapshot> {
+        let source = fs::read_to_string(file).map_err(|err| {
+            LuaError::RuntimeError(format!("failed to read {}: {}", file.display(), err))
+        })?;
+        let mut snapshot = Snapshot::new(name, kind.class_name());
+        snapshot.source = Some(source);
+        // `Foo.server.luau` goes with `Foo.server.meta.json`, `init.luau` with the directory's `init.meta.json`.
+        let file_name = file_name(file);
+        let stem = file_name
+            .strip_suffix(".luau")
+            .or_else(|| file_name.strip_suffix(".lua"))
+            .unwrap_or(&file_name);
+        if ScriptKind::of_file(&file_name).is_some_and(|(x, _)| !x.is_empty()) {
+            let dir = file.parent().unwrap_or(Path::new(""));
+            self.apply_meta(&mut snapshot, &dir.join(format!("{}.meta.json", stem)))?;
+        }
+        Ok(snapshot)
+    }
+    fn apply_meta(&self, snapshot: &mut Snapshot, file: &Path) -> LuaResult<()> {
+        if !file.is_file() {
+            return Ok(());
+        }
+        let meta: MetaFile = read_json(file)?;
+        if let Some(class_name) = meta.class_name {
+            if snapshot.class_name == "Folder" {
+                snapshot.class_name = class_name;
+            }
+        }
+        if let Some(x) = meta.ignore_unknown_instances {
+            snapshot.ignore_unknown_instances = x;
+        }
+        snapshot.properties.extend(meta.properties);
+        Ok(())
+    }
+
+    /// Creates the instance described by `snapshot` under `parent`.
+    fn build(&self, mut snapshot: Snapshot, parent: &ManagedInstance) -> LuaResult<()> {
+        let instance = match create_instance(self.lua, &snapshot.class_name) {
+            Ok(x) => x,
+            Err(err) => {
+                self.warn(format!("{}, {} is loaded as a Folder", err, snapshot.name));
+                snapshot.class_name = "Folder".into();
+                snapshot.properties.clear();
+                create_instance(self.lua, "Folder")?
+            }
+        };
+        instance.set_name(snapshot.name.clone())?;
+        self.add_origin(&snapshot, &instance);
+        let script = instance.clone().cast_from_unsized::<dyn IBaseScript>().ok();
+        let mut disabled = false;
+        if script.is_some() {
+            disabled = match (
+                snapshot.properties.remove("Disabled"),
+                snapshot.properties.remove("Enabled"),
+            ) {
+                (Some(Value::Bool(x)), _) => x,
+                (_, Some(Value::Bool(x))) => !x,
+                _ => false,
+            };
+        }
+        if let Some(Value::String(source)) = snapshot.properties.remove("Source") {
+            snapshot.source.get_or_insert(source);
+        }
+        self.set_properties(&instance, &snapshot.class_name, snapshot.properties);
+        if let Some(source) = snapshot.source {
+            reload_script(self.lua, &instance, source)?;
+        }
+        if let Some(entries) = snapshot.localization {
+            if let Ok(table) = instance
+                .clone()
+                .cast_from_unsized::<dyn ILocalizationTable>()
+            {
+                table.set_localization_entries(entries);
+            }
+        }
+        instance.set_parent(self.lua, Some(parent.clone()))?;
+        for child in snapshot.children {
+            self.build(child, &instance)?;
+        }
+        if let Some(script) = script.filter(|_| !disabled) {
+            script.set_disabled(self.lua, false)?;
+        }
+        Ok(())
+    }
+    /// Builds the children described by `snapshot` into an instance that already exists, the DataModel or a service.
+    fn build_into(&self, snapshot: Snapshot, instance: &ManagedInstance) -> LuaResult<()> {
+        if !snapshot.ignore_unknown_instances {
+            for child in instance.get_children()? {
+                let name = child.get_name();
+                if !snapshot.children.iter().any(|x| x.name == name) {
+                    child.destroy(self.lua)?;
+                }
+            }
+        }
+        self.add_origin(&snapshot, instance);
+        let class_name = instance.get_class_name();
+        self.set_properties(instance, class_name, snapshot.properties);
+        for child in snapshot.children {
+            let is_service = get_class_registry()
+                .get(&child.class_name)
+                .is_some_and(|x| x.is_service());
+            let service = if is_service {
+                DynInstance::find_first_child_of_class(&**instance, child.class_name.clone())?
+            } else {
+                None
+            };
+            match service {
+                Some(service) => self.build_into(child, &service)?,
+                None => self.build(child, instance)?,
+            }
+        }
+        Ok(())
+    }
+    fn add_origin(&self, snapshot: &Snapshot, instance: &ManagedInstance) {
+        if let Some(origin) = snapshot.origin.clone() {
+            self.origins
+                .borrow_mut()
+                .insert(origin, instance.downgrade());
+        }
+    }
+    /// Registers the aliases of the `.luaurc` next to the project. Aliases pointing into the project
+    /// lead to the instances built from there, the others to the files they name.
+    fn load_aliases(&self, file: &Path) -> LuaResult<()> {
+        if !file.is_file() {
+            return Ok(());
+        }
+        let aliases = read_luaurc(file)?;
+        let origins = self.origins.borrow();
+        let mut vm = get_state(self.lua).get_vm_mut();
+        for (name, path) in aliases {
+            let instance = fs::canonicalize(&path)
+                .ok()
+                .and_then(|x| origins.get(&x).cloned());
+            match instance {
+                Some(x) => vm.set_require_alias(&name, RequireAlias::Instance(x)),
+                None => vm.set_require_alias(&name, RequireAlias::Path(path)),
+            }
+        }
+        Ok(())
+    }
+    fn set_properties(
+        &self,
+        instance: &ManagedInstance,
+        class_name: &str,
+        properties: Map<String, Value>,
+    ) {
+        for (name, value) in properties {
+            let value_type = property_type(class_name, &name);
+            let result = json_to_lua(self.lua, &value, value_type.as_deref())
+                .and_then(|x| instance.lua_set(self.lua, name.clone(), x));
+            if let Err(err) = result {
+                self.warn(format!(
+                    "Failed to set {}.{}: {}",
+                    instance.get_full_name().unwrap_or_default(),
+                    name,
+                    err
+                ));
+            }
+        }
+    }
+}
+
+/// Loads a Rojo project into `game`, `path` being the project file or the directory holding `default.project.json`.
+///
+/// The tree has to describe a DataModel. Services the VM implements get filled in, the others become Folders
+/// named after them. Scripts are enabled unless the project says otherwise, they start on the next frame.
+/// CSV files become LocalizationTables. Binary and XML model files (`.rbxm`, `.rbxmx`) are read too, except for
+/// binary chunks compressed with Zstandard. Their properties of types the VM doesn't implement are left out with
+/// a warning.
+/// The aliases of a `.luaurc` next to the project file become require aliases of the VM.
+pub fn load_project(lua: &Lua, path: &Path, game: &ManagedInstance) -> LuaResult<()> {
+    let file = if path.is_dir() {
+        path.join("default.project.json")
+    } else {
+        path.to_path_buf()
+    };
+    let loader = ProjectLoader {
+        lua,
+        origins: RefCell::new(HashMap::new()),
+    };
+    let snapshot = loader.read_project(&file)?;
+    if snapshot.class_name != "DataModel" {
+        return Err(LuaError::RuntimeError(format!(
+            "{} describes a {}, only DataModel projects can be loaded",
+            file.display(),
+            snapshot.class_name
+        )));
+    }
+    loader.build_into(snapshot, game)?;
+    let luaurc = file.parent().unwrap_or(Path::new("")).join(".luaurc");
+    if let Err(err) = loader.load_aliases(&luaurc) {
+        loader.warn(format!("Failed to load the require aliases: {}", err));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use r2g_mlua::prelude::*;
+
+    use super::load_project;
+    use crate::core::{ManagedInstance, RblxVM};
+    use crate::instance::{IBaseScript, IModuleScript};
+    use crate::project::find_path;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn loads_a_sample_project() {
+        let root = std::env::temp_dir().join(format!("rblx-godot-project-{}", std::process::id()));
+        write(
+            &root,
+            "default.project.json",
+            r#"{
+                "name": "Sample",
+                "tree": {
+                    "$className": "DataModel",
+                    "ReplicatedStorage": { "$path": "src/shared" },
+                    "ServerScriptService": { "$path": "src/server" }
+                }
+            }"#,
+        );
+        write(&root, "src/shared/Util.luau", "return {}");
+        write(&root, "src/shared/Greeting.txt", "Hello");
+        write(
+            &root,
+            "src/shared/Strings.csv",
+            "Key,Source,Context,Example,fr\nhello,Hello,,,Bonjour\nquote,\"Say \"\"hi\"\", then go\",,,\n",
+        );
+        write(
+            &root,
+            "src/shared/Config.model.json",
+            r#"{ "className": "Folder", "children": [{ "name": "Speed", "className": "StringValue" }] }"#,
+        );
+        write(&root, "src/server/Main.server.luau", "print('main')");
+        write(
+            &root,
+            "src/server/Main.server.meta.json",
+            r#"{ "properties": { "Disabled": true } }"#,
+        );
+        write(
+            &root,
+            "src/server/Map.rbxmx",
+            r#"<roblox version="4">
+                <Item class="Folder" referent="RBX0">
+                    <Properties><string name="Name">Level</string></Properties>
+                    <Item class="StringValue" referent="RBX1">
+                        <Properties>
+                            <string name="Name">Spawn</string>
+                            <string name="Value">Center</string>
+                        </Properties>
+                    </Item>
+                </Item>
+            </roblox>"#,
+        );
+
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let game: ManagedInstance = vm.get_game_instance().cast_from_sized().unwrap();
+        let lua = vm.get_main_state().get_lua().clone();
+        let result = load_project(&lua, &root, &game);
+        let _ = fs::remove_dir_all(&root);
+        result.unwrap();
+
+        let find = |path: &[&str]| find_path(&game, path).unwrap().unwrap();
+        let util = find(&["ReplicatedStorage", "Util"]);
+        assert_eq!(util.get_class_name(), "ModuleScript");
+        let util = util.cast_from_unsized::<dyn IModuleScript>().unwrap();
+        assert_eq!(util.get_source(), "return {}");
+
+        let greeting = find(&["ReplicatedStorage", "Greeting"]);
+        assert_eq!(greeting.get_class_name(), "StringValue");
+        let value = greeting.lua_get(&lua, "Value".into()).unwrap();
+        assert_eq!(String::from_lua(value, &lua).unwrap(), "Hello");
+
+        let speed = find(&["ReplicatedStorage", "Config", "Speed"]);
+        assert_eq!(speed.get_class_name(), "StringValue");
+
+        let strings = find(&["ReplicatedStorage", "Strings"]);
+        assert_eq!(strings.get_class_name(), "LocalizationTable");
+        let get_entries =
+            LuaFunction::from_lua(strings.lua_get(&lua, "GetEntries".into()).unwrap(), &lua)
+                .unwrap();
+        let entries: LuaTable = get_entries.call(strings.clone()).unwrap();
+        assert_eq!(entries.raw_len(), 2);
+        let hello: LuaTable = entries.raw_get(1).unwrap();
+        assert_eq!(hello.get::<String>("Key").unwrap(), "hello");
+        let values: LuaTable = hello.get("Values").unwrap();
+        assert_eq!(values.get::<String>("fr").unwrap(), "Bonjour");
+        let quote: LuaTable = entries.raw_get(2).unwrap();
+        assert_eq!(
+            quote.get::<String>("Source").unwrap(),
+            "Say \"hi\", then go"
+        );
+
+        let main = find(&["ServerScriptService", "Main"]);
+        assert_eq!(main.get_class_name(), "Script");
+        let main = main.cast_from_unsized::<dyn IBaseScript>().unwrap();
+        assert_eq!(main.get_source(), "print('main')");
+        // Named after the file, not the Name of the instance it holds.
+        let spawn = find(&["ServerScriptService", "Map", "Spawn"]);
+        assert_eq!(spawn.get_class_name(), "StringValue");
+        let value = spawn.lua_get(&lua, "Value".into()).unwrap();
+        assert_eq!(String::from_lua(value, &lua).unwrap(), "Center");
+    }
+}
@@ -0,0 +1,119 @@
+mod analysis;
+mod definitions;
+mod loader;
+mod model;
+mod values;
+mod watcher;
+
+pub use analysis::{analyze_scripts, check_mode_directive, check_source, Diagnostic};
+pub use definitions::generate_type_definitions;
+pub use loader::load_project;
+pub use watcher::{SourceChange, SourceWatcher};
+
+use r2g_mlua::prelude::*;
+
+use crate::core::{create_instance, ManagedInstance};
+use crate::instance::{IBaseScript, IModuleScript};
+
+/// The kind of script a source file maps to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptKind {
+    Script,
+    LocalScript,
+    ModuleScript,
+}
+
+impl ScriptKind {
+    pub fn class_name(self) -> &'static str {
+        match self {
+            ScriptKind::Script => "Script",
+            ScriptKind::LocalScript => "LocalScript",
+            ScriptKind::ModuleScript => "ModuleScript",
+        }
+    }
+    /// Finds the script a file maps to following Rojo's conventions, `Foo.server.luau` is a Script,
+    /// `Foo.client.luau` a LocalScript and `Foo.luau` a ModuleScript, all named `Foo`.
+    /// `init` files stand for the directory holding them, their name is empty.
+    pub fn of_file(file_name: &str) -> Option<(&str, ScriptKind)> {
+        let stem = file_name
+            .strip_suffix(".luau")
+            .or_else(|| file_name.strip_suffix(".lua"))?;
+        let (name, kind) = if let Some(name) = stem.strip_suffix(".server") {
+            (name, ScriptKind::Script)
+        } else if let Some(name) = stem.strip_suffix(".client") {
+            (name, ScriptKind::LocalScript)
+        } else {
+            (stem, ScriptKind::ModuleScript)
+        };
+        match name {
+            "" => None,
+            "init" => Some(("", kind)),
+            name => Some((name, kind)),
+        }
+    }
+}
+
+/// Finds the direct child of `parent` named `name`.
+pub fn find_child(parent: &ManagedInstance, name: &str) -> LuaResult<Option<ManagedInstance>> {
+    Ok(parent
+        .get_children()?
+        .into_iter()
+        .find(|x| x.get_name() == name))
+}
+
+/// Follows `path` down from `root`, one child name at a time.
+pub fn find_path(
+    root: &ManagedInstance,
+    path: &[impl AsRef<str>],
+) -> LuaResult<Option<ManagedInstance>> {
+    let mut current = root.clone();
+    for name in path {
+        match find_child(&current, name.as_ref())? {
+            Some(x) => current = x,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Finds the child of `parent` named `name`, creating a Folder for it if there is none.
+pub(crate) fn find_or_create_folder(
+    lua: &Lua,
+    parent: &ManagedInstance,
+    name: &str,
+) -> LuaResult<ManagedInstance> {
+    if let Some(x) = find_child(parent, name)? {
+        return Ok(x);
+    }
+    let folder = create_instance(lua, "Folder")?;
+    folder.set_name(name.into())?;
+    folder.set_parent(lua, Some(parent.clone()))?;
+    Ok(folder)
+}
+
+/// Replaces `instance` by a new instance of `class_name`, which takes over its name, parent and children.
+pub(crate) fn replace_instance(
+    lua: &Lua,
+    instance: &ManagedInstance,
+    class_name: &str,
+) -> LuaResult<ManagedInstance> {
+    let replacement = create_instance(lua, class_name)?;
+    replacement.set_name(instance.get_name())?;
+    for child in instance.get_children()? {
+        child.set_parent(lua, Some(replacement.clone()))?;
+    }
+    replacement.set_parent(lua, instance.get_parent())?;
+    instance.destroy(lua)?;
+    Ok(replacement)
+}
+
+/// Gives a script or a module a new source, restarting what depends on it.
+pub(crate) fn reload_script(lua: &Lua, script: &ManagedInstance, source: String) -> LuaResult<()> {
+    if let Ok(module) = script.clone().cast_from_unsized::<dyn IModuleScript>() {
+        module.reload(lua, source)
+    } else if let Ok(script) = script.clone().cast_from_unsized::<dyn IBaseScript>() {
+        script.reload(lua, source)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,894 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::{json, Map, Value};
+
+use super::loader::ModelFile;
+
+/// The instances of a Roblox model file, in the shape of a `.model.json` file.
+pub(super) struct Model {
+    pub(super) roots: Vec<ModelFile>,
+    /// The types of the properties that were left out, the VM has no data type for them.
+    pub(super) unsupported: BTreeSet<String>,
+}
+
+/// Reads a binary (`.rbxm`) or XML (`.rbxmx`) model file.
+pub(super) fn read_model(data: &[u8]) -> Result<Model, String> {
+    if data.starts_with(BINARY_MAGIC) {
+        return read_binary(data);
+    }
+    let text = std::str::from_utf8(data).map_err(|_| String::from("not a model file"))?;
+    read_xml(text)
+}
+
+fn new_instance(class_name: String) -> ModelFile {
+    ModelFile {
+        class_name,
+        name: None,
+        properties: Map::new(),
+        children: Vec::new(),
+    }
+}
+
+/// Sets a property read from a model file, the name of the instance is kept apart like in `.model.json` files.
+fn set_property(instance: &mut ModelFile, name: String, value: Value) {
+    match (name.as_str(), value) {
+        ("Name", Value::String(x)) => instance.name = Some(x),
+        (_, value) => {
+            instance.properties.insert(name, value);
+        }
+    }
+}
+
+fn cframe(position: [f64; 3], rows: [[f64; 3]; 3]) -> Value {
+    json!({ "CFrame": { "position": position, "orientation": rows } })
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, x)| x.as_str())
+    }
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|x| match x {
+            Node::Element(x) => Some(x),
+            Node::Text(_) => None,
+        })
+    }
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|x| x.name == name)
+    }
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|x| match x {
+                Node::Text(x) => Some(x.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect()
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let decoded = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            x => x
+                .strip_prefix("#x")
+                .map(|x| u32::from_str_radix(x, 16))
+                .or_else(|| x.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(x) => {
+                out.push(x);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Reads the subset of XML model files use: elements, attributes, text, CDATA sections and comments.
+struct XmlParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+    /// Skips past the next `end`.
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        let found = self
+            .rest()
+            .find(end)
+            .ok_or_else(|| format!("missing {}", end))?;
+        self.pos += found + end.len();
+        Ok(())
+    }
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.rest().starts_with(c) {
+            true => {
+                self.pos += 1;
+                Ok(())
+            }
+            false => Err(format!("expected {} at byte {}", c, self.pos)),
+        }
+    }
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|x: char| x.is_whitespace() || matches!(x, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err(format!("expected a name at byte {}", self.pos));
+        }
+        self.pos += end;
+        Ok(rest[..end].into())
+    }
+    /// Skips the declaration, comments and doctype before the root element.
+    fn skip_prolog(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+    fn element(&mut self) -> Result<Element, String> {
+        self.expect('<')?;
+        let mut element = Element {
+            name: self.name()?,
+            attributes: Vec::new(),
+            children: Vec::new(),
+        };
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let name = self.name()?;
+            self.skip_whitespace();
+            self.expect('=')?;
+            self.skip_whitespace();
+            let quote = self
+                .rest()
+                .chars()
+                .next()
+                .filter(|x| matches!(x, '"' | '\''))
+                .ok_or_else(|| format!("expected a quoted value at byte {}", self.pos))?;
+            self.pos += 1;
+            let end = self
+                .rest()
+                .find(quote)
+                .ok_or_else(|| String::from("unterminated attribute"))?;
+            let value = decode_entities(&self.rest()[..end]);
+            self.pos += end + 1;
+            element.attributes.push((name, value));
+        }
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(format!("unterminated element {}", element.name));
+            } else if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                self.skip_whitespace();
+                self.expect('>')?;
+                if name != element.name {
+                    return Err(format!("{} is closed by {}", element.name, name));
+                }
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if let Some(rest) = rest.strip_prefix("<![CDATA[") {
+                let end = rest
+                    .find("]]>")
+                    .ok_or_else(|| String::from("unterminated CDATA section"))?;
+                element.children.push(Node::Text(rest[..end].into()));
+                self.pos += "<![CDATA[".len() + end + "]]>".len();
+            } else if rest.starts_with('<') {
+                let child = self.element()?;
+                element.children.push(Node::Element(child));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                element
+                    .children
+                    .push(Node::Text(decode_entities(&rest[..end])));
+                self.pos += end;
+            }
+        }
+    }
+}
+
+fn read_xml(text: &str) -> Result<Model, String> {
+    let mut parser = XmlParser { text, pos: 0 };
+    parser.skip_prolog()?;
+    let root = parser.element()?;
+    if root.name != "roblox" {
+        return Err(String::from("not a model file"));
+    }
+    let mut model = Model {
+        roots: Vec::new(),
+        unsupported: BTreeSet::new(),
+    };
+    for item in root.elements().filter(|x| x.name == "Item") {
+        let instance = read_xml_item(item, &mut model.unsupported)?;
+        model.roots.push(instance);
+    }
+    Ok(model)
+}
+
+fn read_xml_item(item: &Element, unsupported: &mut BTreeSet<String>) -> Result<ModelFile, String> {
+    let class_name = item
+        .attribute("class")
+        .ok_or_else(|| String::from("an Item has no class"))?;
+    let mut instance = new_instance(class_name.into());
+    for child in item.elements() {
+        match child.name.as_str() {
+            "Properties" => {
+                for property in child.elements() {
+                    let Some(name) = property.attribute("name") else {
+                        continue;
+                    };
+                    match xml_value(property) {
+                        Some(value) => set_property(&mut instance, name.into(), value),
+                        None => {
+                            unsupported.insert(property.name.clone());
+                        }
+                    }
+                }
+            }
+            "Item" => instance.children.push(read_xml_item(child, unsupported)?),
+            _ => {}
+        }
+    }
+    Ok(instance)
+}
+
+/// Turns a property of an XML model into the value a `.model.json` file would hold, `None` for the types the
+/// VM has no data type for.
+fn xml_value(property: &Element) -> Option<Value> {
+    let text = property.text();
+    let number = |name: &str| -> Option<f64> { property.child(name)?.text().trim().parse().ok() };
+    let numbers =
+        |names: &[&str]| -> Option<Vec<f64>> { names.iter().map(|x| number(x)).collect() };
+    match property.name.as_str() {
+        "string" | "ProtectedString" => Some(Value::String(text)),
+        "Content" => Some(Value::String(
+            property.child("url").map(Element::text).unwrap_or_default(),
+        )),
+        "bool" => Some(Value::Bool(text.trim() == "true")),
+        "int" | "int64" => text.trim().parse::<i64>().ok().map(Value::from),
+        "float" | "double" => text.trim().parse::<f64>().ok().map(Value::from),
+        "token" => text
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .map(|x| json!({ "Enum": x })),
+        "Vector2" => numbers(&["X", "Y"]).map(|x| json!({ "Vector2": x })),
+        "Vector3" => numbers(&["X", "Y", "Z"]).map(|x| json!({ "Vector3": x })),
+        "CoordinateFrame" => {
+            let x = numbers(&[
+                "X", "Y", "Z", "R00", "R01", "R02", "R10", "R11", "R12", "R20", "R21", "R22",
+            ])?;
+            Some(cframe(
+                [x[0], x[1], x[2]],
+                [[x[3], x[4], x[5]], [x[6], x[7], x[8]], [x[9], x[10], x[11]]],
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// `<roblox!` followed by the signature guarding against text mode transfers.
+const BINARY_MAGIC: &[u8] = b"<roblox!\x89\xff\r\n\x1a\n";
+
+/// The magic number chunks compressed with Zstandard start with, instead of LZ4.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decompresses an LZ4 block, the compression chunks of binary models use.
+fn decompress_lz4(input: &[u8], size: usize) -> Result<Vec<u8>, String> {
+    let invalid = || String::from("invalid LZ4 data");
+    let mut out = Vec::with_capacity(size);
+    let mut pos = 0;
+    // Lengths of 15 go on in the following bytes, up to one that isn't 255.
+    let length = |pos: &mut usize, mut length: usize| -> Result<usize, String> {
+        if length == 15 {
+            loop {
+                let byte = *input.get(*pos).ok_or_else(invalid)?;
+                *pos += 1;
+                length += byte as usize;
+                if byte != 255 {
+                    break;
+                }
+            }
+        }
+        Ok(length)
+    };
+    while pos < input.len() {
+        let token = input[pos];
+        pos += 1;
+        let literals = length(&mut pos, (token >> 4) as usize)?;
+        out.extend_from_slice(input.get(pos..pos + literals).ok_or_else(invalid)?);
+        pos += literals;
+        // The last sequence only holds literals.
+        if pos == input.len() {
+            break;
+        }
+        let offset = input.get(pos..pos + 2).ok_or_else(invalid)?;
+        let offset = u16::from_le_bytes([offset[0], offset[1]]) as usize;
+        pos += 2;
+        if offset == 0 || offset > out.len() {
+            return Err(invalid());
+        }
+        let matched = length(&mut pos, (token & 15) as usize)? + 4;
+        let start = out.len() - offset;
+        // The match may overlap what it writes, it's copied byte by byte.
+        for i in start..start + matched {
+            out.push(out[i]);
+        }
+    }
+    match out.len() == size {
+        true => Ok(out),
+        false => Err(invalid()),
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+/// Undoes the transformation binary models apply to integers, moving the sign to the lowest bit.
+fn untransform_i32(x: u32) -> i32 {
+    ((x >> 1) as i32) ^ -((x & 1) as i32)
+}
+
+fn untransform_i64(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + length)
+            .ok_or_else(|| String::from("unexpected end of data"))?;
+        self.pos += length;
+        Ok(bytes)
+    }
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_le_bytes)
+    }
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+    /// Reads `count` values of `N` bytes stored byte plane by byte plane, the first plane holding the most
+    /// significant bytes.
+    fn interleaved<const N: usize>(&mut self, count: usize) -> Result<Vec<[u8; N]>, String> {
+        let bytes = self.bytes(count * N)?;
+        Ok((0..count)
+            .map(|i| std::array::from_fn(|plane| bytes[plane * count + i]))
+            .collect())
+    }
+    fn i32s(&mut self, count: usize) -> Result<Vec<i32>, String> {
+        Ok(self
+            .interleaved::<4>(count)?
+            .into_iter()
+            .map(|x| untransform_i32(u32::from_be_bytes(x)))
+            .collect())
+    }
+    /// Floats are stored with their sign bit moved to the lowest bit.
+    fn f32s(&mut self, count: usize) -> Result<Vec<f64>, String> {
+        Ok(self
+            .interleaved::<4>(count)?
+            .into_iter()
+            .map(|x| f32::from_bits(u32::from_be_bytes(x).rotate_right(1)) as f64)
+            .collect())
+    }
+    /// Referents are stored as the difference with the previous one.
+    fn referents(&mut self, count: usize) -> Result<Vec<i32>, String> {
+        let mut last = 0i32;
+        Ok(self
+            .i32s(count)?
+            .into_iter()
+            .map(|x| {
+                last = last.wrapping_add(x);
+                last
+            })
+            .collect())
+    }
+    fn vector3s(&mut self, count: usize) -> Result<Vec<[f64; 3]>, String> {
+        let (x, y, z) = (self.f32s(count)?, self.f32s(count)?, self.f32s(count)?);
+        Ok((0..count).map(|i| [x[i], y[i], z[i]]).collect())
+    }
+}
+
+/// The rotation a CFrame of a binary model gives by id, the columns of the matrix being axis aligned.
+/// `id - 1` is `6 * a + b`, the first column being the axis `a` and the second the axis `b`, `+X`, `+Y`, `+Z`,
+/// `-X`, `-Y` and `-Z` in that order.
+fn basic_rotation(id: u8) -> Option<[[f64; 3]; 3]> {
+    let axis = |x: u8| {
+        let mut v = [0.0; 3];
+        v[(x % 3) as usize] = if x < 3 { 1.0 } else { -1.0 };
+        v
+    };
+    let (a, b) = ((id.checked_sub(1)?) / 6, (id - 1) % 6);
+    if a >= 6 || a % 3 == b % 3 {
+        return None;
+    }
+    let (right, up) = (axis(a), axis(b));
+    let back = [
+        right[1] * up[2] - right[2] * up[1],
+        right[2] * up[0] - right[0] * up[2],
+        right[0] * up[1] - right[1] * up[0],
+    ];
+    Some(std::array::from_fn(|row| [right[row], up[row], back[row]]))
+}
+
+/// Reads the values of a property for `count` instances, `None` for the types the VM has no data type for.
+fn binary_values(
+    reader: &mut Reader,
+    value_type: u8,
+    count: usize,
+) -> Result<Option<Vec<Value>>, String> {
+    let values = match value_type {
+        0x01 => (0..count)
+            .map(|_| reader.string().map(Value::String))
+            .collect::<Result<_, _>>()?,
+        0x02 => reader
+            .bytes(count)?
+            .iter()
+            .map(|x| Value::Bool(*x != 0))
+            .collect(),
+        0x03 => reader.i32s(count)?.into_iter().map(Value::from).collect(),
+        0x04 => reader.f32s(count)?.into_iter().map(Value::from).collect(),
+        0x05 => (0..count)
+            .map(|_| reader.array().map(|x| Value::from(f64::from_le_bytes(x))))
+            .collect::<Result<_, _>>()?,
+        0x0d => {
+            let (x, y) = (reader.f32s(count)?, reader.f32s(count)?);
+            (0..count)
+                .map(|i| json!({ "Vector2": [x[i], y[i]] }))
+                .collect()
+        }
+        0x0e => reader
+            .vector3s(count)?
+            .into_iter()
+            .map(|x| json!({ "Vector3": x }))
+            .collect(),
+        0x10 => {
+            let mut rotations = Vec::with_capacity(count);
+            for _ in 0..count {
+                let rotation = match reader.u8()? {
+                    0 => {
+                        let mut x = [0.0; 9];
+                        for x in x.iter_mut() {
+                            *x = f32::from_le_bytes(reader.array()?) as f64;
+                        }
+                        [[x[0], x[1], x[2]], [x[3], x[4], x[5]], [x[6], x[7], x[8]]]
+                    }
+                    id => basic_rotation(id)
+                        .ok_or_else(|| format!("invalid CFrame rotation id {}", id))?,
+                };
+                rotations.push(rotation);
+            }
+            reader
+                .vector3s(count)?
+                .into_iter()
+                .zip(rotations)
+                .map(|(position, rows)| cframe(position, rows))
+                .collect()
+        }
+        0x12 => reader
+            .interleaved::<4>(count)?
+            .into_iter()
+            .map(|x| json!({ "Enum": u32::from_be_bytes(x) }))
+            .collect(),
+        0x1b => reader
+            .interleaved::<8>(count)?
+            .into_iter()
+            .map(|x| Value::from(untransform_i64(u64::from_be_bytes(x))))
+            .collect(),
+        _ => return Ok(None),
+    };
+    Ok(Some(values))
+}
+
+fn binary_type_name(value_type: u8) -> String {
+    match value_type {
+        0x06 => "UDim".into(),
+        0x07 => "UDim2".into(),
+        0x08 => "Ray".into(),
+        0x09 => "Faces".into(),
+        0x0a => "Axes".into(),
+        0x0b => "BrickColor".into(),
+        0x0c => "Color3".into(),
+        0x13 => "Ref".into(),
+        0x14 => "Vector3int16".into(),
+        0x15 => "NumberSequence".into(),
+        0x16 => "ColorSequence".into(),
+        0x17 => "NumberRange".into(),
+        0x18 => "Rect".into(),
+        0x19 => "PhysicalProperties".into(),
+        0x1a => "Color3uint8".into(),
+        0x1c => "SharedString".into(),
+        x => format!("type 0x{:02x}", x),
+    }
+}
+
+fn read_binary(data: &[u8]) -> Result<Model, String> {
+    let mut reader = Reader { data, pos: 0 };
+    // The magic, the version, the number of classes and instances, and reserved bytes.
+    reader.bytes(32)?;
+    let mut classes: HashMap<u32, Vec<i32>> = HashMap::new();
+    let mut instances: HashMap<i32, ModelFile> = HashMap::new();
+    let mut parents: Vec<(i32, i32)> = Vec::new();
+    let mut unsupported = BTreeSet::new();
+    loop {
+        let name = reader.array::<4>()?;
+        let compressed = reader.u32()? as usize;
+        let size = reader.u32()? as usize;
+        reader.bytes(4)?;
+        let payload = match compressed {
+            0 => reader.bytes(size)?.to_vec(),
+            _ => {
+                let compressed = reader.bytes(compressed)?;
+                if compressed.starts_with(ZSTD_MAGIC) {
+                    return Err(String::from(
+                        "chunks compressed with Zstandard are not supported",
+                    ));
+                }
+                decompress_lz4(compressed, size)?
+            }
+        };
+        let mut chunk = Reader {
+            data: &payload,
+            pos: 0,
+        };
+        match &name {
+            b"INST" => {
+                let class = chunk.u32()?;
+                let class_name = chunk.string()?;
+                // Services carry one more byte per instance, telling them apart.
+                chunk.u8()?;
+                let count = chunk.u32()? as usize;
+                let referents = chunk.referents(count)?;
+                for referent in referents.iter() {
+                    instances.insert(*referent, new_instance(class_name.clone()));
+                }
+                classes.insert(class, referents);
+            }
+            b"PROP" => {
+                let class = chunk.u32()?;
+                let property = chunk.string()?;
+                let value_type = chunk.u8()?;
+                let referents = classes
+                    .get(&class)
+                    .ok_or_else(|| format!("{} belongs to an unknown class", property))?;
+                match binary_values(&mut chunk, value_type, referents.len())? {
+                    Some(values) => {
+                        for (referent, value) in referents.iter().zip(values) {
+                            if let Some(instance) = instances.get_mut(referent) {
+                                set_property(instance, property.clone(), value);
+                            }
+                        }
+                    }
+                    None => {
+                        unsupported.insert(binary_type_name(value_type));
+                    }
+                }
+            }
+            b"PRNT" => {
+                chunk.u8()?;
+                let count = chunk.u32()? as usize;
+                let children = chunk.referents(count)?;
+                let parent_referents = chunk.referents(count)?;
+                parents.extend(children.into_iter().zip(parent_referents));
+            }
+            b"END\0" => break,
+            _ => {}
+        }
+    }
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (child, parent) in parents {
+        match parent {
+            -1 => roots.push(child),
+            parent => children.entry(parent).or_default().push(child),
+        }
+    }
+    fn take(
+        referent: i32,
+        instances: &mut HashMap<i32, ModelFile>,
+        children: &mut HashMap<i32, Vec<i32>>,
+    ) -> Option<ModelFile> {
+        let mut instance = instances.remove(&referent)?;
+        for child in children.remove(&referent).unwrap_or_default() {
+            instance.children.extend(take(child, instances, children));
+        }
+        Some(instance)
+    }
+    Ok(Model {
+        roots: roots
+            .into_iter()
+            .filter_map(|x| take(x, &mut instances, &mut children))
+            .collect(),
+        unsupported,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{basic_rotation, decompress_lz4, read_model, BINARY_MAGIC};
+
+    #[test]
+    fn reads_xml_models() {
+        let model = read_model(
+            br#"<?xml version="1.0" encoding="utf-8"?>
+<roblox version="4">
+  <!-- A comment -->
+  <Item class="Folder" referent="RBX0">
+    <Properties>
+      <string name="Name">Map &amp; Spawn</string>
+      <Color3 name="Color"><R>1</R><G>0</G><B>0</B></Color3>
+    </Properties>
+    <Item class="Script" referent="RBX1">
+      <Properties>
+        <string name="Name">Main</string>
+        <bool name="Disabled">true</bool>
+        <ProtectedString name="Source"><![CDATA[print("<hi>")]]></ProtectedString>
+      </Properties>
+    </Item>
+    <Item class="Part" referent="RBX2">
+      <Properties>
+        <token name="Shape">0</token>
+        <float name="Transparency">0.5</float>
+        <Vector3 name="Size"><X>1</X><Y>2</Y><Z>3</Z></Vector3>
+        <CoordinateFrame name="CFrame">
+          <X>4</X><Y>5</Y><Z>6</Z>
+          <R00>1</R00><R01>0</R01><R02>0</R02>
+          <R10>0</R10><R11>1</R11><R12>0</R12>
+          <R20>0</R20><R21>0</R21><R22>1</R22>
+        </CoordinateFrame>
+      </Properties>
+    </Item>
+  </Item>
+</roblox>"#,
+        )
+        .unwrap();
+        assert_eq!(model.roots.len(), 1);
+        let folder = &model.roots[0];
+        assert_eq!(folder.class_name, "Folder");
+        assert_eq!(folder.name.as_deref(), Some("Map & Spawn"));
+        assert!(folder.properties.is_empty());
+        assert_eq!(model.unsupported.iter().collect::<Vec<_>>(), vec!["Color3"]);
+
+        let script = &folder.children[0];
+        assert_eq!(script.name.as_deref(), Some("Main"));
+        assert_eq!(script.properties["Disabled"], json!(true));
+        assert_eq!(script.properties["Source"], json!("print(\"<hi>\")"));
+
+        let part = &folder.children[1];
+        assert_eq!(part.properties["Shape"], json!({ "Enum": 0 }));
+        assert_eq!(part.properties["Transparency"], json!(0.5));
+        assert_eq!(
+            part.properties["Size"],
+            json!({ "Vector3": [1.0, 2.0, 3.0] })
+        );
+        assert_eq!(
+            part.properties["CFrame"],
+            json!({ "CFrame": {
+                "position": [4.0, 5.0, 6.0],
+                "orientation": [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            } })
+        );
+    }
+
+    /// Stores values byte plane by byte plane, the way binary models do.
+    fn interleave<const N: usize>(values: &[[u8; N]]) -> Vec<u8> {
+        (0..N)
+            .flat_map(|plane| values.iter().map(move |x| x[plane]))
+            .collect()
+    }
+
+    fn referents(values: &[i32]) -> Vec<u8> {
+        let mut last = 0;
+        let transformed: Vec<[u8; 4]> = values
+            .iter()
+            .map(|x| {
+                let delta = x - last;
+                last = *x;
+                (((delta << 1) ^ (delta >> 31)) as u32).to_be_bytes()
+            })
+            .collect();
+        interleave(&transformed)
+    }
+
+    fn string(x: &str) -> Vec<u8> {
+        [&(x.len() as u32).to_le_bytes()[..], x.as_bytes()].concat()
+    }
+
+    fn chunk(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        [
+            &name[..],
+            &0u32.to_le_bytes(),
+            &(payload.len() as u32).to_le_bytes(),
+            &[0; 4],
+            payload,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn reads_binary_models() {
+        let mut data = BINARY_MAGIC.to_vec();
+        data.resize(32, 0);
+        let instance_chunk = |class: u32, name: &str, referents_: &[i32]| {
+            let payload = [
+                &class.to_le_bytes()[..],
+                &string(name),
+                &[0],
+                &(referents_.len() as u32).to_le_bytes(),
+                &referents(referents_),
+            ]
+            .concat();
+            chunk(b"INST", &payload)
+        };
+        let property_chunk = |class: u32, name: &str, value_type: u8, values: &[u8]| {
+            let payload = [
+                &class.to_le_bytes()[..],
+                &string(name),
+                &[value_type],
+                values,
+            ]
+            .concat();
+            chunk(b"PROP", &payload)
+        };
+        data.extend(instance_chunk(0, "Folder", &[0]));
+        data.extend(instance_chunk(1, "Part", &[1, 2]));
+        data.extend(property_chunk(0, "Name", 0x01, &string("Map")));
+        data.extend(property_chunk(
+            1,
+            "Name",
+            0x01,
+            &[string("Floor"), string("Wall")].concat(),
+        ));
+        data.extend(property_chunk(1, "Anchored", 0x02, &[1, 0]));
+        // 1.5 and -2 with the sign bit moved to the lowest bit.
+        let floats: Vec<[u8; 4]> = [1.5f32, -2.0]
+            .iter()
+            .map(|x| x.to_bits().rotate_left(1).to_be_bytes())
+            .collect();
+        data.extend(property_chunk(
+            1,
+            "Transparency",
+            0x04,
+            &interleave(&floats),
+        ));
+        let zero = [0u8; 4];
+        let vectors = [
+            interleave(&floats),
+            interleave(&[zero, zero]),
+            interleave(&floats),
+        ]
+        .concat();
+        data.extend(property_chunk(
+            1,
+            "CFrame",
+            0x10,
+            &[&[0x02, 0x07][..], &vectors].concat(),
+        ));
+        data.extend(property_chunk(1, "BrickColor", 0x0b, &[0; 8]));
+        let prnt = [
+            &[0][..],
+            &3u32.to_le_bytes(),
+            &referents(&[0, 1, 2]),
+            &referents(&[-1, 0, 0]),
+        ]
+        .concat();
+        data.extend(chunk(b"PRNT", &prnt));
+        data.extend(chunk(b"END\0", b"</roblox>"));
+
+        let model = read_model(&data).unwrap();
+        assert_eq!(model.roots.len(), 1);
+        let folder = &model.roots[0];
+        assert_eq!(folder.class_name, "Folder");
+        assert_eq!(folder.name.as_deref(), Some("Map"));
+        let names: Vec<_> = folder.children.iter().map(|x| x.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("Floor"), Some("Wall")]);
+        let wall = &folder.children[1];
+        assert_eq!(wall.properties["Anchored"], json!(false));
+        assert_eq!(wall.properties["Transparency"], json!(-2.0));
+        assert_eq!(
+            wall.properties["CFrame"],
+            json!({ "CFrame": {
+                "position": [-2.0, 0.0, -2.0],
+                "orientation": [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+            } })
+        );
+        assert_eq!(
+            model.unsupported.iter().collect::<Vec<_>>(),
+            vec!["BrickColor"]
+        );
+    }
+
+    #[test]
+    fn decompresses_lz4_blocks() {
+        // "abc", then 9 bytes copied from 3 back, then "!".
+        let block = [0x35, b'a', b'b', b'c', 3, 0, 0x10, b'!'];
+        assert_eq!(decompress_lz4(&block, 13).unwrap(), b"abcabcabcabc!");
+        assert!(decompress_lz4(&block, 12).is_err());
+        assert!(decompress_lz4(&[0x05, 1, 0], 4).is_err());
+    }
+
+    #[test]
+    fn basic_rotations_are_axis_aligned() {
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        assert_eq!(basic_rotation(0x02), Some(identity));
+        assert_eq!(
+            basic_rotation(0x03),
+            Some([[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]])
+        );
+        assert_eq!(basic_rotation(0x01), None);
+        assert_eq!(basic_rotation(0x25), None);
+    }
+}
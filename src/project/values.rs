@@ -0,0 +1,126 @@
+use r2g_mlua::prelude::*;
+use serde_json::Value;
+
+use crate::core::get_class_registry;
+use crate::userdata::enums::LuaEnums;
+use crate::userdata::{CFrame, Vector2, Vector3};
+
+/// Finds the type of a property of a class or one of its superclasses in the API dump.
+pub(crate) fn property_type(class_name: &str, property: &str) -> Option<String> {
+    let registry = get_class_registry();
+    let mut current = Some(class_name);
+    while let Some(name) = current {
+        let class = registry
+            .get_reflected(name)
+            .or_else(|| registry.get(name))?;
+        if let Some(x) = class.properties.iter().find(|x| x.name == property) {
+            return Some(x.value_type.clone());
+        }
+        current = class.superclass.as_deref();
+    }
+    None
+}
+
+fn unsupported(value: &Value, value_type: &str) -> LuaError {
+    LuaError::RuntimeError(format!("cannot read {} as a {}", value, value_type))
+}
+
+fn numbers<const N: usize>(value: &Value, value_type: &str) -> LuaResult<[f64; N]> {
+    let array = value
+        .as_array()
+        .filter(|x| x.len() == N)
+        .ok_or_else(|| unsupported(value, value_type))?;
+    let mut out = [0.0; N];
+    for (out, x) in out.iter_mut().zip(array) {
+        *out = x.as_f64().ok_or_else(|| unsupported(value, value_type))?;
+    }
+    Ok(out)
+}
+
+/// Turns a property value of a project or model file into a Lua value.
+/// Values are either implicit, `true` or `[1, 2, 3]`, and then read as `value_type`, the type of the
+/// property in the API dump, or explicit, `{"Vector3": [1, 2, 3]}`.
+pub(crate) fn json_to_lua(
+    lua: &Lua,
+    value: &Value,
+    value_type: Option<&str>,
+) -> LuaResult<LuaValue> {
+    if let Some((explicit_type, inner)) = value
+        .as_object()
+        .filter(|x| x.len() == 1)
+        .and_then(|x| x.iter().next())
+    {
+        // Enum values only carry their number, the enum comes from the property.
+        let explicit_type = match (explicit_type.as_str(), value_type) {
+            ("Enum", Some(x)) => x,
+            (x, _) => x,
+        };
+        return typed_to_lua(lua, inner, explicit_type);
+    }
+    match value_type {
+        Some(x) => typed_to_lua(lua, value, x),
+        None => match value {
+            Value::Bool(x) => Ok(LuaValue::Boolean(*x)),
+            Value::Number(x) => Ok(LuaValue::Number(x.as_f64().unwrap_or_default())),
+            Value::String(x) => lua.create_string(x).map(LuaValue::String),
+            _ => Err(unsupported(value, "property")),
+        },
+    }
+}
+
+fn typed_to_lua(lua: &Lua, value: &Value, value_type: &str) -> LuaResult<LuaValue> {
+    match (value_type, value) {
+        ("bool" | "Bool", Value::Bool(x)) => Ok(LuaValue::Boolean(*x)),
+        (
+            "string" | "String" | "ProtectedString" | "Content" | "BinaryString",
+            Value::String(x),
+        ) => lua.create_string(x).map(LuaValue::String),
+        ("int" | "int64" | "Int32" | "Int64", Value::Number(x)) if x.is_i64() => {
+            Ok(LuaValue::Integer(x.as_i64().unwrap() as _))
+        }
+        ("float" | "double" | "Float32" | "Float64", Value::Number(x)) => {
+            Ok(LuaValue::Number(x.as_f64().unwrap()))
+        }
+        ("Vector2", _) => {
+            let [x, y] = numbers(value, value_type)?;
+            Vector2::new(x, y).into_lua(lua)
+        }
+        ("Vector3", _) => {
+            let [x, y, z] = numbers(value, value_type)?;
+            Vector3::new(x, y, z).into_lua(lua)
+        }
+        ("CFrame", Value::Object(x)) => {
+            let [px, py, pz] = numbers(
+                x.get("position")
+                    .ok_or_else(|| unsupported(value, value_type))?,
+                value_type,
+            )?;
+            let rows = x
+                .get("orientation")
+                .and_then(Value::as_array)
+                .filter(|x| x.len() == 3)
+                .ok_or_else(|| unsupported(value, value_type))?;
+            let [r0, r1, r2] = [
+                numbers::<3>(&rows[0], value_type)?,
+                numbers::<3>(&rows[1], value_type)?,
+                numbers::<3>(&rows[2], value_type)?,
+            ];
+            CFrame::new_rot_matrix(
+                (px, py, pz),
+                (
+                    (r0[0], r0[1], r0[2]),
+                    (r1[0], r1[1], r1[2]),
+                    (r2[0], r2[1], r2[2]),
+                ),
+            )
+            .into_lua(lua)
+        }
+        (_, Value::String(x)) => LuaEnums::item_from_name(lua, value_type, x)?
+            .ok_or_else(|| unsupported(value, value_type)),
+        (_, Value::Number(x)) if x.as_i64().is_some_and(|x| i32::try_from(x).is_ok()) => {
+            LuaEnums::item_from_value(lua, value_type, x.as_i64().unwrap() as i32)?
+                .ok_or_else(|| unsupported(value, value_type))
+        }
+        _ => Err(unsupported(value, value_type)),
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use r2g_mlua::prelude::*;
+
+use super::{
+    find_child, find_or_create_folder, find_path, reload_script, replace_instance, ScriptKind,
+};
+use crate::core::{create_instance, ManagedInstance, WeakManagedInstance};
+use crate::instance::IBaseScript;
+
+/// A script file that was added, edited or deleted since the previous scan.
+/// `path` holds the names of the instances leading to the script, from the watched instance.
+#[derive(Clone, Debug)]
+pub enum SourceChange {
+    Changed {
+        path: Vec<String>,
+        kind: ScriptKind,
+        source: String,
+    },
+    Removed {
+        path: Vec<String>,
+        kind: ScriptKind,
+    },
+}
+
+impl SourceChange {
+    fn path(&self) -> &Vec<String> {
+        match self {
+            SourceChange::Changed { path, .. } => path,
+            SourceChange::Removed { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct WatchedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    path: Vec<String>,
+    kind: ScriptKind,
+}
+
+/// Keeps the scripts under an instance in sync with a directory laid out the Rojo way.
+/// Directories become Folders, unless they hold an `init` script which then takes their place.
+///
+/// The directory is polled, a file counts as changed once its modification time or length differ,
+/// so touching a file is enough to reload it. The first poll creates every script.
+#[derive(Debug)]
+pub struct SourceWatcher {
+    root: PathBuf,
+    target: WeakManagedInstance,
+    files: HashMap<PathBuf, WatchedFile>,
+}
+
+impl SourceWatcher {
+    pub fn new(root: PathBuf, target: &ManagedInstance) -> SourceWatcher {
+        SourceWatcher {
+            root,
+            target: target.downgrade(),
+            files: HashMap::new(),
+        }
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    /// Walks the directory, returning what changed since the previous scan.
+    /// Removals come first, then changes from the outermost script in.
+    pub fn scan(&mut self) -> std::io::Result<Vec<SourceChange>> {
+        let mut found = HashMap::new();
+        Self::walk(&self.root, &mut Vec::new(), &mut found)?;
+        let mut removed = Vec::new();
+        for (file, watched) in self.files.iter() {
+            if !found.contains_key(file) {
+                removed.push(SourceChange::Removed {
+                    path: watched.path.clone(),
+                    kind: watched.kind,
+                });
+            }
+        }
+        let mut changed = Vec::new();
+        found.retain(|file, watched| {
+            if self
+                .files
+                .get(file)
+                .is_some_and(|x| x.modified == watched.modified && x.len == watched.len)
+            {
+                return true;
+            }
+            // A file that can't be read yet is likely still being written, it's picked up next time.
+            let Ok(source) = fs::read_to_string(file) else {
+                return false;
+            };
+            changed.push(SourceChange::Changed {
+                path: watched.path.clone(),
+                kind: watched.kind,
+                source,
+            });
+            true
+        });
+        self.files = found;
+        changed.sort_by(|a, b| {
+            a.path()
+                .len()
+                .cmp(&b.path().len())
+                .then_with(|| a.path().cmp(b.path()))
+        });
+        removed.extend(changed);
+        Ok(removed)
+    }
+    fn walk(
+        dir: &Path,
+        path: &mut Vec<String>,
+        found: &mut HashMap<PathBuf, WatchedFile>,
+    ) -> std::io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                path.push(name);
+                Self::walk(&entry.path(), path, found)?;
+                path.pop();
+                continue;
+            }
+            let Some((script_name, kind)) = ScriptKind::of_file(&name) else {
+                continue;
+            };
+            let mut script_path = path.clone();
+            if !script_name.is_empty() {
+                script_path.push(script_name.into());
+            } else if path.is_empty() {
+                // The watched instance already exists, an `init` script at the root can't replace it.
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            found.insert(
+                entry.path(),
+                WatchedFile {
+                    modified: metadata.modified().ok(),
+                    len: metadata.len(),
+                    path: script_path,
+                    kind,
+                },
+            );
+        }
+        Ok(())
+    }
+    /// Brings the instances in line with `changes`. Edited scripts get restarted, edited modules are
+    /// required again by everything that required them before.
+    pub fn apply(&self, lua: &Lua, changes: Vec<SourceChange>) -> LuaResult<()> {
+        let Some(target) = self.target.upgrade() else {
+            return Ok(());
+        };
+        for change in changes {
+            match change {
+                SourceChange::Removed { path, kind } => {
+                    let Some(instance) = find_path(&target, &path)? else {
+                        continue;
+                    };
+                    // Another file may have taken over the name already.
+                    if instance.get_class_name() != kind.class_name() {
+                        continue;
+                    }
+                    if instance.get_children()?.is_empty() {
+                        instance.destroy(lua)?;
+                    } else {
+                        replace_instance(lua, &instance, "Folder")?;
+                    }
+                }
+                SourceChange::Changed { path, kind, source } => {
+                    let (name, parents) = path.split_last().unwrap();
+                    let mut parent = target.clone();
+                    for x in parents {
+                        parent = find_or_create_folder(lua, &parent, x)?;
+                    }
+                    let existing = find_child(&parent, name)?;
+                    if let Some(script) = existing
+                        .as_ref()
+                        .filter(|x| x.get_class_name() == kind.class_name())
+                    {
+                        reload_script(lua, script, source)?;
+                        continue;
+                    }
+                    let script = match existing {
+                        Some(x) => replace_instance(lua, &x, kind.class_name())?,
+                        None => {
+                            let script = create_instance(lua, kind.class_name())?;
+                            script.set_name(name.clone())?;
+                            script.set_parent(lua, Some(parent))?;
+                            script
+                        }
+                    };
+                    reload_script(lua, &script, source)?;
+                    if let Ok(script) = script.cast_from_unsized::<dyn IBaseScript>() {
+                        script.set_disabled(lua, false)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Scans the directory and applies what changed, returning how many files did.
+    pub fn poll(&mut self, lua: &Lua) -> LuaResult<usize> {
+        let changes = self.scan().map_err(|err| {
+            LuaError::RuntimeError(format!("failed to scan {}: {}", self.root.display(), err))
+        })?;
+        let count = changes.len();
+        self.apply(lua, changes)?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::SourceWatcher;
+    use crate::core::{ManagedInstance, RblxVM};
+    use crate::instance::{IBaseScript, IModuleScript};
+    use crate::project::find_path;
+
+    #[test]
+    fn reloads_edited_scripts() {
+        let root = std::env::temp_dir().join(format!("rblx-godot-watcher-{}", std::process::id()));
+        fs::create_dir_all(root.join("lib")).unwrap();
+        fs::write(root.join("Main.server.luau"), "print('first')").unwrap();
+        fs::write(root.join("lib/Util.luau"), "return 1").unwrap();
+
+        let mut vm = RblxVM::new_for_test();
+        let vm = vm.get_mut();
+        let workspace: ManagedInstance = vm.get_workspace().cast_from_sized().unwrap();
+        let lua = vm.get_main_state().get_lua().clone();
+        let mut watcher = SourceWatcher::new(root.clone(), &workspace);
+        let source_of = |path: &[&str]| {
+            let script = find_path(&workspace, path).unwrap().unwrap();
+            match script.clone().cast_from_unsized::<dyn IBaseScript>() {
+                Ok(x) => x.get_source(),
+                Err(_) => script
+                    .cast_from_unsized::<dyn IModuleScript>()
+                    .unwrap()
+                    .get_source(),
+            }
+        };
+
+        let first = watcher.poll(&lua);
+        let first_source = source_of(&["Main"]);
+        // The length changes along with the contents, file times may be too coarse to tell the edit apart.
+        fs::write(root.join("Main.server.luau"), "print('second edit')").unwrap();
+        let second = watcher.poll(&lua);
+        let main_source = source_of(&["Main"]);
+        fs::remove_file(root.join("lib/Util.luau")).unwrap();
+        let third = watcher.poll(&lua);
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(first.unwrap(), 2);
+        assert_eq!(first_source, "print('first')");
+        assert_eq!(second.unwrap(), 1);
+        assert_eq!(main_source, "print('second edit')");
+        assert_eq!(third.unwrap(), 1);
+        assert!(find_path(&workspace, &["lib", "Util"]).unwrap().is_none());
+        assert_eq!(source_of(&["Main"]), "print('second edit')");
+    }
+}
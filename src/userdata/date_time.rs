@@ -0,0 +1,562 @@
+use godot::classes::Time;
+use r2g_mlua::prelude::*;
+
+use super::LuaSingleton;
+use crate::core::get_state;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+/// The optional year, month, day, hour, minute, second and millisecond the constructors take.
+type ComponentArgs = (
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+);
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// The [`DateTime`](https://create.roblox.com/docs/en-us/reference/engine/datatypes/DateTime) data type represents a moment in time using a Unix timestamp, with millisecond precision.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub struct DateTime {
+    pub unix_timestamp_millis: i64,
+}
+
+from_lua_copy_impl!(DateTime);
+
+/// Broken down date and time components, as returned by `DateTime:ToUniversalTime()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DateTimeComponents {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millisecond: u32,
+    pub weekday: u32,
+    /// The moment the components describe, the `X` and `x` format tokens give it whatever the time zone.
+    pub unix_timestamp_millis: i64,
+}
+
+// Days since 1970-01-01 from a proleptic Gregorian date (Howard Hinnant's algorithm), `None` if the year is too far
+// out for an i64.
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    let year = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy =
+        ((153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5).checked_add(day - 1)?;
+    let doe = (yoe * 365 + yoe / 4 - yoe / 100).checked_add(doy)?;
+    era.checked_mul(146097)?
+        .checked_add(doe)?
+        .checked_sub(719468)
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+impl DateTimeComponents {
+    pub fn from_millis(millis: i64) -> DateTimeComponents {
+        let days = millis.div_euclid(MILLIS_PER_DAY);
+        let ms_of_day = millis.rem_euclid(MILLIS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        DateTimeComponents {
+            year,
+            month,
+            day,
+            hour: (ms_of_day / 3_600_000) as u32,
+            minute: (ms_of_day / 60_000 % 60) as u32,
+            second: (ms_of_day / 1000 % 60) as u32,
+            millisecond: (ms_of_day % 1000) as u32,
+            weekday: (days + 4).rem_euclid(7) as u32,
+            unix_timestamp_millis: millis,
+        }
+    }
+    pub fn to_lua_table(&self, lua: &Lua) -> LuaResult<LuaTable> {
+        let table = lua.create_table()?;
+        table.raw_set("Year", self.year)?;
+        table.raw_set("Month", self.month)?;
+        table.raw_set("Day", self.day)?;
+        table.raw_set("Hour", self.hour)?;
+        table.raw_set("Minute", self.minute)?;
+        table.raw_set("Second", self.second)?;
+        table.raw_set("Millisecond", self.millisecond)?;
+        Ok(table)
+    }
+    /// Formats the components using the Roblox (moment.js style) format tokens, in the `en-us` locale.
+    pub fn format(&self, format: &str) -> String {
+        const TOKENS: [&str; 41] = [
+            "LLLL", "llll", "LLL", "lll", "LTS", "LL", "ll", "LT", "L", "l", "YYYY", "YY", "MMMM",
+            "MMM", "MM", "Mo", "M", "DDDD", "DDD", "DD", "Do", "D", "dddd", "ddd", "dd", "d", "HH",
+            "H", "hh", "h", "mm", "m", "ss", "s", "SSS", "SS", "S", "A", "a", "X", "x",
+        ];
+        let mut output = String::new();
+        let mut rest = format;
+        while !rest.is_empty() {
+            if let Some(stripped) = rest.strip_prefix('[') {
+                if let Some(end) = stripped.find(']') {
+                    output.push_str(&stripped[..end]);
+                    rest = &stripped[end + 1..];
+                    continue;
+                }
+            }
+            if let Some(token) = TOKENS.iter().find(|x| rest.starts_with(**x)) {
+                output.push_str(&self.format_token(token));
+                rest = &rest[token.len()..];
+            } else {
+                let c = rest.chars().next().unwrap();
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        output
+    }
+    fn day_of_year(&self) -> i64 {
+        // Both fit, the year comes from a timestamp.
+        days_from_civil(self.year, self.month as i64, self.day as i64).unwrap()
+            - days_from_civil(self.year, 1, 1).unwrap()
+            + 1
+    }
+    fn hour12(&self) -> u32 {
+        match self.hour % 12 {
+            0 => 12,
+            x => x,
+        }
+    }
+    fn format_token(&self, token: &str) -> String {
+        let month_name = MONTH_NAMES[self.month as usize - 1];
+        let weekday_name = WEEKDAY_NAMES[self.weekday as usize];
+        match token {
+            "LT" => self.format("h:mm A"),
+            "LTS" => self.format("h:mm:ss A"),
+            "L" => self.format("MM/DD/YYYY"),
+            "l" => self.format("M/D/YYYY"),
+            "LL" => self.format("MMMM D, YYYY"),
+            "ll" => self.format("MMM D, YYYY"),
+            "LLL" => self.format("MMMM D, YYYY h:mm A"),
+            "lll" => self.format("MMM D, YYYY h:mm A"),
+            "LLLL" => self.format("dddd, MMMM D, YYYY h:mm A"),
+            "llll" => self.format("ddd, MMM D, YYYY h:mm A"),
+            "YYYY" => format!("{:04}", self.year),
+            "YY" => format!("{:02}", self.year.rem_euclid(100)),
+            "MMMM" => month_name.into(),
+            "MMM" => month_name[..3].into(),
+            "MM" => format!("{:02}", self.month),
+            "Mo" => ordinal(self.month as i64),
+            "M" => self.month.to_string(),
+            "DDDD" => format!("{:03}", self.day_of_year()),
+            "DDD" => self.day_of_year().to_string(),
+            "DD" => format!("{:02}", self.day),
+            "Do" => ordinal(self.day as i64),
+            "D" => self.day.to_string(),
+            "dddd" => weekday_name.into(),
+            "ddd" => weekday_name[..3].into(),
+            "dd" => weekday_name[..2].into(),
+            "d" => self.weekday.to_string(),
+            "HH" => format!("{:02}", self.hour),
+            "H" => self.hour.to_string(),
+            "hh" => format!("{:02}", self.hour12()),
+            "h" => self.hour12().to_string(),
+            "mm" => format!("{:02}", self.minute),
+            "m" => self.minute.to_string(),
+            "ss" => format!("{:02}", self.second),
+            "s" => self.second.to_string(),
+            "SSS" => format!("{:03}", self.millisecond),
+            "SS" => format!("{:02}", self.millisecond / 10),
+            "S" => (self.millisecond / 100).to_string(),
+            "A" => if self.hour < 12 { "AM" } else { "PM" }.into(),
+            "a" => if self.hour < 12 { "am" } else { "pm" }.into(),
+            "X" => self.unix_timestamp_millis.div_euclid(1000).to_string(),
+            "x" => self.unix_timestamp_millis.to_string(),
+            _ => token.into(),
+        }
+    }
+}
+
+fn ordinal(n: i64) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+impl DateTime {
+    pub const fn from_unix_timestamp_millis(millis: i64) -> DateTime {
+        DateTime {
+            unix_timestamp_millis: millis,
+        }
+    }
+    pub fn from_unix_timestamp(seconds: f64) -> DateTime {
+        DateTime::from_unix_timestamp_millis((seconds * 1000.0).floor() as i64)
+    }
+    /// The current time of the VM `lua` belongs to, which only moves with frames if its clock is manual.
+    pub fn now(lua: &Lua) -> DateTime {
+        DateTime::from_unix_timestamp(get_state(lua).get_unix_time())
+    }
+    /// Builds a DateTime from date components, normalizing out of range values like Roblox does. Errors if the
+    /// moment doesn't fit a timestamp.
+    pub fn from_components(
+        year: i64,
+        month: i64,
+        day: i64,
+        hour: i64,
+        minute: i64,
+        second: i64,
+        millisecond: i64,
+    ) -> LuaResult<DateTime> {
+        let millis = month
+            .checked_sub(1)
+            .and_then(|month0| {
+                let year = year.checked_add(month0.div_euclid(12))?;
+                days_from_civil(year, month0.rem_euclid(12) + 1, 1)
+            })
+            .and_then(|x| x.checked_add(day.checked_sub(1)?))
+            .and_then(|x| x.checked_mul(MILLIS_PER_DAY))
+            .and_then(|start| {
+                [
+                    (hour, 3_600_000),
+                    (minute, 60_000),
+                    (second, 1000),
+                    (millisecond, 1),
+                ]
+                .into_iter()
+                .try_fold(start, |total, (x, unit)| {
+                    total.checked_add(x.checked_mul(unit)?)
+                })
+            });
+        millis
+            .map(DateTime::from_unix_timestamp_millis)
+            .ok_or_else(|| LuaError::RuntimeError("the date is out of range".into()))
+    }
+    /// Parses an ISO 8601 date, like `2020-01-02T10:30:45Z` or `2020-01-02T10:30:45.123+02:00`.
+    pub fn from_iso_date(date: &str) -> Option<DateTime> {
+        fn number(s: &str, range: std::ops::Range<usize>) -> Option<i64> {
+            let part = s.get(range)?;
+            if part.bytes().all(|x| x.is_ascii_digit()) {
+                part.parse().ok()
+            } else {
+                None
+            }
+        }
+        let date = date.trim();
+        let bytes = date.as_bytes();
+        if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+            return None;
+        }
+        let year = number(date, 0..4)?;
+        let month = number(date, 5..7)?;
+        let day = number(date, 8..10)?;
+        let (mut hour, mut minute, mut second, mut millisecond) = (0, 0, 0, 0);
+        let mut rest = &date[10..];
+        if let Some(time) = rest.strip_prefix(['T', 't', ' ']) {
+            let tb = time.as_bytes();
+            if tb.len() < 5 || tb[2] != b':' {
+                return None;
+            }
+            hour = number(time, 0..2)?;
+            minute = number(time, 3..5)?;
+            rest = &time[5..];
+            if let Some(secs) = rest.strip_prefix(':') {
+                second = number(secs, 0..2)?;
+                rest = &secs[2..];
+                if let Some(frac) = rest.strip_prefix(['.', ',']) {
+                    let digits = frac.bytes().take_while(|x| x.is_ascii_digit()).count();
+                    if digits == 0 {
+                        return None;
+                    }
+                    let padded = format!("{:0<3}", &frac[..digits.min(3)]);
+                    millisecond = padded.parse().ok()?;
+                    rest = &frac[digits..];
+                }
+            }
+        }
+        let offset = match rest {
+            "" | "Z" | "z" => 0,
+            _ => {
+                let sign = match rest.as_bytes()[0] {
+                    b'+' => 1,
+                    b'-' => -1,
+                    _ => return None,
+                };
+                let tz = &rest[1..];
+                let (h, m) = match tz.len() {
+                    2 => (number(tz, 0..2)?, 0),
+                    4 => (number(tz, 0..2)?, number(tz, 2..4)?),
+                    5 if tz.as_bytes()[2] == b':' => (number(tz, 0..2)?, number(tz, 3..5)?),
+                    _ => return None,
+                };
+                sign * (h * 60 + m)
+            }
+        };
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 24
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+        let dt =
+            DateTime::from_components(year, month, day, hour, minute, second, millisecond).ok()?;
+        Some(DateTime::from_unix_timestamp_millis(
+            dt.unix_timestamp_millis - offset * 60_000,
+        ))
+    }
+    pub fn to_universal_time(&self) -> DateTimeComponents {
+        DateTimeComponents::from_millis(self.unix_timestamp_millis)
+    }
+    pub fn to_local_time(&self) -> DateTimeComponents {
+        self.to_time_zone(local_offset_millis())
+    }
+    /// The components in a time zone `offset_millis` ahead of UTC.
+    pub fn to_time_zone(&self, offset_millis: i64) -> DateTimeComponents {
+        DateTimeComponents {
+            unix_timestamp_millis: self.unix_timestamp_millis,
+            ..DateTimeComponents::from_millis(
+                self.unix_timestamp_millis.saturating_add(offset_millis),
+            )
+        }
+    }
+    pub fn to_iso_date(&self) -> String {
+        let c = self.to_universal_time();
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            c.year, c.month, c.day, c.hour, c.minute, c.second
+        )
+    }
+}
+
+/// Offset of the local time zone from UTC, in milliseconds.
+fn local_offset_millis() -> i64 {
+    Time::singleton()
+        .get_time_zone_from_system()
+        .get("bias")
+        .and_then(|x| x.try_to::<i64>().ok())
+        .unwrap_or(0)
+        * 60_000
+}
+
+impl LuaUserData for DateTime {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "DateTime");
+
+        fields.add_field_method_get("UnixTimestamp", |_, this| {
+            Ok(this.unix_timestamp_millis.div_euclid(1000))
+        });
+        fields.add_field_method_get("UnixTimestampMillis", |_, this| {
+            Ok(this.unix_timestamp_millis)
+        });
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("ToUniversalTime", |lua, this, ()| {
+            this.to_universal_time().to_lua_table(lua)
+        });
+        methods.add_method("ToLocalTime", |lua, this, ()| {
+            this.to_local_time().to_lua_table(lua)
+        });
+        methods.add_method("ToIsoDate", |_, this, ()| Ok(this.to_iso_date()));
+        methods.add_method(
+            "FormatUniversalTime",
+            |_, this, (format, _locale): (String, String)| {
+                Ok(this.to_universal_time().format(&format))
+            },
+        );
+        methods.add_method(
+            "FormatLocalTime",
+            |_, this, (format, _locale): (String, String)| Ok(this.to_local_time().format(&format)),
+        );
+        methods.add_meta_method("__tostring", |_, this, ()| Ok(this.to_iso_date()));
+        methods.add_meta_method("__eq", |_, this, other| Ok(*this == other));
+        methods.add_meta_method("__lt", |_, this, other: DateTime| Ok(*this < other));
+        methods.add_meta_method("__le", |_, this, other: DateTime| Ok(*this <= other));
+    }
+}
+
+impl LuaSingleton for DateTime {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "now",
+            lua.create_function(|lua, ()| Ok(DateTime::now(lua)))?,
+        )?;
+        table.raw_set(
+            "fromUnixTimestamp",
+            lua.create_function(|_, seconds: f64| Ok(DateTime::from_unix_timestamp(seconds)))?,
+        )?;
+        table.raw_set(
+            "fromUnixTimestampMillis",
+            lua.create_function(|_, millis: f64| {
+                Ok(DateTime::from_unix_timestamp_millis(millis as i64))
+            })?,
+        )?;
+        table.raw_set(
+            "fromUniversalTime",
+            lua.create_function(
+                |_, (year, month, day, hour, minute, second, millisecond): ComponentArgs| {
+                    DateTime::from_components(
+                        year.unwrap_or(1970),
+                        month.unwrap_or(1),
+                        day.unwrap_or(1),
+                        hour.unwrap_or(0),
+                        minute.unwrap_or(0),
+                        second.unwrap_or(0),
+                        millisecond.unwrap_or(0),
+                    )
+                },
+            )?,
+        )?;
+        table.raw_set(
+            "fromLocalTime",
+            lua.create_function(
+                |_, (year, month, day, hour, minute, second, millisecond): ComponentArgs| {
+                    let dt = DateTime::from_components(
+                        year.unwrap_or(1970),
+                        month.unwrap_or(1),
+                        day.unwrap_or(1),
+                        hour.unwrap_or(0),
+                        minute.unwrap_or(0),
+                        second.unwrap_or(0),
+                        millisecond.unwrap_or(0),
+                    )?;
+                    dt.unix_timestamp_millis
+                        .checked_sub(local_offset_millis())
+                        .map(DateTime::from_unix_timestamp_millis)
+                        .ok_or_else(|| LuaError::RuntimeError("the date is out of range".into()))
+                },
+            )?,
+        )?;
+        table.raw_set(
+            "fromIsoDate",
+            lua.create_function(|_, date: String| Ok(DateTime::from_iso_date(&date)))?,
+        )?;
+        lua.globals().raw_set("DateTime", table)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DateTime;
+
+    fn iso(date: &str) -> Option<i64> {
+        DateTime::from_iso_date(date).map(|x| x.unix_timestamp_millis)
+    }
+
+    #[test]
+    fn parses_iso_dates() {
+        assert_eq!(iso("2020-01-02T10:30:45Z"), Some(1_577_961_045_000));
+        assert_eq!(iso("2020-01-02t10:30:45z"), Some(1_577_961_045_000));
+        assert_eq!(iso("2020-01-02 10:30:45"), Some(1_577_961_045_000));
+        assert_eq!(
+            iso("2020-01-02T10:30:45.123+02:00"),
+            Some(1_577_961_045_123 - 7_200_000)
+        );
+        assert_eq!(
+            iso("2020-01-02T10:30:45,5-0130"),
+            Some(1_577_961_045_500 + 5_400_000)
+        );
+        assert_eq!(iso("2020-01-02T10:30"), Some(1_577_961_000_000));
+        assert_eq!(iso("2020-01-02"), Some(1_577_923_200_000));
+        for invalid in [
+            "2020-13-01",
+            "2020/01/02",
+            "20-01-02",
+            "2020-01-02T1:30",
+            "2020-01-02T10:30:45.Z",
+            "2020-01-02T10:30:45+2",
+            "2020-01-02T10:30:45 UTC",
+        ] {
+            assert_eq!(iso(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn normalizes_components_and_rejects_overflows() {
+        let date = |month, day| {
+            DateTime::from_components(2020, month, day, 0, 0, 0, 0)
+                .unwrap()
+                .to_iso_date()
+        };
+        assert_eq!(date(13, 1), "2021-01-01T00:00:00Z");
+        assert_eq!(date(0, 1), "2019-12-01T00:00:00Z");
+        assert_eq!(date(3, 0), "2020-02-29T00:00:00Z");
+        assert!(DateTime::from_components(i64::MAX, 1, 1, 0, 0, 0, 0).is_err());
+        assert!(DateTime::from_components(2020, i64::MIN, 1, 0, 0, 0, 0).is_err());
+        assert!(DateTime::from_components(2020, 1, i64::MAX, 0, 0, 0, 0).is_err());
+        assert!(DateTime::from_components(2020, 1, 1, 0, 0, 0, i64::MAX).is_err());
+    }
+
+    #[test]
+    fn formats_tokens() {
+        let date = DateTime::from_components(2021, 3, 4, 15, 5, 9, 7).unwrap();
+        let utc = date.to_universal_time();
+        for (format, expected) in [
+            ("LT", "3:05 PM"),
+            ("LTS", "3:05:09 PM"),
+            ("L", "03/04/2021"),
+            ("l", "3/4/2021"),
+            ("LL", "March 4, 2021"),
+            ("ll", "Mar 4, 2021"),
+            ("LLLL", "Thursday, March 4, 2021 3:05 PM"),
+            ("llll", "Thu, Mar 4, 2021 3:05 PM"),
+            ("YYYY YY", "2021 21"),
+            ("MMMM MMM MM Mo M", "March Mar 03 3rd 3"),
+            ("DDDD DDD DD Do D", "063 63 04 4th 4"),
+            ("dddd ddd dd d", "Thursday Thu Th 4"),
+            ("HH H hh h mm m ss s", "15 15 03 3 05 5 09 9"),
+            ("SSS SS S A a", "007 00 0 PM pm"),
+            ("X x", "1614870309 1614870309007"),
+            ("[YYYY Year] YYYY", "YYYY Year 2021"),
+        ] {
+            assert_eq!(utc.format(format), expected, "{}", format);
+        }
+        // What FormatLocalTime shows ten hours ahead of UTC, the timestamp stays the same.
+        let local = date.to_time_zone(10 * 3_600_000);
+        assert_eq!(
+            local.format("dddd Do HH:mm a X"),
+            "Friday 5th 01:05 am 1614870309"
+        );
+    }
+}
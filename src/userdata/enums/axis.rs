@@ -1,8 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum]
-pub enum Axis {
-    X,
-    Y,
-    Z,
-}
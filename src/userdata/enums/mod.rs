@@ -1,29 +1,27 @@
-rblx_godot_derive::create_enums!([
-    AnimatorRetargetingMode,
-    AvatarUnificationMode,
-    ClientAnimatorThrottlingMode,
-    Axis,
-    FluidForces,
-    IKControlConstraintSupport,
-    MeshPartHeadsAndAccessories,
-    MessageType,
-    ModelLevelOfDetail,
-    ModelStreamingBehavior,
-    ModelStreamingMode,
-    MoverConstraintRootBehaviorMode,
-    NormalId,
-    PathfindingUseImprovedSearch,
-    PhysicsSteppingMethod,
-    PlayerCharacterDestroyBehavior,
-    PrimalPhysicsSolver,
-    RejectCharacterDeletions,
-    RenderingCacheOptimizationMode,
-    ReplicateInstanceDestroySetting,
-    RollOutState,
-    RotationOrder,
-    RunContext,
-    SandboxedInstanceMode,
-    SignalBehavior,
-    StreamOutBehavior,
-    StreamingIntegrityMode
-]);
+rblx_godot_derive::api_dump_enums!(
+    "api/API-Dump.json",
+    [
+        AnimatorRetargetingMode(default = Disabled),
+        AvatarUnificationMode(default = Disabled),
+        ClientAnimatorThrottlingMode(default = Disabled),
+        FluidForces(default = Default),
+        IKControlConstraintSupport(default = Disabled),
+        MeshPartHeadsAndAccessories(default = Disabled),
+        ModelLevelOfDetail(default = Disabled, default_name = Automatic),
+        ModelStreamingBehavior(default = Legacy),
+        ModelStreamingMode(default = Atomic),
+        MoverConstraintRootBehaviorMode(default = Disabled),
+        PathfindingUseImprovedSearch(default = Disabled),
+        PhysicsSteppingMethod(default = Fixed),
+        PlayerCharacterDestroyBehavior(default = Enabled),
+        PrimalPhysicsSolver(default = Disabled),
+        RejectCharacterDeletions(default = Disabled),
+        RenderingCacheOptimizationMode(default = Disabled),
+        ReplicateInstanceDestroySetting(default = Enabled),
+        RollOutState(default = Default),
+        SandboxedInstanceMode(default = Default),
+        SignalBehavior(default = Immediate),
+        StreamOutBehavior(default = LowMemory),
+        StreamingIntegrityMode(default = Disabled),
+    ]
+);
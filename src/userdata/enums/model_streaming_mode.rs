@@ -1,10 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=Atomic)]
-pub enum ModelStreamingMode {
-    Default,
-    Atomic,
-    Persistent,
-    PersistentPerPlayer,
-    Nonatomic,
-}
\ No newline at end of file
@@ -1,8 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=Fixed)]
-pub enum PhysicsSteppingMethod {
-    Default,
-    Fixed,
-    Adaptive,
-}
\ No newline at end of file
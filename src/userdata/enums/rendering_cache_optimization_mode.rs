@@ -1,8 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=Disabled)]
-pub enum RenderingCacheOptimizationMode {
-    Default,
-    Disabled,
-    Enabled,
-}
\ No newline at end of file
@@ -1,8 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=Default)]
-pub enum RollOutState {
-    Default,
-    Disabled,
-    Enabled
-}
\ No newline at end of file
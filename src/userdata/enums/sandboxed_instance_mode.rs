@@ -1,7 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=Default)]
-pub enum SandboxedInstanceMode {
-    Default,
-    Experimental
-}
@@ -1,8 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=LowMemory)]
-pub enum StreamOutBehavior {
-    Default,
-    LowMemory,
-    Opportunistic
-}
\ No newline at end of file
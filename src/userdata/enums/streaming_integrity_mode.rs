@@ -1,9 +0,0 @@
-use rblx_godot_derive::lua_enum;
-
-#[lua_enum(default=Disabled)]
-pub enum StreamingIntegrityMode {
-    Default,
-    Disabled,
-    MinimumRadiusPause,
-    PauseOutsideLoadedArea
-}
\ No newline at end of file
@@ -8,11 +8,10 @@ use std::{
     task::{Context, Poll},
 };
 
-use super::from_lua_clone_impl;
 use crate::core::{
-    get_state, get_state_with_rwlock, get_task_scheduler_from_lua, FastFlag,
-    InstanceCreationMetadata, InstanceCreationSignalList, LuauState, ParallelDispatch, Trc,
-    TrcReadLock, TrcWriteLock, Weak,
+    get_current_identity, get_state, get_state_with_rwlock, get_task_scheduler_from_lua, FastFlag,
+    InstanceCreationMetadata, InstanceCreationSignalList, LuauState, ParallelDispatch,
+    ThreadIdentity, Trc, TrcReadLock, TrcWriteLock, Weak,
 };
 use r2g_mlua::prelude::*;
 pub type ManagedRBXScriptSignal = Trc<RBXScriptSignal>;
@@ -28,6 +27,8 @@ struct SignalCallback {
     state: Trc<LuauState>,
     once: bool,
     parallel: ParallelDispatch,
+    /// The identity of the thread that connected, the callback runs with it whoever fires the signal.
+    identity: Option<ThreadIdentity>,
 }
 #[derive(Debug)]
 pub struct RBXScriptSignal {
@@ -77,6 +78,7 @@ impl RBXScriptSignal {
                 state: get_state_with_rwlock(lua).clone(),
                 once: false,
                 parallel: parallel,
+                identity: get_current_identity(lua).map(|x| x.clone()),
             },
         );
         Ok(RBXScriptConnection {
@@ -107,6 +109,7 @@ impl RBXScriptSignal {
                 state: get_state_with_rwlock(lua).clone(),
                 once: true,
                 parallel: parallel,
+                identity: get_current_identity(lua).map(|x| x.clone()),
             },
         );
         Ok(RBXScriptConnection {
@@ -128,8 +131,16 @@ impl RBXScriptSignal {
         let fire_behavior = get_state(lua).flags().get_int(FastFlag::SignalBehavior);
         for (id, callback) in callbacks_clone {
             let _ = match fire_behavior {
-                0 | 1 | 3 => task.spawn_func(lua, callback.func, args.clone()),
-                2 => task.defer_func(lua, callback.func, args.clone(), callback.parallel),
+                0 | 1 | 3 => {
+                    task.spawn_func_as(lua, callback.func, args.clone(), callback.identity)
+                }
+                2 => task.defer_func_as(
+                    lua,
+                    callback.func,
+                    args.clone(),
+                    callback.parallel,
+                    callback.identity,
+                ),
                 _ => unreachable!(),
             };
             if callback.once {
@@ -158,8 +169,14 @@ impl RBXScriptSignal {
         let fire_behavior = get_state(lua).flags().get_int(FastFlag::SignalBehavior);
         for (id, callback) in callbacks_clone {
             let _ = match fire_behavior {
-                0 | 1 => task.spawn_func(lua, callback.func, args.clone()),
-                2 | 3 => task.defer_func(lua, callback.func, args.clone(), callback.parallel),
+                0 | 1 => task.spawn_func_as(lua, callback.func, args.clone(), callback.identity),
+                2 | 3 => task.defer_func_as(
+                    lua,
+                    callback.func,
+                    args.clone(),
+                    callback.parallel,
+                    callback.identity,
+                ),
                 _ => unreachable!(),
             };
             if callback.once {
@@ -174,6 +191,36 @@ impl RBXScriptSignal {
         }
         Ok(())
     }
+    /// Spawns every callback right away whatever the SignalBehavior is, returning their threads so the caller can
+    /// tell once they're done.
+    pub(crate) fn spawn_all(
+        mut self: TrcWriteLock<'_, RBXScriptSignal>,
+        lua: &Lua,
+        args: impl IntoLuaMulti,
+    ) -> LuaResult<Vec<LuaThread>> {
+        let args = args.into_lua_multi(lua)?;
+        let task =
+            get_task_scheduler_from_lua(unsafe { (lua as *const Lua).as_ref().unwrap_unchecked() });
+        let callbacks = take(&mut self.callbacks);
+        self.callbacks.extend(
+            callbacks
+                .iter()
+                .filter(|x| !x.1.once)
+                .map(|(id, x)| (*id, x.clone())),
+        );
+        let release = self.guard_release();
+        let mut threads = Vec::with_capacity(callbacks.len());
+        for (_, callback) in callbacks {
+            threads.push(task.spawn_func_as(
+                lua,
+                callback.func,
+                args.clone(),
+                callback.identity,
+            )?);
+        }
+        drop(release);
+        Ok(threads)
+    }
     pub fn wait<'a>(self: TrcReadLock<'_, Self>, lua: &'a Lua) -> RBXScriptSignalFuture {
         RBXScriptSignalFuture {
             future: Rc::new(RefCell::new(InnerRBXScriptSignalFuture {
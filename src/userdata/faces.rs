@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use r2g_mlua::prelude::*;
+
+use super::{enums::NormalId, LuaSingleton};
+
+/// The [`Faces`](https://create.roblox.com/docs/en-us/reference/engine/datatypes/Faces) data type contains six booleans representing whether a feature is enabled on each face (`NormalId`) of a Part or other 3D object.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Faces {
+    pub top: bool,
+    pub bottom: bool,
+    pub left: bool,
+    pub right: bool,
+    pub back: bool,
+    pub front: bool,
+}
+
+from_lua_copy_impl!(Faces);
+
+impl Faces {
+    pub fn set(&mut self, normal: NormalId, value: bool) {
+        match normal {
+            NormalId::Right => self.right = value,
+            NormalId::Top => self.top = value,
+            NormalId::Back => self.back = value,
+            NormalId::Left => self.left = value,
+            NormalId::Bottom => self.bottom = value,
+            NormalId::Front => self.front = value,
+        }
+    }
+}
+
+impl LuaUserData for Faces {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "Faces");
+
+        fields.add_field_method_get("Top", |_, this| Ok(this.top));
+        fields.add_field_method_get("Bottom", |_, this| Ok(this.bottom));
+        fields.add_field_method_get("Left", |_, this| Ok(this.left));
+        fields.add_field_method_get("Right", |_, this| Ok(this.right));
+        fields.add_field_method_get("Back", |_, this| Ok(this.back));
+        fields.add_field_method_get("Front", |_, this| Ok(this.front));
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            let names: Vec<&str> = [
+                (this.right, "Right"),
+                (this.top, "Top"),
+                (this.back, "Back"),
+                (this.left, "Left"),
+                (this.bottom, "Bottom"),
+                (this.front, "Front"),
+            ]
+            .into_iter()
+            .filter_map(|(enabled, name)| enabled.then_some(name))
+            .collect();
+            Ok(names.join(", "))
+        });
+        methods.add_meta_method("__eq", |_, this, other| Ok(*this == other));
+    }
+}
+
+impl LuaSingleton for Faces {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "new",
+            lua.create_function(|_lua, mult: LuaMultiValue| {
+                let mut faces = Faces::default();
+                for (i, value) in mult.into_iter().enumerate() {
+                    let normal = value
+                        .as_userdata()
+                        .and_then(|userdata| userdata.borrow::<NormalId>().ok().map(|x| *x));
+                    if let Some(normal) = normal {
+                        faces.set(normal, true);
+                    } else {
+                        return Err(LuaError::BadArgument {
+                            to: Some("Faces::new".into()),
+                            pos: i + 1,
+                            name: None,
+                            cause: Arc::new(LuaError::FromLuaConversionError {
+                                from: value.type_name(),
+                                to: "Enum.NormalId".into(),
+                                message: None,
+                            }),
+                        });
+                    }
+                }
+                Ok(faces)
+            })?,
+        )?;
+        lua.globals().raw_set("Faces", table)?;
+        Ok(())
+    }
+}
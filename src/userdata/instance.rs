@@ -1,9 +1,9 @@
 use r2g_mlua::prelude::*;
 
-use crate::{
-    core::{get_state, lua_macros::lua_getter, DynInstance, ManagedInstance},
-    instance::{Actor, LocalScript, Model, ModuleScript, Script},
+use crate::core::{
+    check_capability, check_member_access, create_instance, DynInstance, ManagedInstance,
 };
+use crate::userdata::enums::ScriptCapability;
 
 use super::LuaSingleton;
 
@@ -13,11 +13,15 @@ impl LuaUserData for ManagedInstance {
     }
     fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
         methods.add_meta_method("__index", |lua, this, field: String| {
+            check_member_access(lua, &**this, &field, false)?;
             this.lua_get(lua, field)
         });
         methods.add_meta_method(
             "__newindex",
-            |lua, this, (field, val): (String, LuaValue)| this.lua_set(lua, field, val),
+            |lua, this, (field, val): (String, LuaValue)| {
+                check_member_access(lua, &**this, &field, true)?;
+                this.lua_set(lua, field, val)
+            },
         );
         methods.add_meta_method("__tostring", |_, this: &ManagedInstance, ()| {
             let instance_read = this.get_instance_component();
@@ -36,17 +40,16 @@ impl LuaSingleton for ManagedInstance {
         let table = lua.create_table()?;
         table.raw_set(
             "new",
-            lua.create_function(|lua, (class_name,): (String,)| match class_name.as_str() {
-                "Model" => lua_getter!(lua, Model::new()),
-                "Actor" => lua_getter!(lua, Actor::new(get_state(lua).get_vm_mut())),
-                "Script" => lua_getter!(lua, Script::new()),
-                "LocalScript" => lua_getter!(lua, LocalScript::new()),
-                "ModuleScript" => lua_getter!(lua, ModuleScript::new()),
-                _ => Err::<LuaValue, LuaError>(LuaError::RuntimeError(format!(
-                    "invalid class name \"{}\"",
-                    class_name
-                ))),
-            })?,
+            lua.create_function(
+                |lua, (class_name, parent): (String, Option<ManagedInstance>)| {
+                    check_capability(lua, ScriptCapability::CreateInstances, "Instance.new")?;
+                    let instance = create_instance(lua, &class_name)?;
+                    if parent.is_some() {
+                        instance.set_parent(lua, parent)?;
+                    }
+                    Ok(instance)
+                },
+            )?,
         )?;
         lua.globals().raw_set("Instance", table)?;
         Ok(())
@@ -62,13 +62,18 @@ macro_rules! from_lua_clone_impl {
         }
     };
 }
-pub(self) use from_lua_clone_impl;
 
 mod axes;
 mod cframe;
+mod date_time;
 pub mod enums;
 mod events;
+mod faces;
 mod instance;
+mod random;
+mod ray;
+mod region3;
+mod security_capabilities;
 mod vectors;
 
 pub use axes::Axes;
@@ -76,13 +81,26 @@ pub use vectors::{Vector2int16, Vector3int16};
 pub type Vector2 = vectors::Vector2<f64>;
 pub type Vector3 = vectors::Vector3<f64>;
 pub use cframe::CFrame;
+pub use date_time::{DateTime, DateTimeComponents};
 pub use events::{ManagedRBXScriptSignal, RBXScriptConnection, RBXScriptSignal};
+pub use faces::Faces;
+pub use random::Random;
+pub use ray::Ray;
+pub use region3::{Region3, Region3int16};
+pub use security_capabilities::SecurityCapabilities;
 
 use crate::core::ManagedInstance;
 
 pub fn register_userdata_singletons(lua: &mut Lua) -> LuaResult<()> {
     Axes::register_singleton(lua)?;
     CFrame::register_singleton(lua)?;
+    DateTime::register_singleton(lua)?;
+    Faces::register_singleton(lua)?;
+    Random::register_singleton(lua)?;
+    Ray::register_singleton(lua)?;
+    Region3::register_singleton(lua)?;
+    Region3int16::register_singleton(lua)?;
+    SecurityCapabilities::register_singleton(lua)?;
 
     Vector2::register_singleton(lua)?;
     Vector2int16::register_singleton(lua)?;
@@ -0,0 +1,210 @@
+use std::f64::consts::TAU;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use r2g_mlua::prelude::*;
+
+use super::{LuaSingleton, Vector3};
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const PCG_DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+/// The [`Random`](https://create.roblox.com/docs/en-us/reference/engine/datatypes/Random) data type generates pseudorandom numbers and directions, using a PCG32 generator.
+/// The generator follows the PCG reference, the sequences haven't been compared with Roblox's, a seed may not give
+/// the same numbers there.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Random {
+    state: u64,
+    inc: u64,
+}
+
+from_lua_copy_impl!(Random);
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random::with_stream(seed, PCG_DEFAULT_STREAM)
+    }
+    /// Seeds the generator the way `pcg32_srandom_r` of the PCG reference implementation does.
+    fn with_stream(seed: u64, stream: u64) -> Random {
+        let mut rng = Random {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+    pub fn new_from_entropy() -> Random {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_nanos() as u64)
+            .unwrap_or(0);
+        Random::new(nanos)
+    }
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    pub fn next_number(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+    pub fn next_number_range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next_number()
+    }
+    /// Returns a uniformly distributed integer in `[min, max]`. `min` must not be greater than `max`.
+    pub fn next_integer(&mut self, min: i64, max: i64) -> i64 {
+        let range = (max as i128 - min as i128 + 1) as u128;
+        if range > u64::MAX as u128 {
+            return min.wrapping_add(self.next_u64() as i64);
+        }
+        let range = range as u64;
+        // Rejection sampling to avoid modulo bias.
+        let threshold = range.wrapping_neg() % range;
+        loop {
+            let r = self.next_u64();
+            if r >= threshold {
+                return min.wrapping_add((r % range) as i64);
+            }
+        }
+    }
+    pub fn next_unit_vector(&mut self) -> Vector3 {
+        let z = self.next_number() * 2.0 - 1.0;
+        let theta = self.next_number() * TAU;
+        let r = (1.0 - z * z).sqrt();
+        Vector3::new(r * theta.cos(), r * theta.sin(), z)
+    }
+    pub fn shuffle(&mut self, table: &LuaTable) -> LuaResult<()> {
+        let len = table.raw_len();
+        for i in 1..=len {
+            if table.raw_get::<LuaValue>(i)?.is_nil() {
+                return Err(LuaError::BadArgument {
+                    to: Some("Random::Shuffle".into()),
+                    pos: 1,
+                    name: Some("tb".into()),
+                    cause: Arc::new(LuaError::RuntimeError("invalid array".into())),
+                });
+            }
+        }
+        for i in (2..=len).rev() {
+            let j = self.next_integer(1, i as i64) as usize;
+            let a: LuaValue = table.raw_get(i)?;
+            let b: LuaValue = table.raw_get(j)?;
+            table.raw_set(i, b)?;
+            table.raw_set(j, a)?;
+        }
+        Ok(())
+    }
+}
+
+impl LuaUserData for Random {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "Random");
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut(
+            "NextNumber",
+            |_, this, (min, max): (Option<f64>, Option<f64>)| match (min, max) {
+                (None, None) => Ok(this.next_number()),
+                (Some(min), Some(max)) => Ok(this.next_number_range(min, max)),
+                _ => Err(LuaError::BadArgument {
+                    to: Some("Random::NextNumber".into()),
+                    pos: 2,
+                    name: Some("max".into()),
+                    cause: Arc::new(LuaError::RuntimeError("number expected".into())),
+                }),
+            },
+        );
+        methods.add_method_mut("NextInteger", |_, this, (min, max): (f64, f64)| {
+            let (min, max) = (min.floor() as i64, max.floor() as i64);
+            if min > max {
+                return Err(LuaError::BadArgument {
+                    to: Some("Random::NextInteger".into()),
+                    pos: 2,
+                    name: Some("max".into()),
+                    cause: Arc::new(LuaError::RuntimeError("interval is empty".into())),
+                });
+            }
+            Ok(this.next_integer(min, max))
+        });
+        methods.add_method_mut("NextUnitVector", |_, this, ()| Ok(this.next_unit_vector()));
+        methods.add_method_mut("Shuffle", |_, this, table: LuaTable| this.shuffle(&table));
+        methods.add_method("Clone", |_, this, ()| Ok(*this));
+        methods.add_meta_method("__tostring", |_, _, ()| Ok("Random"));
+    }
+}
+
+impl LuaSingleton for Random {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "new",
+            lua.create_function(|_, seed: Option<f64>| {
+                Ok(seed
+                    .map(|x| Random::new(x as i64 as u64))
+                    .unwrap_or_else(Random::new_from_entropy))
+            })?,
+        )?;
+        lua.globals().raw_set("Random", table)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Random;
+
+    #[test]
+    fn matches_pcg32_reference() {
+        // The first outputs of pcg32-demo from the PCG reference implementation, seeded with 42 on stream 54.
+        let mut rng = Random::with_stream(42, 54);
+        let outputs: Vec<u32> = (0..6).map(|_| rng.next_u32()).collect();
+        assert_eq!(
+            outputs,
+            [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]
+        );
+    }
+
+    #[test]
+    fn numbers_come_from_pairs_of_outputs() {
+        // The first two outputs of the reference stream above make up the first 64 bits, high half first.
+        let bits: u64 = 0xa15c02b7_7b47f409;
+        let mut rng = Random::with_stream(42, 54);
+        assert_eq!(rng.next_number(), (bits >> 11) as f64 / (1u64 << 53) as f64);
+        // Far above the rejection threshold, 2^64 % 100 = 16.
+        let mut rng = Random::with_stream(42, 54);
+        assert_eq!(rng.next_integer(1, 100), 1 + (bits % 100) as i64);
+        let mut rng = Random::with_stream(42, 54);
+        assert_eq!(
+            rng.next_number_range(-1.0, 1.0),
+            -1.0 + 2.0 * ((bits >> 11) as f64 / (1u64 << 53) as f64)
+        );
+    }
+
+    #[test]
+    fn clones_continue_the_same_sequence() {
+        let mut rng = Random::new(0);
+        rng.next_u32();
+        let mut clone = rng;
+        let numbers: Vec<f64> = (0..3).map(|_| rng.next_number()).collect();
+        let cloned: Vec<f64> = (0..3).map(|_| clone.next_number()).collect();
+        assert_eq!(numbers, cloned);
+    }
+
+    #[test]
+    fn next_integer_stays_in_range() {
+        let mut rng = Random::new(7);
+        for _ in 0..1000 {
+            let x = rng.next_integer(-3, 3);
+            assert!((-3..=3).contains(&x));
+        }
+        assert_eq!(rng.next_integer(5, 5), 5);
+    }
+}
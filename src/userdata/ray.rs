@@ -0,0 +1,103 @@
+use r2g_mlua::prelude::*;
+
+use super::{LuaSingleton, Vector3};
+
+/// The [`Ray`](https://create.roblox.com/docs/en-us/reference/engine/datatypes/Ray) data type represents a half-line, finite in one direction but infinite in the other. It can be defined by a 3D point, where the line originates from, and a direction vector, which is the direction it goes in.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Ray {
+    pub origin: Vector3,
+    pub direction: Vector3,
+}
+
+from_lua_copy_impl!(Ray);
+
+impl Ray {
+    pub const fn new(origin: Vector3, direction: Vector3) -> Ray {
+        Ray { origin, direction }
+    }
+    pub fn get_unit(&self) -> Ray {
+        Ray {
+            origin: self.origin,
+            direction: self.direction.get_unit(),
+        }
+    }
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        let unit = self.direction.get_unit();
+        let t = (point - self.origin).dot(unit);
+        if t.is_nan() || t <= 0.0 {
+            self.origin
+        } else {
+            self.origin + unit * t
+        }
+    }
+    pub fn distance(&self, point: Vector3) -> f64 {
+        (self.closest_point(point) - point).get_magnitude()
+    }
+}
+
+impl LuaUserData for Ray {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "Ray");
+
+        fields.add_field_method_get("Origin", |_, this| Ok(this.origin));
+        fields.add_field_method_get("Direction", |_, this| Ok(this.direction));
+        fields.add_field_method_get("Unit", |_, this| Ok(this.get_unit()));
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("ClosestPoint", |_, this, point| {
+            Ok(this.closest_point(point))
+        });
+        methods.add_method("Distance", |_, this, point| Ok(this.distance(point)));
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            Ok(format!(
+                "{{{}, {}, {}}}, {{{}, {}, {}}}",
+                this.origin.x,
+                this.origin.y,
+                this.origin.z,
+                this.direction.x,
+                this.direction.y,
+                this.direction.z
+            ))
+        });
+        methods.add_meta_method("__eq", |_, this, other| Ok(*this == other));
+    }
+}
+
+impl LuaSingleton for Ray {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "new",
+            lua.create_function(|_, (origin, direction): (Vector3, Vector3)| {
+                Ok(Ray::new(origin, direction))
+            })?,
+        )?;
+        lua.globals().raw_set("Ray", table)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ray, Vector3};
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test]
+    fn finds_the_closest_point_on_the_half_line() {
+        let ray = Ray::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 0.0, 10.0));
+        // Beside the ray, the direction's length doesn't matter.
+        let point = Vector3::new(4.0, 6.0, 8.0);
+        assert!(ray
+            .closest_point(point)
+            .fuzzy_eq(Vector3::new(1.0, 2.0, 8.0), EPSILON));
+        assert!((ray.distance(point) - 5.0).abs() < EPSILON);
+        // Behind the origin.
+        let behind = Vector3::new(1.0, 6.0, -3.0);
+        assert!(ray.closest_point(behind).fuzzy_eq(ray.origin, EPSILON));
+        assert!((ray.distance(behind) - 52f64.sqrt()).abs() < EPSILON);
+        // A ray without a direction is only its origin.
+        let empty = Ray::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 0.0, 0.0));
+        assert!(empty.closest_point(point).fuzzy_eq(empty.origin, EPSILON));
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use r2g_mlua::prelude::*;
+
+use super::{CFrame, LuaSingleton, Vector3, Vector3int16};
+
+/// The [`Region3`](https://create.roblox.com/docs/en-us/reference/engine/datatypes/Region3) data type describes a volume in 3D space similar to an axis-aligned rectangular prism.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Region3 {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+/// The [`Region3int16`](https://create.roblox.com/docs/en-us/reference/engine/datatypes/Region3int16) data type represents a volume in 3D space similar to an axis-aligned rectangular prism. It uses two [`Vector3int16`](https://docs.rs/roblox-to-godot-project/latest/roblox_to_godot_project/userdata/type.Vector3int16.html) to store the volume's bounds.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Region3int16 {
+    pub min: Vector3int16,
+    pub max: Vector3int16,
+}
+
+from_lua_copy_impl!(Region3);
+from_lua_copy_impl!(Region3int16);
+
+impl Region3 {
+    pub const fn new(min: Vector3, max: Vector3) -> Region3 {
+        Region3 { min, max }
+    }
+    pub fn get_cframe(&self) -> CFrame {
+        CFrame::new_with_position((self.min + self.max) * 0.5)
+    }
+    pub fn get_size(&self) -> Vector3 {
+        self.max - self.min
+    }
+    pub fn expand_to_grid(&self, resolution: f64) -> Region3 {
+        Region3 {
+            min: (self.min / resolution).floor() * resolution,
+            max: (self.max / resolution).ceil() * resolution,
+        }
+    }
+}
+
+impl Region3int16 {
+    pub const fn new(min: Vector3int16, max: Vector3int16) -> Region3int16 {
+        Region3int16 { min, max }
+    }
+}
+
+impl LuaUserData for Region3 {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "Region3");
+
+        fields.add_field_method_get("CFrame", |_, this| Ok(this.get_cframe()));
+        fields.add_field_method_get("Size", |_, this| Ok(this.get_size()));
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("ExpandToGrid", |_, this, resolution: f64| {
+            if resolution <= 0.0 || !resolution.is_finite() {
+                return Err(LuaError::BadArgument {
+                    to: Some("Region3::ExpandToGrid".into()),
+                    pos: 1,
+                    name: Some("resolution".into()),
+                    cause: Arc::new(LuaError::RuntimeError(
+                        "resolution must be a positive number".into(),
+                    )),
+                });
+            }
+            Ok(this.expand_to_grid(resolution))
+        });
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            let cf = this.get_cframe();
+            let size = this.get_size();
+            Ok(format!(
+                "{}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}; {}, {}, {}",
+                cf.pos[0],
+                cf.pos[1],
+                cf.pos[2],
+                cf.rot_matrix[0][0],
+                cf.rot_matrix[0][1],
+                cf.rot_matrix[0][2],
+                cf.rot_matrix[1][0],
+                cf.rot_matrix[1][1],
+                cf.rot_matrix[1][2],
+                cf.rot_matrix[2][0],
+                cf.rot_matrix[2][1],
+                cf.rot_matrix[2][2],
+                size.x,
+                size.y,
+                size.z
+            ))
+        });
+        methods.add_meta_method("__eq", |_, this, other| Ok(*this == other));
+    }
+}
+
+impl LuaSingleton for Region3 {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "new",
+            // The corners can be given in any order, like in Roblox.
+            lua.create_function(|_, (a, b): (Vector3, Vector3)| {
+                Ok(Region3::new(a.min(b), a.max(b)))
+            })?,
+        )?;
+        lua.globals().raw_set("Region3", table)?;
+        Ok(())
+    }
+}
+
+impl LuaUserData for Region3int16 {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "Region3int16");
+
+        fields.add_field_method_get("Min", |_, this| Ok(this.min));
+        fields.add_field_method_get("Max", |_, this| Ok(this.max));
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            Ok(format!(
+                "{}, {}, {}; {}, {}, {}",
+                this.min.x, this.min.y, this.min.z, this.max.x, this.max.y, this.max.z
+            ))
+        });
+        methods.add_meta_method("__eq", |_, this, other| Ok(*this == other));
+    }
+}
+
+impl LuaSingleton for Region3int16 {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "new",
+            lua.create_function(|_, (min, max): (Vector3int16, Vector3int16)| {
+                Ok(Region3int16::new(min, max))
+            })?,
+        )?;
+        lua.globals().raw_set("Region3int16", table)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use r2g_mlua::prelude::*;
+
+    use super::{LuaSingleton, Region3, Vector3};
+
+    #[test]
+    fn new_sorts_the_corners() {
+        let lua = Lua::new();
+        Region3::register_singleton(&lua).unwrap();
+        let new: LuaFunction = lua
+            .globals()
+            .get::<LuaTable>("Region3")
+            .unwrap()
+            .get("new")
+            .unwrap();
+        let region: Region3 = new
+            .call((Vector3::new(4.0, -2.0, 6.0), Vector3::new(-4.0, 2.0, 0.0)))
+            .unwrap();
+        assert_eq!(region.min, Vector3::new(-4.0, -2.0, 0.0));
+        assert_eq!(region.max, Vector3::new(4.0, 2.0, 6.0));
+        assert_eq!(region.get_size(), Vector3::new(8.0, 4.0, 6.0));
+        assert_eq!(region.get_cframe().pos, [0.0, 0.0, 3.0]);
+    }
+}
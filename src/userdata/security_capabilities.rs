@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use r2g_mlua::prelude::*;
+
+use super::{enums::ScriptCapability, LuaSingleton};
+
+/// The [`SecurityCapabilities`](https://create.roblox.com/docs/reference/engine/datatypes/SecurityCapabilities) data type is a set of `ScriptCapability` items, used by sandboxed instances.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct SecurityCapabilities(u32);
+
+from_lua_copy_impl!(SecurityCapabilities);
+
+impl SecurityCapabilities {
+    pub const fn empty() -> SecurityCapabilities {
+        SecurityCapabilities(0)
+    }
+    pub const fn all() -> SecurityCapabilities {
+        SecurityCapabilities(u32::MAX)
+    }
+    pub const fn contains(self, capability: ScriptCapability) -> bool {
+        self.0 & (1 << capability as u32) != 0
+    }
+    pub const fn contains_all(self, other: SecurityCapabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub const fn with(self, capability: ScriptCapability) -> SecurityCapabilities {
+        SecurityCapabilities(self.0 | (1 << capability as u32))
+    }
+    pub const fn without(self, capability: ScriptCapability) -> SecurityCapabilities {
+        SecurityCapabilities(self.0 & !(1 << capability as u32))
+    }
+    pub const fn intersection(self, other: SecurityCapabilities) -> SecurityCapabilities {
+        SecurityCapabilities(self.0 & other.0)
+    }
+    pub fn iter(self) -> impl Iterator<Item = ScriptCapability> {
+        (0..32)
+            .filter(move |x| self.0 & (1 << x) != 0)
+            .filter_map(|x| ScriptCapability::from_value(x))
+    }
+
+    fn from_lua_multi(mult: LuaMultiValue, function: &str) -> LuaResult<SecurityCapabilities> {
+        let mut capabilities = SecurityCapabilities::empty();
+        for (i, value) in mult.into_iter().enumerate() {
+            let userdata = value.as_userdata();
+            if let Some(capability) = userdata.and_then(|x| x.borrow::<ScriptCapability>().ok()) {
+                capabilities = capabilities.with(*capability);
+            } else if let Some(other) =
+                userdata.and_then(|x| x.borrow::<SecurityCapabilities>().ok())
+            {
+                capabilities = SecurityCapabilities(capabilities.0 | other.0);
+            } else {
+                return Err(LuaError::BadArgument {
+                    to: Some(function.into()),
+                    pos: i + 1,
+                    name: None,
+                    cause: Arc::new(LuaError::FromLuaConversionError {
+                        from: value.type_name(),
+                        to: "Enum.ScriptCapability".into(),
+                        message: None,
+                    }),
+                });
+            }
+        }
+        Ok(capabilities)
+    }
+}
+
+impl LuaUserData for SecurityCapabilities {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field("__type", "SecurityCapabilities");
+    }
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("Contains", |_, this, mult: LuaMultiValue| {
+            Ok(this.contains_all(SecurityCapabilities::from_lua_multi(
+                mult,
+                "SecurityCapabilities::Contains",
+            )?))
+        });
+        methods.add_method("Add", |_, this, mult: LuaMultiValue| {
+            let other = SecurityCapabilities::from_lua_multi(mult, "SecurityCapabilities::Add")?;
+            Ok(SecurityCapabilities(this.0 | other.0))
+        });
+        methods.add_method("Remove", |_, this, mult: LuaMultiValue| {
+            let other = SecurityCapabilities::from_lua_multi(mult, "SecurityCapabilities::Remove")?;
+            Ok(SecurityCapabilities(this.0 & !other.0))
+        });
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            let names: Vec<&str> = this.iter().map(|x| x.name()).collect();
+            Ok(names.join(", "))
+        });
+        methods.add_meta_method("__eq", |_, this, other| Ok(*this == other));
+    }
+}
+
+impl LuaSingleton for SecurityCapabilities {
+    fn register_singleton(lua: &Lua) -> LuaResult<()> {
+        let table = lua.create_table()?;
+        table.raw_set(
+            "new",
+            lua.create_function(|_lua, mult: LuaMultiValue| {
+                SecurityCapabilities::from_lua_multi(mult, "SecurityCapabilities::new")
+            })?,
+        )?;
+        lua.globals().raw_set("SecurityCapabilities", table)?;
+        Ok(())
+    }
+}